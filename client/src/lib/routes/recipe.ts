@@ -7,6 +7,7 @@ export type Recipe = {
     poster: MinimalUserDetails,
     id: number,
     thumbnail: string,
+    revision: number,
     recipe: {
         title: string,
         description: string,
@@ -0,0 +1,32 @@
+-- Named collections of recipes, e.g. "Weeknight dinners"
+create table collections
+(
+    id           serial,
+    user_id      integer                                            not null,
+    name         varchar(50)                                        not null,
+    description  varchar(255),
+    public       boolean                  default false             not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (id),
+    constraint collections_users___fk
+        foreign key (user_id) references users
+            on delete cascade
+);
+
+create index collections_user_index
+    on collections (user_id);
+
+create table collection_recipes
+(
+    collection_id integer                                            not null,
+    recipe_id     integer                                            not null,
+    position      integer                                            not null,
+    date_added    timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (collection_id, recipe_id),
+    constraint collection_recipes_collections___fk
+        foreign key (collection_id) references collections
+            on delete cascade,
+    constraint collection_recipes_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade
+);
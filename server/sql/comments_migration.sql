@@ -0,0 +1,30 @@
+-- Comments on recipes with one level of replies
+create table comments
+(
+    id           serial,
+    recipe_id    integer                                            not null,
+    user_id      integer                                            not null,
+    -- Set on replies, which only go one level deep
+    parent_id    integer,
+    body         text                                               not null,
+    deleted      boolean                  default false             not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    date_edited  timestamp with time zone,
+    primary key (id),
+    constraint comments_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade,
+    constraint comments_users___fk
+        foreign key (user_id) references users
+            on delete cascade,
+    constraint comments_parent___fk
+        foreign key (parent_id) references comments
+            on delete cascade
+);
+
+create index comments_recipe_index
+    on comments (recipe_id, id)
+    where parent_id is null;
+
+create index comments_parent_index
+    on comments (parent_id);
@@ -0,0 +1,29 @@
+-- Records of users cooking recipes, with an optional photo
+create table cook_logs
+(
+    id           serial,
+    user_id      integer                                            not null,
+    recipe_id    integer                                            not null,
+    cooked_on    date                     default CURRENT_DATE      not null,
+    notes        text,
+    rating       smallint,
+    photo_path   varchar(255),
+    public       boolean                  default true              not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (id),
+    constraint cook_logs_rating_check
+        check (rating between 1 and 5),
+    constraint cook_logs_users___fk
+        foreign key (user_id) references users
+            on delete cascade,
+    constraint cook_logs_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade
+);
+
+create index cook_logs_user_index
+    on cook_logs (user_id, cooked_on);
+
+create index cook_logs_gallery_index
+    on cook_logs (recipe_id, id)
+    where public and photo_path is not null;
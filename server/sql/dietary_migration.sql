@@ -0,0 +1,16 @@
+-- Adds allergen and diet classification, run `cargo run -- classify-recipes` afterwards to
+-- classify the existing ingredients and recipes
+alter table ingredients
+    add column allergens text[],
+    add column meat boolean default false not null,
+    add column animal_product boolean default false not null;
+
+alter table recipes
+    add column allergens text[] default '{}' not null,
+    add column diets text[] default '{}' not null;
+
+create index recipes_allergens_index
+    on recipes using gin (allergens);
+
+create index recipes_diets_index
+    on recipes using gin (diets);
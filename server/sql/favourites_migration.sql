@@ -0,0 +1,18 @@
+-- Users can save recipes as favourites
+create table favourites
+(
+    user_id      integer                                            not null,
+    recipe_id    integer                                            not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (user_id, recipe_id),
+    constraint favourites_users___fk
+        foreign key (user_id) references users
+            on delete cascade,
+    constraint favourites_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade
+);
+
+-- Recipe listings count favourites per recipe
+create index favourites_recipe_index
+    on favourites (recipe_id);
@@ -0,0 +1,23 @@
+-- Users following each other, for the home feed
+create table follows
+(
+    follower_id  integer                                            not null,
+    followee_id  integer                                            not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (follower_id, followee_id),
+    constraint follows_not_self_check
+        check (follower_id <> followee_id),
+    constraint follows_follower___fk
+        foreign key (follower_id) references users
+            on delete cascade,
+    constraint follows_followee___fk
+        foreign key (followee_id) references users
+            on delete cascade
+);
+
+create index follows_followee_index
+    on follows (followee_id);
+
+-- The feed walks each followed user's recipes newest first
+create index recipes_user_published_index
+    on recipes (user_id, (coalesce(publish_at, date_created)) desc, id desc);
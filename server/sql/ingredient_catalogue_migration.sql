@@ -0,0 +1,40 @@
+-- Adds the ingredient catalogue, run `cargo run -- link-ingredients` afterwards to link the
+-- ingredients of existing recipes
+alter table users
+    add column admin boolean default false not null;
+
+create table ingredients
+(
+    id     serial,
+    name   varchar(100) not null,
+    plural varchar(100),
+    primary key (id),
+    unique (name)
+);
+
+create table ingredient_aliases
+(
+    alias         varchar(100) not null,
+    ingredient_id integer      not null,
+    primary key (alias),
+    constraint ingredient_aliases___fk
+        foreign key (ingredient_id) references ingredients
+            on delete cascade
+);
+
+create table recipe_ingredients
+(
+    recipe_id     integer not null,
+    position      integer not null,
+    ingredient_id integer not null,
+    primary key (recipe_id, position),
+    constraint recipe_ingredients_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade,
+    constraint recipe_ingredients_ingredients___fk
+        foreign key (ingredient_id) references ingredients
+            on delete cascade
+);
+
+create index recipe_ingredients_ingredient_index
+    on recipe_ingredients (ingredient_id);
@@ -0,0 +1,38 @@
+-- Recipes planned onto days, and the secret tokens of each user's calendar feed
+create table meal_plan_entries
+(
+    id           serial,
+    user_id      integer                                            not null,
+    recipe_id    integer                                            not null,
+    planned_on   date                                               not null,
+    slot         text                                               not null,
+    servings     smallint,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (id),
+    constraint meal_plan_entries_slot_check
+        check (slot in ('breakfast', 'lunch', 'dinner')),
+    constraint meal_plan_entries_servings_check
+        check (servings > 0),
+    constraint meal_plan_entries_users___fk
+        foreign key (user_id) references users
+            on delete cascade,
+    constraint meal_plan_entries_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade
+);
+
+create index meal_plan_entries_user_index
+    on meal_plan_entries (user_id, planned_on);
+
+create table meal_plan_feeds
+(
+    user_id      integer                                            not null,
+    token        text                                               not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (user_id),
+    constraint meal_plan_feeds_token_key
+        unique (token),
+    constraint meal_plan_feeds_users___fk
+        foreign key (user_id) references users
+            on delete cascade
+);
@@ -4,6 +4,7 @@ create table users
     username varchar(16)  not null,
     email    varchar(100) not null,
     password varchar(255) not null,
+    admin    boolean      default false not null,
     primary key (uid),
     unique (username),
     unique (email)
@@ -12,13 +13,70 @@ create table users
 create table recipes
 (
     id               serial,
-    recipe_file_path varchar(255)                                       not null,
+    recipe           jsonb                                              not null,
     user_id          integer                                            not null,
     date_created     timestamp with time zone default CURRENT_TIMESTAMP not null,
+    search           tsvector generated always as (
+        setweight(to_tsvector('english', coalesce(recipe ->> 'title', '')), 'A') ||
+        setweight(jsonb_to_tsvector('english', jsonb_path_query_array(recipe, '$.ingredients[*].ingredient'), '["string"]'), 'B') ||
+        setweight(to_tsvector('english', coalesce(recipe ->> 'description', '')), 'C') ||
+        setweight(jsonb_to_tsvector('english', jsonb_path_query_array(recipe, '$.steps[*].step_details'), '["string"]'), 'D')
+        ) stored,
+    -- Worked out from the ingredients, see Recipe::classify
+    allergens        text[]                   default '{}'              not null,
+    diets            text[]                   default '{}'              not null,
+    revision         integer                  default 1                 not null,
+    visibility       text                     default 'public'          not null,
+    -- When a scheduled recipe becomes public, see Recipe::set_visibility
+    publish_at       timestamp with time zone,
+    -- When the recipe went or goes public, orders the feed
+    published_at     timestamp with time zone,
+    -- The recipe this one was forked from and who wrote it
+    parent_id        integer,
+    parent_user_id   integer,
     primary key (id),
     constraint fk_user
         foreign key (user_id) references users
-            on delete cascade
+            on delete cascade,
+    constraint recipes_parent___fk
+        foreign key (parent_id) references recipes
+            on delete set null,
+    constraint recipes_parent_user___fk
+        foreign key (parent_user_id) references users
+            on delete set null,
+    constraint recipes_visibility_check
+        check (visibility in ('draft', 'private', 'unlisted', 'public'))
+);
+
+create index recipes_parent_index
+    on recipes (parent_id);
+
+create index recipes_visibility_index
+    on recipes (visibility, publish_at);
+
+create index recipes_search_index
+    on recipes using gin (search);
+
+create index recipes_allergens_index
+    on recipes using gin (allergens);
+
+create index recipes_diets_index
+    on recipes using gin (diets);
+
+create table recipe_revisions
+(
+    recipe_id    integer                                            not null,
+    revision     integer                                            not null,
+    recipe       jsonb                                              not null,
+    user_id      integer,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (recipe_id, revision),
+    constraint recipe_revisions_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade,
+    constraint recipe_revisions_users___fk
+        foreign key (user_id) references users
+            on delete set null
 );
 
 create table user_details
@@ -56,3 +114,382 @@ create table profile_pictures
             on delete cascade
 );
 
+create table nutrients
+(
+    id        integer      not null,
+    name      varchar(255) not null,
+    unit_name varchar(20)  not null,
+    primary key (id)
+);
+
+create table foods
+(
+    fdc_id      integer not null,
+    description text    not null,
+    primary key (fdc_id)
+);
+
+create index foods_description_index
+    on foods (lower(description) text_pattern_ops);
+
+create table food_nutrients
+(
+    fdc_id      integer          not null,
+    nutrient_id integer          not null,
+    -- Per 100g of the food
+    amount      double precision not null,
+    primary key (fdc_id, nutrient_id),
+    constraint food_nutrients_foods___fk
+        foreign key (fdc_id) references foods
+            on delete cascade,
+    constraint food_nutrients_nutrients___fk
+        foreign key (nutrient_id) references nutrients
+            on delete cascade
+);
+
+create table food_portions
+(
+    fdc_id       integer          not null,
+    measure_unit text             not null,
+    amount       double precision not null,
+    gram_weight  double precision not null,
+    constraint food_portions_foods___fk
+        foreign key (fdc_id) references foods
+            on delete cascade
+);
+
+create index food_portions_fdc_index
+    on food_portions (fdc_id);
+
+create table ingredients
+(
+    id     serial,
+    name   varchar(100) not null,
+    plural varchar(100),
+    -- The FoodData Central food the ingredient's nutrition comes from
+    fdc_id integer,
+    -- What one piece of the ingredient weighs, for when the food has no portion for it
+    grams_per_piece double precision,
+    -- Null until the ingredient has been classified
+    allergens text[],
+    meat boolean default false not null,
+    animal_product boolean default false not null,
+    -- Where it is found in a shop, guessed when null
+    aisle text,
+    primary key (id),
+    unique (name),
+    constraint ingredients_foods___fk
+        foreign key (fdc_id) references foods
+            on delete set null,
+    constraint ingredients_aisle_check
+        check (aisle in ('produce', 'bakery', 'meat_and_fish', 'dairy_and_eggs', 'frozen', 'pantry', 'spices', 'drinks', 'other'))
+);
+
+create table ingredient_aliases
+(
+    alias         varchar(100) not null,
+    ingredient_id integer      not null,
+    primary key (alias),
+    constraint ingredient_aliases___fk
+        foreign key (ingredient_id) references ingredients
+            on delete cascade
+);
+
+create table recipe_ingredients
+(
+    recipe_id     integer not null,
+    position      integer not null,
+    ingredient_id integer not null,
+    primary key (recipe_id, position),
+    constraint recipe_ingredients_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade,
+    constraint recipe_ingredients_ingredients___fk
+        foreign key (ingredient_id) references ingredients
+            on delete cascade
+);
+
+create index recipe_ingredients_ingredient_index
+    on recipe_ingredients (ingredient_id);
+
+create table collections
+(
+    id           serial,
+    user_id      integer                                            not null,
+    name         varchar(50)                                        not null,
+    description  varchar(255),
+    public       boolean                  default false             not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (id),
+    constraint collections_users___fk
+        foreign key (user_id) references users
+            on delete cascade
+);
+
+create index collections_user_index
+    on collections (user_id);
+
+create table collection_recipes
+(
+    collection_id integer                                            not null,
+    recipe_id     integer                                            not null,
+    position      integer                                            not null,
+    date_added    timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (collection_id, recipe_id),
+    constraint collection_recipes_collections___fk
+        foreign key (collection_id) references collections
+            on delete cascade,
+    constraint collection_recipes_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade
+);
+
+create table favourites
+(
+    user_id      integer                                            not null,
+    recipe_id    integer                                            not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (user_id, recipe_id),
+    constraint favourites_users___fk
+        foreign key (user_id) references users
+            on delete cascade,
+    constraint favourites_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade
+);
+
+-- Recipe listings count favourites per recipe
+create index favourites_recipe_index
+    on favourites (recipe_id);
+
+create table reviews
+(
+    user_id      integer                                            not null,
+    recipe_id    integer                                            not null,
+    rating       smallint                                           not null,
+    review       text,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    date_updated timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (user_id, recipe_id),
+    constraint reviews_rating_check
+        check (rating between 1 and 5),
+    constraint reviews_users___fk
+        foreign key (user_id) references users
+            on delete cascade,
+    constraint reviews_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade
+);
+
+-- Recipe listings average the ratings per recipe
+create index reviews_recipe_index
+    on reviews (recipe_id, date_updated);
+
+create table comments
+(
+    id           serial,
+    recipe_id    integer                                            not null,
+    user_id      integer                                            not null,
+    -- Set on replies, which only go one level deep
+    parent_id    integer,
+    body         text                                               not null,
+    deleted      boolean                  default false             not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    date_edited  timestamp with time zone,
+    primary key (id),
+    constraint comments_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade,
+    constraint comments_users___fk
+        foreign key (user_id) references users
+            on delete cascade,
+    constraint comments_parent___fk
+        foreign key (parent_id) references comments
+            on delete cascade
+);
+
+create index comments_recipe_index
+    on comments (recipe_id, id)
+    where parent_id is null;
+
+create index comments_parent_index
+    on comments (parent_id);
+
+create table cook_logs
+(
+    id           serial,
+    user_id      integer                                            not null,
+    recipe_id    integer                                            not null,
+    cooked_on    date                     default CURRENT_DATE      not null,
+    notes        text,
+    rating       smallint,
+    photo_path   varchar(255),
+    public       boolean                  default true              not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (id),
+    constraint cook_logs_rating_check
+        check (rating between 1 and 5),
+    constraint cook_logs_users___fk
+        foreign key (user_id) references users
+            on delete cascade,
+    constraint cook_logs_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade
+);
+
+create index cook_logs_user_index
+    on cook_logs (user_id, cooked_on);
+
+create index cook_logs_gallery_index
+    on cook_logs (recipe_id, id)
+    where public and photo_path is not null;
+
+create table follows
+(
+    follower_id  integer                                            not null,
+    followee_id  integer                                            not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (follower_id, followee_id),
+    constraint follows_not_self_check
+        check (follower_id <> followee_id),
+    constraint follows_follower___fk
+        foreign key (follower_id) references users
+            on delete cascade,
+    constraint follows_followee___fk
+        foreign key (followee_id) references users
+            on delete cascade
+);
+
+create index follows_followee_index
+    on follows (followee_id);
+
+-- The feed walks each followed user's recipes newest first
+create index recipes_user_published_index
+    on recipes (user_id, published_at desc, id desc);
+
+create table notifications
+(
+    id           serial,
+    user_id      integer                                            not null,
+    kind         text                                               not null,
+    actor_id     integer,
+    recipe_id    integer,
+    comment_id   integer,
+    read         boolean                  default false             not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (id),
+    constraint notifications_kind_check
+        check (kind in ('comment', 'review', 'favourite', 'fork', 'follow')),
+    constraint notifications_users___fk
+        foreign key (user_id) references users
+            on delete cascade,
+    constraint notifications_actors___fk
+        foreign key (actor_id) references users
+            on delete set null,
+    constraint notifications_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade,
+    constraint notifications_comments___fk
+        foreign key (comment_id) references comments
+            on delete cascade
+);
+
+create index notifications_user_index
+    on notifications (user_id, id desc);
+
+create index notifications_unread_index
+    on notifications (user_id)
+    where not read;
+
+create table notification_opt_outs
+(
+    user_id integer not null,
+    kind    text    not null,
+    primary key (user_id, kind),
+    constraint notification_opt_outs_users___fk
+        foreign key (user_id) references users
+            on delete cascade
+);
+
+create table meal_plan_entries
+(
+    id           serial,
+    user_id      integer                                            not null,
+    recipe_id    integer                                            not null,
+    planned_on   date                                               not null,
+    slot         text                                               not null,
+    servings     smallint,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (id),
+    constraint meal_plan_entries_slot_check
+        check (slot in ('breakfast', 'lunch', 'dinner')),
+    constraint meal_plan_entries_servings_check
+        check (servings > 0),
+    constraint meal_plan_entries_users___fk
+        foreign key (user_id) references users
+            on delete cascade,
+    constraint meal_plan_entries_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade
+);
+
+create index meal_plan_entries_user_index
+    on meal_plan_entries (user_id, planned_on);
+
+create table meal_plan_feeds
+(
+    user_id      integer                                            not null,
+    token        text                                               not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (user_id),
+    constraint meal_plan_feeds_token_key
+        unique (token),
+    constraint meal_plan_feeds_users___fk
+        foreign key (user_id) references users
+            on delete cascade
+);
+
+create table shopping_lists
+(
+    id           serial,
+    user_id      integer                                            not null,
+    name         varchar(50)                                        not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (id),
+    constraint shopping_lists_users___fk
+        foreign key (user_id) references users
+            on delete cascade
+);
+
+create index shopping_lists_user_index
+    on shopping_lists (user_id);
+
+create table shopping_list_items
+(
+    id            serial,
+    list_id       integer                     not null,
+    ingredient_id integer,
+    name          varchar(100)                not null,
+    -- The measurement and amount to buy, null for items added without one
+    quantity      jsonb,
+    aisle         text    default 'other'     not null,
+    checked       boolean default false       not null,
+    manual        boolean default false       not null,
+    primary key (id),
+    constraint shopping_list_items_shopping_lists___fk
+        foreign key (list_id) references shopping_lists
+            on delete cascade,
+    constraint shopping_list_items_ingredients___fk
+        foreign key (ingredient_id) references ingredients
+            on delete set null
+);
+
+create index shopping_list_items_list_index
+    on shopping_list_items (list_id);
+
+create view recipe_stats as
+select r.id                                                                    as recipe_id,
+       (select count(*) from favourites f where f.recipe_id = r.id)            as favourites,
+       (select avg(rv.rating)::float8 from reviews rv where rv.recipe_id = r.id) as rating,
+       (select count(*) from reviews rv where rv.recipe_id = r.id)             as ratings
+from recipes r;
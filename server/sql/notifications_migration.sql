@@ -0,0 +1,44 @@
+-- In-app notifications, and the kinds of them each user has turned off
+create table notifications
+(
+    id           serial,
+    user_id      integer                                            not null,
+    kind         text                                               not null,
+    actor_id     integer,
+    recipe_id    integer,
+    comment_id   integer,
+    read         boolean                  default false             not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (id),
+    constraint notifications_kind_check
+        check (kind in ('comment', 'review', 'favourite', 'fork', 'follow')),
+    constraint notifications_users___fk
+        foreign key (user_id) references users
+            on delete cascade,
+    constraint notifications_actors___fk
+        foreign key (actor_id) references users
+            on delete set null,
+    constraint notifications_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade,
+    constraint notifications_comments___fk
+        foreign key (comment_id) references comments
+            on delete cascade
+);
+
+create index notifications_user_index
+    on notifications (user_id, id desc);
+
+create index notifications_unread_index
+    on notifications (user_id)
+    where not read;
+
+create table notification_opt_outs
+(
+    user_id integer not null,
+    kind    text    not null,
+    primary key (user_id, kind),
+    constraint notification_opt_outs_users___fk
+        foreign key (user_id) references users
+            on delete cascade
+);
@@ -0,0 +1,55 @@
+-- Adds the nutrient database, run `cargo run -- import-nutrients <dir>` afterwards with the
+-- directory of an unzipped FoodData Central csv export
+create table nutrients
+(
+    id        integer      not null,
+    name      varchar(255) not null,
+    unit_name varchar(20)  not null,
+    primary key (id)
+);
+
+create table foods
+(
+    fdc_id      integer not null,
+    description text    not null,
+    primary key (fdc_id)
+);
+
+create index foods_description_index
+    on foods (lower(description) text_pattern_ops);
+
+create table food_nutrients
+(
+    fdc_id      integer          not null,
+    nutrient_id integer          not null,
+    -- Per 100g of the food
+    amount      double precision not null,
+    primary key (fdc_id, nutrient_id),
+    constraint food_nutrients_foods___fk
+        foreign key (fdc_id) references foods
+            on delete cascade,
+    constraint food_nutrients_nutrients___fk
+        foreign key (nutrient_id) references nutrients
+            on delete cascade
+);
+
+create table food_portions
+(
+    fdc_id       integer          not null,
+    measure_unit text             not null,
+    amount       double precision not null,
+    gram_weight  double precision not null,
+    constraint food_portions_foods___fk
+        foreign key (fdc_id) references foods
+            on delete cascade
+);
+
+create index food_portions_fdc_index
+    on food_portions (fdc_id);
+
+alter table ingredients
+    add column fdc_id integer,
+    add column grams_per_piece double precision,
+    add constraint ingredients_foods___fk
+        foreign key (fdc_id) references foods
+            on delete set null;
@@ -0,0 +1,13 @@
+-- Forked recipes point back at the recipe they were adapted from
+alter table recipes
+    add column parent_id integer,
+    add column parent_user_id integer,
+    add constraint recipes_parent___fk
+        foreign key (parent_id) references recipes
+            on delete set null,
+    add constraint recipes_parent_user___fk
+        foreign key (parent_user_id) references users
+            on delete set null;
+
+create index recipes_parent_index
+    on recipes (parent_id);
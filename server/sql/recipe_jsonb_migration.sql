@@ -0,0 +1,16 @@
+-- Moves recipe bodies out of ./recipes/ and into recipes.recipe
+--
+-- 1. Run this step, then `cargo run -- import-recipes` to import every recipe file
+alter table recipes
+    add column recipe jsonb;
+
+-- Recipes saved from here on have no file
+alter table recipes
+    alter column recipe_file_path drop not null;
+
+-- 2. Only once the import has reported success
+alter table recipes
+    alter column recipe set not null;
+
+alter table recipes
+    drop column recipe_file_path;
@@ -0,0 +1,14 @@
+-- When a recipe went public, or is scheduled to, so the feed doesn't bury drafts published later
+alter table recipes
+    add column published_at timestamp with time zone;
+
+update recipes
+set published_at = coalesce(publish_at, date_created)
+where visibility = 'public'
+   or publish_at is not null;
+
+drop index recipes_user_published_index;
+
+-- The feed walks each followed user's recipes newest first
+create index recipes_user_published_index
+    on recipes (user_id, published_at desc, id desc);
@@ -0,0 +1,6 @@
+-- Tracks the current revision on the recipe itself, so edits can check it in the same update
+alter table recipes
+    add column revision integer default 1 not null;
+
+update recipes r
+set revision = coalesce((select max(revision) from recipe_revisions where recipe_id = r.id), 1);
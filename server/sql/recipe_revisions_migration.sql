@@ -0,0 +1,21 @@
+-- Adds recipe revision history, every existing recipe starts out with its current version as
+-- revision 1
+create table recipe_revisions
+(
+    recipe_id    integer                                            not null,
+    revision     integer                                            not null,
+    recipe       jsonb                                              not null,
+    user_id      integer,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (recipe_id, revision),
+    constraint recipe_revisions_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade,
+    constraint recipe_revisions_users___fk
+        foreign key (user_id) references users
+            on delete set null
+);
+
+insert into recipe_revisions (recipe_id, revision, recipe, user_id, date_created)
+select id, 1, recipe, user_id, date_created
+from recipes;
@@ -0,0 +1,11 @@
+-- Adds the full text search column used by /v1/recipes/search
+alter table recipes
+    add column search tsvector generated always as (
+        setweight(to_tsvector('english', coalesce(recipe ->> 'title', '')), 'A') ||
+        setweight(jsonb_to_tsvector('english', jsonb_path_query_array(recipe, '$.ingredients[*].ingredient'), '["string"]'), 'B') ||
+        setweight(to_tsvector('english', coalesce(recipe ->> 'description', '')), 'C') ||
+        setweight(jsonb_to_tsvector('english', jsonb_path_query_array(recipe, '$.steps[*].step_details'), '["string"]'), 'D')
+        ) stored;
+
+create index recipes_search_index
+    on recipes using gin (search);
@@ -0,0 +1,8 @@
+-- The favourite and rating counts shown on every recipe listing, in one place. Whether the
+-- viewer favourited a recipe depends on who is asking so it stays in the queries
+create view recipe_stats as
+select r.id                                                                    as recipe_id,
+       (select count(*) from favourites f where f.recipe_id = r.id)            as favourites,
+       (select avg(rv.rating)::float8 from reviews rv where rv.recipe_id = r.id) as rating,
+       (select count(*) from reviews rv where rv.recipe_id = r.id)             as ratings
+from recipes r;
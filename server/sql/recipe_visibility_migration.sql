@@ -0,0 +1,9 @@
+-- Recipes can be kept as drafts, private or unlisted, existing ones stay public
+alter table recipes
+    add column visibility text default 'public' not null,
+    add column publish_at timestamp with time zone,
+    add constraint recipes_visibility_check
+        check (visibility in ('draft', 'private', 'unlisted', 'public'));
+
+create index recipes_visibility_index
+    on recipes (visibility, publish_at);
@@ -0,0 +1,23 @@
+-- One star rating and optional written review per user per recipe
+create table reviews
+(
+    user_id      integer                                            not null,
+    recipe_id    integer                                            not null,
+    rating       smallint                                           not null,
+    review       text,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    date_updated timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (user_id, recipe_id),
+    constraint reviews_rating_check
+        check (rating between 1 and 5),
+    constraint reviews_users___fk
+        foreign key (user_id) references users
+            on delete cascade,
+    constraint reviews_recipes___fk
+        foreign key (recipe_id) references recipes
+            on delete cascade
+);
+
+-- Recipe listings average the ratings per recipe
+create index reviews_recipe_index
+    on reviews (recipe_id, date_updated);
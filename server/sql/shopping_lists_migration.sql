@@ -0,0 +1,45 @@
+-- Shopping lists worked out from recipes and meal plans, and where ingredients are found in a shop
+alter table ingredients
+    add aisle text;
+
+alter table ingredients
+    add constraint ingredients_aisle_check
+        check (aisle in ('produce', 'bakery', 'meat_and_fish', 'dairy_and_eggs', 'frozen', 'pantry', 'spices', 'drinks', 'other'));
+
+create table shopping_lists
+(
+    id           serial,
+    user_id      integer                                            not null,
+    name         varchar(50)                                        not null,
+    date_created timestamp with time zone default CURRENT_TIMESTAMP not null,
+    primary key (id),
+    constraint shopping_lists_users___fk
+        foreign key (user_id) references users
+            on delete cascade
+);
+
+create index shopping_lists_user_index
+    on shopping_lists (user_id);
+
+create table shopping_list_items
+(
+    id            serial,
+    list_id       integer                     not null,
+    ingredient_id integer,
+    name          varchar(100)                not null,
+    -- The measurement and amount to buy, null for items added without one
+    quantity      jsonb,
+    aisle         text    default 'other'     not null,
+    checked       boolean default false       not null,
+    manual        boolean default false       not null,
+    primary key (id),
+    constraint shopping_list_items_shopping_lists___fk
+        foreign key (list_id) references shopping_lists
+            on delete cascade,
+    constraint shopping_list_items_ingredients___fk
+        foreign key (ingredient_id) references ingredients
+            on delete set null
+);
+
+create index shopping_list_items_list_index
+    on shopping_list_items (list_id);
@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Context};
+use sqlx::{types::Json, Pool, Postgres, Row};
+
+use crate::{
+    recipe_io::RecipeFileJson,
+    storage::{constants::RECIPE_DIR, key, Storage},
+};
+
+use super::models::{ingredient::Ingredient, recipe::Recipe};
+
+pub struct RecipeFileImport {
+    pub imported: usize,
+    // Files in the recipe directory that no recipe row points at
+    pub unreferenced: Vec<String>,
+}
+
+// One shot import of the loose recipe files into the recipes.recipe column, run with
+// `server import-recipes` between the two steps of sql/recipe_jsonb_migration.sql.
+// Everything happens in a single transaction, so if any file fails to parse nothing is imported
+pub async fn import_recipe_files(
+    pool: &Pool<Postgres>,
+    storage: &dyn Storage,
+) -> anyhow::Result<RecipeFileImport> {
+    let rows = sqlx::query(
+        r#"SELECT id, recipe_file_path, recipe IS NULL AS pending FROM recipes
+        WHERE recipe_file_path IS NOT NULL ORDER BY id"#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to get recipe file paths, has the recipe column been added?")?;
+
+    let mut tx = pool.begin().await?;
+    let mut referenced = HashSet::new();
+    let mut failed = Vec::new();
+    let mut imported = 0;
+
+    for row in rows.iter() {
+        let id: i32 = row.get("id");
+        let file_name: String = row.get("recipe_file_path");
+        let pending: bool = row.get("pending");
+        referenced.insert(file_name.clone());
+
+        if !pending {
+            continue;
+        }
+
+        let recipe = match read_recipe_file(storage, &file_name).await {
+            Ok(recipe) => recipe,
+            Err(e) => {
+                failed.push(format!("{} (recipe {}): {}", file_name, id, e));
+                continue;
+            }
+        };
+
+        sqlx::query(r#"UPDATE recipes SET recipe = $1 WHERE id = $2"#)
+            .bind(Json(&recipe))
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .context(format!("Failed to import recipe with id: {}", id))?;
+
+        // Read it back to make sure what was stored is what was in the file
+        let stored: Json<serde_json::Value> =
+            sqlx::query(r#"SELECT recipe FROM recipes WHERE id = $1"#)
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?
+                .try_get("recipe")?;
+
+        if stored.0 != serde_json::to_value(&recipe)? {
+            failed.push(format!(
+                "{} (recipe {}): stored recipe does not match the file",
+                file_name, id
+            ));
+            continue;
+        }
+
+        imported += 1;
+    }
+
+    if !failed.is_empty() {
+        tx.rollback().await?;
+
+        return Err(anyhow!(
+            "Failed to import {} recipe file(s), nothing was imported:\n{}",
+            failed.len(),
+            failed.join("\n")
+        ));
+    }
+
+    let remaining: i64 =
+        sqlx::query(r#"SELECT COUNT(*) AS remaining FROM recipes WHERE recipe IS NULL"#)
+            .fetch_one(&mut *tx)
+            .await?
+            .get("remaining");
+
+    if remaining != 0 {
+        tx.rollback().await?;
+
+        return Err(anyhow!(
+            "{} recipe(s) still have no recipe body, nothing was imported",
+            remaining
+        ));
+    }
+
+    tx.commit().await?;
+
+    let unreferenced = storage
+        .list(RECIPE_DIR)
+        .await?
+        .into_iter()
+        .filter(|file_name| !referenced.contains(file_name))
+        .collect();
+
+    Ok(RecipeFileImport {
+        imported,
+        unreferenced,
+    })
+}
+
+async fn read_recipe_file(
+    storage: &dyn Storage,
+    file_name: &str,
+) -> anyhow::Result<RecipeFileJson> {
+    let data = storage
+        .get(&key(RECIPE_DIR, file_name))
+        .await?
+        .ok_or(anyhow!("File does not exist"))?;
+    let recipe = serde_json::from_slice::<RecipeFileJson>(&data)?;
+
+    Ok(recipe)
+}
+
+// Links the ingredients of every existing recipe to the ingredient catalogue, run with
+// `server link-ingredients` after sql/ingredient_catalogue_migration.sql
+pub async fn link_recipe_ingredients(pool: &Pool<Postgres>) -> anyhow::Result<usize> {
+    let rows = sqlx::query(r#"SELECT id, recipe FROM recipes ORDER BY id"#)
+        .fetch_all(pool)
+        .await?;
+
+    let mut tx = pool.begin().await?;
+    for row in rows.iter() {
+        let id: i32 = row.get("id");
+        let recipe: Json<RecipeFileJson> = row.try_get("recipe")?;
+
+        Ingredient::link_recipe(&mut tx, id, &recipe.ingredients).await?;
+    }
+    Ingredient::match_foods(&mut tx).await?;
+    tx.commit().await?;
+
+    Ok(rows.len())
+}
+
+// Guesses the profile of ingredients from before allergens were tracked and stores the
+// allergens and diets of every recipe, run with `server classify-recipes` after
+// sql/dietary_migration.sql. Returns how many ingredients and recipes were classified
+pub async fn classify_recipes(pool: &Pool<Postgres>) -> anyhow::Result<(u64, usize)> {
+    let mut tx = pool.begin().await?;
+    let ingredients = Ingredient::classify_unclassified(&mut tx).await?;
+    let recipes = Recipe::reclassify(&mut tx, None).await?;
+    tx.commit().await?;
+
+    Ok((ingredients, recipes))
+}
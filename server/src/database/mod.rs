@@ -1 +1,3 @@
+pub mod migrations;
+pub mod nutrient_import;
 pub mod models;
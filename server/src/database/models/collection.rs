@@ -0,0 +1,287 @@
+use anyhow::Context;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{prelude::FromRow, Pool, Postgres, Row};
+
+use super::recipe::RecipeWithPoster;
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct Collection {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub public: bool,
+    pub date_created: chrono::DateTime<Utc>,
+    // How many recipes in the collection the viewer can see
+    pub recipes: i64,
+}
+
+impl Collection {
+    pub async fn insert(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        name: &str,
+        description: &Option<String>,
+        public: bool,
+    ) -> Result<i32, anyhow::Error> {
+        let rec = sqlx::query(
+            r#"INSERT INTO collections (user_id, name, description, public)
+            VALUES ($1, $2, $3, $4) RETURNING id"#,
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(description)
+        .bind(public)
+        .fetch_one(pool)
+        .await
+        .context("Failed to create collection")?;
+
+        Ok(rec.get("id"))
+    }
+
+    pub async fn get(
+        pool: &Pool<Postgres>,
+        collection_id: i32,
+        viewer: Option<i32>,
+    ) -> Result<Option<Collection>, anyhow::Error> {
+        let collection = sqlx::query_as::<_, Collection>(
+            r#"
+            SELECT c.*, (
+                SELECT COUNT(*) FROM collection_recipes cr
+                INNER JOIN recipes r
+                    ON r.id = cr.recipe_id
+                        WHERE cr.collection_id = c.id
+                        AND (r.user_id = $2 OR r.visibility IN ('public', 'unlisted') OR r.publish_at <= CURRENT_TIMESTAMP)
+            ) AS recipes
+            FROM collections c
+                WHERE c.id = $1"#,
+        )
+        .bind(collection_id)
+        .bind(viewer)
+        .fetch_optional(pool)
+        .await
+        .context(format!(
+            "Failed to get collection with id: {}",
+            collection_id
+        ))?;
+
+        Ok(collection)
+    }
+
+    // Newest first, private collections are only included if asked for
+    pub async fn get_by_user(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        include_private: bool,
+        viewer: Option<i32>,
+    ) -> Result<Vec<Collection>, anyhow::Error> {
+        let collections = sqlx::query_as::<_, Collection>(
+            r#"
+            SELECT c.*, (
+                SELECT COUNT(*) FROM collection_recipes cr
+                INNER JOIN recipes r
+                    ON r.id = cr.recipe_id
+                        WHERE cr.collection_id = c.id
+                        AND (r.user_id = $3 OR r.visibility IN ('public', 'unlisted') OR r.publish_at <= CURRENT_TIMESTAMP)
+            ) AS recipes
+            FROM collections c
+                WHERE c.user_id = $1 AND (c.public OR $2)
+                ORDER BY c.id DESC"#,
+        )
+        .bind(user_id)
+        .bind(include_private)
+        .bind(viewer)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get collections of user with id: {}",
+            user_id
+        ))?;
+
+        Ok(collections)
+    }
+
+    pub async fn update(
+        pool: &Pool<Postgres>,
+        collection_id: i32,
+        name: &str,
+        description: &Option<String>,
+        public: bool,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"UPDATE collections SET name = $2, description = $3, public = $4 WHERE id = $1"#,
+        )
+        .bind(collection_id)
+        .bind(name)
+        .bind(description)
+        .bind(public)
+        .execute(pool)
+        .await
+        .context(format!(
+            "Failed to update collection with id: {}",
+            collection_id
+        ))?;
+
+        Ok(())
+    }
+
+    pub async fn delete(pool: &Pool<Postgres>, collection_id: i32) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"DELETE FROM collections WHERE id = $1"#)
+            .bind(collection_id)
+            .execute(pool)
+            .await
+            .context(format!(
+                "Failed to delete collection with id: {}",
+                collection_id
+            ))?;
+
+        Ok(())
+    }
+
+    // New recipes go to the end of the collection, adding one that is already in it does nothing
+    pub async fn add_recipe(
+        pool: &Pool<Postgres>,
+        collection_id: i32,
+        recipe_id: i32,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO collection_recipes (collection_id, recipe_id, position)
+            SELECT $1, $2, coalesce(max(position) + 1, 0) FROM collection_recipes WHERE collection_id = $1
+                ON CONFLICT (collection_id, recipe_id) DO NOTHING"#,
+        )
+        .bind(collection_id)
+        .bind(recipe_id)
+        .execute(pool)
+        .await
+        .context(format!(
+            "Failed to add recipe with id: {} to collection with id: {}",
+            recipe_id, collection_id
+        ))?;
+
+        Ok(())
+    }
+
+    // Returns false if the recipe wasn't in the collection
+    pub async fn remove_recipe(
+        pool: &Pool<Postgres>,
+        collection_id: i32,
+        recipe_id: i32,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(
+            r#"DELETE FROM collection_recipes WHERE collection_id = $1 AND recipe_id = $2"#,
+        )
+        .bind(collection_id)
+        .bind(recipe_id)
+        .execute(pool)
+        .await
+        .context(format!(
+            "Failed to remove recipe with id: {} from collection with id: {}",
+            recipe_id, collection_id
+        ))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // The recipe ids must be exactly the recipes in the collection the owner can see, in their
+    // new order, returns false without changing anything if they aren't. Recipes hidden from
+    // the owner keep their places and the rest are ordered around them
+    pub async fn reorder(
+        pool: &Pool<Postgres>,
+        collection_id: i32,
+        owner_id: i32,
+        recipe_ids: &[i32],
+    ) -> Result<bool, anyhow::Error> {
+        let mut tx = pool.begin().await?;
+        let rows = sqlx::query(
+            r#"
+            SELECT cr.recipe_id, cr.position,
+                coalesce(r.user_id = $2 OR r.visibility IN ('public', 'unlisted') OR r.publish_at <= CURRENT_TIMESTAMP, false) AS viewable
+            FROM collection_recipes cr
+            INNER JOIN recipes r
+                ON r.id = cr.recipe_id
+                    WHERE cr.collection_id = $1
+                    ORDER BY cr.position, r.id
+                    FOR UPDATE OF cr"#,
+        )
+        .bind(collection_id)
+        .bind(owner_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let viewable: Vec<_> = rows
+            .iter()
+            .filter(|row| row.get::<bool, _>("viewable"))
+            .collect();
+        // The places the viewable recipes are in, which they are shuffled between
+        let positions: Vec<i32> = viewable.iter().map(|row| row.get("position")).collect();
+
+        let mut current: Vec<i32> = viewable.iter().map(|row| row.get("recipe_id")).collect();
+        let mut given = recipe_ids.to_vec();
+        current.sort_unstable();
+        given.sort_unstable();
+        if current != given {
+            tx.rollback().await?;
+
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE collection_recipes cr SET position = o.position
+            FROM unnest($2::integer[], $3::integer[]) AS o(recipe_id, position)
+                WHERE cr.collection_id = $1 AND cr.recipe_id = o.recipe_id"#,
+        )
+        .bind(collection_id)
+        .bind(recipe_ids)
+        .bind(&positions)
+        .execute(&mut *tx)
+        .await
+        .context(format!(
+            "Failed to reorder collection with id: {}",
+            collection_id
+        ))?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    // In collection order, leaving out recipes the viewer isn't allowed to see
+    pub async fn get_recipes(
+        pool: &Pool<Postgres>,
+        collection_id: i32,
+        viewer: Option<i32>,
+    ) -> Result<Vec<RecipeWithPoster>, anyhow::Error> {
+        let recipes = sqlx::query_as::<_, RecipeWithPoster>(
+            r#"
+            SELECT u.uid, u.username, pp.picture_path, r.id, r.recipe, r.date_created, rt.thumbnail_path, r.allergens, r.diets, r.revision, r.visibility, r.publish_at,
+                stats.favourites, stats.rating, stats.ratings,
+                EXISTS (SELECT 1 FROM favourites f WHERE f.recipe_id = r.id AND f.user_id = $2) AS favourited
+            FROM collection_recipes cr
+            INNER JOIN recipes r
+                ON r.id = cr.recipe_id
+            LEFT OUTER JOIN users u
+                ON u.uid = r.user_id
+            LEFT OUTER JOIN profile_pictures pp
+                ON u.uid = pp.user_id
+            INNER JOIN recipe_stats stats
+                ON stats.recipe_id = r.id
+            LEFT OUTER JOIN recipe_thumbnails rt
+                ON rt.recipe_id = r.id
+                    WHERE cr.collection_id = $1
+                    AND (r.user_id = $2 OR r.visibility IN ('public', 'unlisted') OR r.publish_at <= CURRENT_TIMESTAMP)
+                    ORDER BY cr.position, r.id"#,
+        )
+        .bind(collection_id)
+        .bind(viewer)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get recipes in collection with id: {}",
+            collection_id
+        ))?;
+
+        Ok(recipes)
+    }
+}
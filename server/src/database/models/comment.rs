@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, prelude::FromRow, Pool, Postgres, Row};
+
+use super::recipe::Poster;
+
+#[derive(Serialize, Deserialize)]
+pub struct Comment {
+    pub id: i32,
+    pub recipe_id: i32,
+    // Set on replies, replies only go one level deep
+    pub parent_id: Option<i32>,
+    // The author and body are left out once the comment has been deleted, the comment itself
+    // stays so its replies still have somewhere to hang off
+    pub author: Option<Poster>,
+    pub body: Option<String>,
+    pub deleted: bool,
+    pub date_created: chrono::DateTime<Utc>,
+    pub date_edited: Option<chrono::DateTime<Utc>>,
+}
+
+impl FromRow<'_, PgRow> for Comment {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let deleted: bool = row.get("deleted");
+
+        let (author, body) = if deleted {
+            (None, None)
+        } else {
+            (
+                Some(Poster {
+                    uid: row.get("uid"),
+                    username: row.get("username"),
+                    picture: row.try_get("picture_path").unwrap_or(None),
+                }),
+                Some(row.get("body")),
+            )
+        };
+
+        Ok(Comment {
+            id: row.get("id"),
+            recipe_id: row.get("recipe_id"),
+            parent_id: row.get("parent_id"),
+            author,
+            body,
+            deleted,
+            date_created: row.get("date_created"),
+            date_edited: row.get("date_edited"),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CommentThread {
+    #[serde(flatten)]
+    pub comment: Comment,
+    // Oldest first
+    pub replies: Vec<Comment>,
+}
+
+// Who a comment belongs to, for checking who is allowed to change it
+#[derive(FromRow)]
+pub struct CommentOwner {
+    pub id: i32,
+    pub recipe_id: i32,
+    pub user_id: i32,
+    pub deleted: bool,
+}
+
+impl Comment {
+    // A reply to a reply goes on the thread of the comment it replies to. Returns None if the
+    // comment being replied to isn't on the recipe
+    pub async fn insert(
+        pool: &Pool<Postgres>,
+        recipe_id: i32,
+        user_id: i32,
+        parent_id: Option<i32>,
+        body: &str,
+    ) -> Result<Option<i32>, anyhow::Error> {
+        let rec = match parent_id {
+            Some(parent_id) => {
+                sqlx::query(
+                    r#"
+                    INSERT INTO comments (recipe_id, user_id, parent_id, body)
+                    SELECT $1, $2, coalesce(c.parent_id, c.id), $4 FROM comments c
+                        WHERE c.id = $3 AND c.recipe_id = $1
+                    RETURNING id"#,
+                )
+                .bind(recipe_id)
+                .bind(user_id)
+                .bind(parent_id)
+                .bind(body)
+                .fetch_optional(pool)
+                .await
+            }
+            None => {
+                sqlx::query(
+                    r#"INSERT INTO comments (recipe_id, user_id, body) VALUES ($1, $2, $3)
+                    RETURNING id"#,
+                )
+                .bind(recipe_id)
+                .bind(user_id)
+                .bind(body)
+                .fetch_optional(pool)
+                .await
+            }
+        }
+        .context(format!(
+            "Failed to comment on recipe with id: {}",
+            recipe_id
+        ))?;
+
+        Ok(rec.map(|rec| rec.get("id")))
+    }
+
+    pub async fn get_owner(
+        pool: &Pool<Postgres>,
+        comment_id: i32,
+    ) -> Result<Option<CommentOwner>, anyhow::Error> {
+        let owner = sqlx::query_as::<_, CommentOwner>(
+            r#"SELECT id, recipe_id, user_id, deleted FROM comments WHERE id = $1"#,
+        )
+        .bind(comment_id)
+        .fetch_optional(pool)
+        .await
+        .context(format!("Failed to get comment with id: {}", comment_id))?;
+
+        Ok(owner)
+    }
+
+    pub async fn update(
+        pool: &Pool<Postgres>,
+        comment_id: i32,
+        body: &str,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"UPDATE comments SET body = $2, date_edited = CURRENT_TIMESTAMP WHERE id = $1"#,
+        )
+        .bind(comment_id)
+        .bind(body)
+        .execute(pool)
+        .await
+        .context(format!("Failed to edit comment with id: {}", comment_id))?;
+
+        Ok(())
+    }
+
+    // Used when the author deletes their comment, the body is cleared but replies are kept
+    pub async fn soft_delete(pool: &Pool<Postgres>, comment_id: i32) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"UPDATE comments SET deleted = true, body = '' WHERE id = $1"#)
+            .bind(comment_id)
+            .execute(pool)
+            .await
+            .context(format!("Failed to delete comment with id: {}", comment_id))?;
+
+        Ok(())
+    }
+
+    // Used when the recipe's poster removes a comment, its replies go with it
+    pub async fn delete(pool: &Pool<Postgres>, comment_id: i32) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"DELETE FROM comments WHERE id = $1"#)
+            .bind(comment_id)
+            .execute(pool)
+            .await
+            .context(format!("Failed to delete comment with id: {}", comment_id))?;
+
+        Ok(())
+    }
+
+    // Paginates over the top level comments, each comes with all of its replies
+    pub async fn get_threads(
+        pool: &Pool<Postgres>,
+        recipe_id: i32,
+        newest_first: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<CommentThread>, anyhow::Error> {
+        let comments = sqlx::query_as::<_, Comment>(
+            r#"
+            SELECT c.id, c.recipe_id, c.parent_id, c.body, c.deleted, c.date_created, c.date_edited, u.uid, u.username, pp.picture_path FROM comments c
+            INNER JOIN users u
+                ON u.uid = c.user_id
+            LEFT OUTER JOIN profile_pictures pp
+                ON u.uid = pp.user_id
+                    WHERE c.recipe_id = $1 AND c.parent_id IS NULL
+                    ORDER BY CASE WHEN $2 THEN -c.id ELSE c.id END LIMIT $3 OFFSET $4"#,
+        )
+        .bind(recipe_id)
+        .bind(newest_first)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get comments on recipe with id: {}",
+            recipe_id
+        ))?;
+
+        let ids: Vec<i32> = comments.iter().map(|comment| comment.id).collect();
+        let replies = sqlx::query_as::<_, Comment>(
+            r#"
+            SELECT c.id, c.recipe_id, c.parent_id, c.body, c.deleted, c.date_created, c.date_edited, u.uid, u.username, pp.picture_path FROM comments c
+            INNER JOIN users u
+                ON u.uid = c.user_id
+            LEFT OUTER JOIN profile_pictures pp
+                ON u.uid = pp.user_id
+                    WHERE c.parent_id = ANY($1)
+                    ORDER BY c.id"#,
+        )
+        .bind(&ids)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get replies on recipe with id: {}",
+            recipe_id
+        ))?;
+
+        let mut replies_by_parent: HashMap<i32, Vec<Comment>> = HashMap::new();
+        for reply in replies {
+            if let Some(parent_id) = reply.parent_id {
+                replies_by_parent.entry(parent_id).or_default().push(reply);
+            }
+        }
+
+        Ok(comments
+            .into_iter()
+            .map(|comment| CommentThread {
+                replies: replies_by_parent.remove(&comment.id).unwrap_or_default(),
+                comment,
+            })
+            .collect())
+    }
+}
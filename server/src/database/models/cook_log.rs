@@ -0,0 +1,190 @@
+use anyhow::Context;
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, prelude::FromRow, Pool, Postgres, Row};
+
+use super::recipe::Poster;
+
+// A record of someone cooking a recipe
+#[derive(Serialize, Deserialize)]
+pub struct CookLog {
+    pub id: i32,
+    pub recipe_id: i32,
+    // None if the recipe has since been hidden by its poster
+    pub recipe_title: Option<String>,
+    pub cook: Poster,
+    pub cooked_on: NaiveDate,
+    pub notes: Option<String>,
+    // The cook's own rating, separate from reviews
+    pub rating: Option<i16>,
+    pub photo: Option<String>,
+    // Public entries with a photo show up in the recipe's gallery
+    pub public: bool,
+    pub date_created: chrono::DateTime<Utc>,
+}
+
+impl FromRow<'_, PgRow> for CookLog {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(CookLog {
+            id: row.get("id"),
+            recipe_id: row.get("recipe_id"),
+            recipe_title: row.get("recipe_title"),
+            cook: Poster {
+                uid: row.get("uid"),
+                username: row.get("username"),
+                picture: row.try_get("picture_path").unwrap_or(None),
+            },
+            cooked_on: row.get("cooked_on"),
+            notes: row.get("notes"),
+            rating: row.get("rating"),
+            photo: row.get("photo_path"),
+            public: row.get("public"),
+            date_created: row.get("date_created"),
+        })
+    }
+}
+
+// Who an entry belongs to and its photo, for deleting it
+#[derive(FromRow)]
+pub struct CookLogOwner {
+    pub id: i32,
+    pub user_id: i32,
+    pub photo_path: Option<String>,
+}
+
+pub struct NewCookLog {
+    pub recipe_id: i32,
+    pub cooked_on: NaiveDate,
+    pub notes: Option<String>,
+    pub rating: Option<i16>,
+    pub photo_path: Option<String>,
+    pub public: bool,
+}
+
+impl CookLog {
+    pub async fn insert(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        entry: &NewCookLog,
+    ) -> Result<i32, anyhow::Error> {
+        let rec = sqlx::query(
+            r#"INSERT INTO cook_logs (user_id, recipe_id, cooked_on, notes, rating, photo_path, public)
+            VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id"#,
+        )
+        .bind(user_id)
+        .bind(entry.recipe_id)
+        .bind(entry.cooked_on)
+        .bind(&entry.notes)
+        .bind(entry.rating)
+        .bind(&entry.photo_path)
+        .bind(entry.public)
+        .fetch_one(pool)
+        .await
+        .context(format!(
+            "Failed to log cooking recipe with id: {}",
+            entry.recipe_id
+        ))?;
+
+        Ok(rec.get("id"))
+    }
+
+    pub async fn get_owner(
+        pool: &Pool<Postgres>,
+        entry_id: i32,
+    ) -> Result<Option<CookLogOwner>, anyhow::Error> {
+        let owner = sqlx::query_as::<_, CookLogOwner>(
+            r#"SELECT id, user_id, photo_path FROM cook_logs WHERE id = $1"#,
+        )
+        .bind(entry_id)
+        .fetch_optional(pool)
+        .await
+        .context(format!(
+            "Failed to get cook log entry with id: {}",
+            entry_id
+        ))?;
+
+        Ok(owner)
+    }
+
+    pub async fn delete(pool: &Pool<Postgres>, entry_id: i32) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"DELETE FROM cook_logs WHERE id = $1"#)
+            .bind(entry_id)
+            .execute(pool)
+            .await
+            .context(format!(
+                "Failed to delete cook log entry with id: {}",
+                entry_id
+            ))?;
+
+        Ok(())
+    }
+
+    // The user's own history, most recently cooked first
+    pub async fn get_by_user(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<CookLog>, anyhow::Error> {
+        let entries = sqlx::query_as::<_, CookLog>(
+            r#"
+            SELECT cl.id, cl.recipe_id, cl.cooked_on, cl.notes, cl.rating, cl.photo_path, cl.public, cl.date_created,
+                CASE WHEN r.user_id = $1 OR r.visibility IN ('public', 'unlisted') OR r.publish_at <= CURRENT_TIMESTAMP
+                    THEN r.recipe ->> 'title' END AS recipe_title,
+                u.uid, u.username, pp.picture_path FROM cook_logs cl
+            INNER JOIN recipes r
+                ON r.id = cl.recipe_id
+            INNER JOIN users u
+                ON u.uid = cl.user_id
+            LEFT OUTER JOIN profile_pictures pp
+                ON u.uid = pp.user_id
+                    WHERE cl.user_id = $1
+                    ORDER BY cl.cooked_on DESC, cl.id DESC LIMIT $2 OFFSET $3"#,
+        )
+        .bind(user_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get the cook log of user with id: {}",
+            user_id
+        ))?;
+
+        Ok(entries)
+    }
+
+    // Public entries with a photo, newest first
+    pub async fn get_gallery(
+        pool: &Pool<Postgres>,
+        recipe_id: i32,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<CookLog>, anyhow::Error> {
+        let entries = sqlx::query_as::<_, CookLog>(
+            r#"
+            SELECT cl.id, cl.recipe_id, cl.cooked_on, cl.notes, cl.rating, cl.photo_path, cl.public, cl.date_created,
+                r.recipe ->> 'title' AS recipe_title,
+                u.uid, u.username, pp.picture_path FROM cook_logs cl
+            INNER JOIN recipes r
+                ON r.id = cl.recipe_id
+            INNER JOIN users u
+                ON u.uid = cl.user_id
+            LEFT OUTER JOIN profile_pictures pp
+                ON u.uid = pp.user_id
+                    WHERE cl.recipe_id = $1 AND cl.public AND cl.photo_path IS NOT NULL
+                    ORDER BY cl.id DESC LIMIT $2 OFFSET $3"#,
+        )
+        .bind(recipe_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get the cook photos of recipe with id: {}",
+            recipe_id
+        ))?;
+
+        Ok(entries)
+    }
+}
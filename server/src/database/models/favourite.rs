@@ -0,0 +1,87 @@
+use anyhow::Context;
+use sqlx::{Pool, Postgres};
+
+use super::recipe::RecipeWithPoster;
+
+pub struct Favourite;
+
+impl Favourite {
+    // Returns false if the user had already favourited the recipe
+    pub async fn insert(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        recipe_id: i32,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(
+            r#"INSERT INTO favourites (user_id, recipe_id) VALUES ($1, $2)
+            ON CONFLICT (user_id, recipe_id) DO NOTHING"#,
+        )
+        .bind(user_id)
+        .bind(recipe_id)
+        .execute(pool)
+        .await
+        .context(format!("Failed to favourite recipe with id: {}", recipe_id))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Returns false if the user hadn't favourited the recipe
+    pub async fn delete(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        recipe_id: i32,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(r#"DELETE FROM favourites WHERE user_id = $1 AND recipe_id = $2"#)
+            .bind(user_id)
+            .bind(recipe_id)
+            .execute(pool)
+            .await
+            .context(format!(
+                "Failed to unfavourite recipe with id: {}",
+                recipe_id
+            ))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Most recently favourited first, recipes that have since been hidden by their poster are
+    // left out
+    pub async fn get_recipes(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<RecipeWithPoster>, anyhow::Error> {
+        let recipes = sqlx::query_as::<_, RecipeWithPoster>(
+            r#"
+            SELECT u.uid, u.username, pp.picture_path, r.id, r.recipe, r.date_created, rt.thumbnail_path, r.allergens, r.diets, r.revision, r.visibility, r.publish_at,
+                stats.favourites, stats.rating, stats.ratings,
+                true AS favourited
+            FROM favourites fav
+            INNER JOIN recipes r
+                ON r.id = fav.recipe_id
+            LEFT OUTER JOIN users u
+                ON u.uid = r.user_id
+            LEFT OUTER JOIN profile_pictures pp
+                ON u.uid = pp.user_id
+            INNER JOIN recipe_stats stats
+                ON stats.recipe_id = r.id
+            LEFT OUTER JOIN recipe_thumbnails rt
+                ON rt.recipe_id = r.id
+                    WHERE fav.user_id = $1
+                    AND (r.user_id = $1 OR r.visibility IN ('public', 'unlisted') OR r.publish_at <= CURRENT_TIMESTAMP)
+                    ORDER BY fav.date_created DESC, r.id LIMIT $2 OFFSET $3"#,
+        )
+        .bind(user_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get favourites of user with id: {}",
+            user_id
+        ))?;
+
+        Ok(recipes)
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::Context;
+use chrono::Utc;
+use sqlx::{prelude::FromRow, Pool, Postgres, Row};
+
+use super::recipe::RecipeWithPoster;
+
+pub struct Follow;
+
+// Where the feed carries on from, the time and id of the last recipe on the previous page
+#[derive(Clone, Copy)]
+pub struct FeedCursor {
+    pub published: chrono::DateTime<Utc>,
+    pub recipe_id: i32,
+}
+
+impl FeedCursor {
+    // Passed to clients as "<published in microseconds>.<recipe id>"
+    pub fn encode(&self) -> String {
+        format!("{}.{}", self.published.timestamp_micros(), self.recipe_id)
+    }
+
+    pub fn decode(cursor: &str) -> Option<FeedCursor> {
+        let (published, recipe_id) = cursor.split_once('.')?;
+
+        Some(FeedCursor {
+            published: chrono::DateTime::from_timestamp_micros(published.parse().ok()?)?,
+            recipe_id: recipe_id.parse().ok()?,
+        })
+    }
+}
+
+impl Follow {
+    // Returns false if the user was already following them
+    pub async fn insert(
+        pool: &Pool<Postgres>,
+        follower_id: i32,
+        followee_id: i32,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(
+            r#"INSERT INTO follows (follower_id, followee_id) VALUES ($1, $2)
+            ON CONFLICT (follower_id, followee_id) DO NOTHING"#,
+        )
+        .bind(follower_id)
+        .bind(followee_id)
+        .execute(pool)
+        .await
+        .context(format!("Failed to follow user with id: {}", followee_id))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Returns false if the user wasn't following them
+    pub async fn delete(
+        pool: &Pool<Postgres>,
+        follower_id: i32,
+        followee_id: i32,
+    ) -> Result<bool, anyhow::Error> {
+        let result =
+            sqlx::query(r#"DELETE FROM follows WHERE follower_id = $1 AND followee_id = $2"#)
+                .bind(follower_id)
+                .bind(followee_id)
+                .execute(pool)
+                .await
+                .context(format!("Failed to unfollow user with id: {}", followee_id))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Public recipes from the users someone follows, newest first. Recipes count from when they
+    // were published rather than when they were written
+    pub async fn get_feed(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        after: Option<FeedCursor>,
+        limit: u32,
+    ) -> Result<Vec<(RecipeWithPoster, FeedCursor)>, anyhow::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT u.uid, u.username, pp.picture_path, r.id, r.recipe, r.date_created, rt.thumbnail_path, r.allergens, r.diets, r.revision, r.visibility, r.publish_at,
+                stats.favourites, stats.rating, stats.ratings,
+                EXISTS (SELECT 1 FROM favourites f WHERE f.recipe_id = r.id AND f.user_id = $1) AS favourited,
+                r.published_at AS published
+            FROM follows fo
+            INNER JOIN recipes r
+                ON r.user_id = fo.followee_id
+            INNER JOIN users u
+                ON u.uid = r.user_id
+            LEFT OUTER JOIN profile_pictures pp
+                ON u.uid = pp.user_id
+            INNER JOIN recipe_stats stats
+                ON stats.recipe_id = r.id
+            LEFT OUTER JOIN recipe_thumbnails rt
+                ON rt.recipe_id = r.id
+                    WHERE fo.follower_id = $1
+                    AND (r.visibility = 'public' OR r.publish_at <= CURRENT_TIMESTAMP)
+                    AND r.published_at IS NOT NULL
+                    AND ($2::timestamptz IS NULL OR (r.published_at, r.id) < ($2, $3))
+                    ORDER BY r.published_at DESC, r.id DESC LIMIT $4"#,
+        )
+        .bind(user_id)
+        .bind(after.map(|cursor| cursor.published))
+        .bind(after.map(|cursor| cursor.recipe_id))
+        .bind(limit as i64)
+        .fetch_all(pool)
+        .await
+        .context(format!("Failed to get the feed of user with id: {}", user_id))?;
+
+        let mut feed = Vec::new();
+        for row in rows.iter() {
+            let recipe = RecipeWithPoster::from_row(row)?;
+            let cursor = FeedCursor {
+                published: row.try_get("published")?,
+                recipe_id: recipe.id,
+            };
+
+            feed.push((recipe, cursor));
+        }
+
+        Ok(feed)
+    }
+}
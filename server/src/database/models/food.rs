@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sqlx::{prelude::FromRow, Pool, Postgres, Row};
+
+use crate::recipe_io::nutrition::{FoodPortion, IngredientFood};
+
+#[derive(FromRow, Serialize, Deserialize)]
+pub struct Food {
+    pub fdc_id: i32,
+    pub description: String,
+}
+
+impl Food {
+    pub async fn exists(pool: &Pool<Postgres>, fdc_id: i32) -> Result<bool, anyhow::Error> {
+        let row = sqlx::query(r#"SELECT 1 AS found FROM foods WHERE fdc_id = $1"#)
+            .bind(fdc_id)
+            .fetch_optional(pool)
+            .await
+            .context(format!("Failed to find food with fdc id: {}", fdc_id))?;
+
+        Ok(row.is_some())
+    }
+
+    // Foods whose description contains the query, shortest first as those are the plainest
+    pub async fn search(
+        pool: &Pool<Postgres>,
+        query: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<Food>, anyhow::Error> {
+        let foods = sqlx::query_as::<_, Food>(
+            r#"
+            SELECT fdc_id, description FROM foods
+                WHERE lower(description) LIKE '%' || lower($1) || '%'
+                ORDER BY length(description), fdc_id LIMIT $2 OFFSET $3"#,
+        )
+        .bind(query)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(foods)
+    }
+
+    // The foods linked to the ingredients of a recipe, keyed by the position of the ingredient
+    // in the recipe. Ingredients without a food are left out
+    pub async fn get_for_recipe(
+        pool: &Pool<Postgres>,
+        recipe_id: i32,
+    ) -> Result<HashMap<usize, IngredientFood>, anyhow::Error> {
+        let links = sqlx::query(
+            r#"
+            SELECT ri.position, i.fdc_id, i.grams_per_piece FROM recipe_ingredients ri
+            INNER JOIN ingredients i
+                ON i.id = ri.ingredient_id
+                    WHERE ri.recipe_id = $1 AND i.fdc_id IS NOT NULL"#,
+        )
+        .bind(recipe_id)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get the foods of recipe with id: {}",
+            recipe_id
+        ))?;
+
+        let fdc_ids: Vec<i32> = links.iter().map(|row| row.get("fdc_id")).collect();
+
+        let mut nutrients: HashMap<i32, HashMap<i32, f64>> = HashMap::new();
+        let rows = sqlx::query(
+            r#"SELECT fdc_id, nutrient_id, amount FROM food_nutrients WHERE fdc_id = ANY($1)"#,
+        )
+        .bind(&fdc_ids)
+        .fetch_all(pool)
+        .await?;
+
+        for row in rows.iter() {
+            nutrients
+                .entry(row.get("fdc_id"))
+                .or_default()
+                .insert(row.get("nutrient_id"), row.get("amount"));
+        }
+
+        let mut portions: HashMap<i32, Vec<FoodPortion>> = HashMap::new();
+        let rows = sqlx::query(
+            r#"SELECT fdc_id, measure_unit, amount, gram_weight FROM food_portions WHERE fdc_id = ANY($1)"#,
+        )
+        .bind(&fdc_ids)
+        .fetch_all(pool)
+        .await?;
+
+        for row in rows.iter() {
+            portions
+                .entry(row.get("fdc_id"))
+                .or_default()
+                .push(FoodPortion {
+                    measure_unit: row.get("measure_unit"),
+                    amount: row.get("amount"),
+                    gram_weight: row.get("gram_weight"),
+                });
+        }
+
+        Ok(links
+            .iter()
+            .map(|row| {
+                let position: i32 = row.get("position");
+                let fdc_id: i32 = row.get("fdc_id");
+
+                (
+                    position as usize,
+                    IngredientFood::new(
+                        row.get("grams_per_piece"),
+                        portions.get(&fdc_id).map(Vec::as_slice).unwrap_or_default(),
+                        nutrients.get(&fdc_id).cloned().unwrap_or_default(),
+                    ),
+                )
+            })
+            .collect())
+    }
+}
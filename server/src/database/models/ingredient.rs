@@ -0,0 +1,515 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, prelude::FromRow, PgConnection, Pool, Postgres, Row};
+
+use crate::recipe_io::{
+    dietary::{Allergen, IngredientProfile},
+    normalise::{ingredient_key, normalise_ingredient},
+    shopping::Aisle,
+    RecipeMeasurements,
+};
+
+use super::recipe::Recipe;
+
+#[derive(FromRow, Serialize, Deserialize)]
+pub struct Ingredient {
+    pub id: i32,
+    pub name: String,
+    pub plural: Option<String>,
+}
+
+// The catalogue entry an ingredient of a recipe is linked to
+pub struct IngredientLink {
+    pub id: i32,
+    pub name: String,
+    pub plural: Option<String>,
+    pub aisle: Aisle,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IngredientWithAliases {
+    pub id: i32,
+    pub name: String,
+    pub plural: Option<String>,
+    pub aliases: Vec<String>,
+    pub profile: IngredientProfile,
+}
+
+impl Ingredient {
+    pub async fn get_by_id(
+        pool: &Pool<Postgres>,
+        id: i32,
+    ) -> Result<Option<Ingredient>, anyhow::Error> {
+        let ingredient =
+            sqlx::query_as::<_, Ingredient>(r#"SELECT * FROM ingredients WHERE id = $1"#)
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+                .context(format!("Failed to find ingredient with id: {}", id))?;
+
+        Ok(ingredient)
+    }
+
+    // Lists the catalogue in name order, optionally only the entries whose name contains query
+    pub async fn get_paginated(
+        pool: &Pool<Postgres>,
+        query: Option<&str>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<IngredientWithAliases>, anyhow::Error> {
+        let query = query.map(normalise_ingredient);
+        let rows = sqlx::query(
+            r#"
+            SELECT i.id, i.name, i.plural, i.allergens, i.meat, i.animal_product,
+                coalesce(array_agg(a.alias ORDER BY a.alias) FILTER (WHERE a.alias IS NOT NULL), '{}') AS aliases
+            FROM ingredients i
+            LEFT OUTER JOIN ingredient_aliases a
+                ON a.ingredient_id = i.id
+                    WHERE $1::text IS NULL OR i.name LIKE '%' || $1 || '%'
+                    GROUP BY i.id
+                    ORDER BY i.name LIMIT $2 OFFSET $3"#,
+        )
+        .bind(query)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| IngredientWithAliases {
+                id: row.get("id"),
+                name: row.get("name"),
+                plural: row.get("plural"),
+                aliases: row.get("aliases"),
+                profile: profile_from_row(row),
+            })
+            .collect())
+    }
+
+    // Finds the catalogue entry for an ingredient by its name, plural or one of its aliases.
+    // A name wins over an alias
+    pub async fn find_id(
+        conn: &mut PgConnection,
+        name: &str,
+    ) -> Result<Option<i32>, anyhow::Error> {
+        let key = ingredient_key(name);
+        let normalised = normalise_ingredient(name);
+
+        let row = sqlx::query(
+            r#"
+            SELECT id, 0 AS priority FROM ingredients WHERE name = $1 OR plural = $2
+            UNION ALL
+            SELECT ingredient_id AS id, 1 AS priority FROM ingredient_aliases WHERE alias = $1 OR alias = $2
+            ORDER BY priority, id
+            LIMIT 1"#,
+        )
+        .bind(&key)
+        .bind(&normalised)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        Ok(row.map(|row| row.get("id")))
+    }
+
+    // Finds the catalogue entry for an ingredient, adding a new one if it isn't in there yet
+    pub async fn find_or_insert(conn: &mut PgConnection, name: &str) -> Result<i32, anyhow::Error> {
+        if let Some(id) = Self::find_id(&mut *conn, name).await? {
+            return Ok(id);
+        }
+
+        let key = ingredient_key(name);
+        let normalised = normalise_ingredient(name);
+        let plural = if normalised != key {
+            Some(normalised)
+        } else {
+            None
+        };
+
+        let profile = IngredientProfile::guess(&key);
+
+        // Another recipe may have added it since we looked
+        let rec = sqlx::query(
+            r#"INSERT INTO ingredients (name, plural, allergens, meat, animal_product)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (name) DO UPDATE SET name = excluded.name RETURNING id"#,
+        )
+        .bind(key)
+        .bind(plural)
+        .bind(allergen_names(&profile.allergens))
+        .bind(profile.meat)
+        .bind(profile.animal_product)
+        .fetch_one(&mut *conn)
+        .await
+        .context(format!("Failed to add ingredient: {}", name))?;
+
+        let id: i32 = rec.get("id");
+        Self::match_food(&mut *conn, id).await?;
+
+        Ok(id)
+    }
+
+    // Links one ingredient to its food the same way as match_foods. The prefix is looked up
+    // as a range so the description index is used, rather than scanning every food
+    async fn match_food(conn: &mut PgConnection, ingredient_id: i32) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+            UPDATE ingredients i SET fdc_id = (
+                SELECT f.fdc_id FROM foods f
+                    WHERE lower(f.description) IN (i.name, i.plural)
+                        OR (lower(f.description) ~>=~ (i.name || ',') AND lower(f.description) ~<~ (i.name || '-'))
+                        OR (lower(f.description) ~>=~ (i.plural || ',') AND lower(f.description) ~<~ (i.plural || '-'))
+                        ORDER BY length(f.description), f.fdc_id LIMIT 1
+            )
+            WHERE i.id = $1 AND i.fdc_id IS NULL"#,
+        )
+        .bind(ingredient_id)
+        .execute(&mut *conn)
+        .await
+        .context(format!(
+            "Failed to match ingredient with id: {} to a food",
+            ingredient_id
+        ))?;
+
+        Ok(())
+    }
+
+    // Links ingredients that have no food yet to the food named the same, either exactly or
+    // as the start of a FoodData Central description like "onions, raw". Returns how many got
+    // linked
+    pub async fn match_foods(conn: &mut PgConnection) -> Result<u64, anyhow::Error> {
+        let rec = sqlx::query(
+            r#"
+            UPDATE ingredients SET fdc_id = matches.fdc_id
+            FROM (
+                SELECT DISTINCT ON (i.id) i.id, f.fdc_id FROM ingredients i
+                INNER JOIN foods f
+                    ON lower(f.description) IN (i.name, i.plural)
+                        OR lower(f.description) LIKE i.name || ',%'
+                        OR lower(f.description) LIKE i.plural || ',%'
+                        WHERE i.fdc_id IS NULL
+                        ORDER BY i.id, length(f.description), f.fdc_id
+            ) matches
+            WHERE ingredients.id = matches.id"#,
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to match ingredients to foods")?;
+
+        Ok(rec.rows_affected())
+    }
+
+    // Matches the ingredients that had no food when they were added, e.g. because the foods
+    // hadn't been imported yet
+    pub async fn match_unlinked_foods(pool: &Pool<Postgres>) -> Result<u64, anyhow::Error> {
+        let mut conn = pool.acquire().await?;
+
+        Self::match_foods(&mut conn).await
+    }
+
+    // The profiles of a recipe's linked ingredients, in the order of the recipe
+    pub async fn get_profiles_for_recipe(
+        conn: &mut PgConnection,
+        recipe_id: i32,
+    ) -> Result<Vec<IngredientProfile>, anyhow::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT i.name, i.allergens, i.meat, i.animal_product FROM recipe_ingredients ri
+            INNER JOIN ingredients i
+                ON i.id = ri.ingredient_id
+                    WHERE ri.recipe_id = $1
+                    ORDER BY ri.position"#,
+        )
+        .bind(recipe_id)
+        .fetch_all(&mut *conn)
+        .await
+        .context(format!(
+            "Failed to get the ingredients of recipe with id: {}",
+            recipe_id
+        ))?;
+
+        Ok(rows.iter().map(profile_from_row).collect())
+    }
+
+    // Guesses the profile of every ingredient that has never had one, returns how many
+    pub async fn classify_unclassified(conn: &mut PgConnection) -> Result<u64, anyhow::Error> {
+        let rows = sqlx::query(r#"SELECT id, name FROM ingredients WHERE allergens IS NULL"#)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        for row in rows.iter() {
+            let id: i32 = row.get("id");
+            let name: String = row.get("name");
+
+            Self::set_profile(&mut *conn, id, &IngredientProfile::guess(&name)).await?;
+        }
+
+        Ok(rows.len() as u64)
+    }
+
+    // Corrects the profile of an ingredient and classifies the recipes using it again,
+    // returns how many recipes were classified
+    pub async fn update_profile(
+        pool: &Pool<Postgres>,
+        ingredient_id: i32,
+        profile: &IngredientProfile,
+    ) -> Result<usize, anyhow::Error> {
+        let mut tx = pool.begin().await?;
+        Self::set_profile(&mut tx, ingredient_id, profile).await?;
+        let reclassified = Recipe::reclassify(&mut tx, Some(ingredient_id)).await?;
+        tx.commit().await?;
+
+        Ok(reclassified)
+    }
+
+    pub async fn set_profile(
+        conn: &mut PgConnection,
+        ingredient_id: i32,
+        profile: &IngredientProfile,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"UPDATE ingredients SET allergens = $2, meat = $3, animal_product = $4 WHERE id = $1"#,
+        )
+        .bind(ingredient_id)
+        .bind(allergen_names(&profile.allergens))
+        .bind(profile.meat)
+        .bind(profile.animal_product)
+        .execute(&mut *conn)
+        .await
+        .context(format!(
+            "Failed to set the profile of ingredient with id: {}",
+            ingredient_id
+        ))?;
+
+        Ok(())
+    }
+
+    // Sets where the ingredient is found in a shop, None goes back to guessing from its profile
+    pub async fn set_aisle(
+        pool: &Pool<Postgres>,
+        ingredient_id: i32,
+        aisle: Option<Aisle>,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"UPDATE ingredients SET aisle = $2 WHERE id = $1"#)
+            .bind(ingredient_id)
+            .bind(aisle.map(|aisle| aisle.name()))
+            .execute(pool)
+            .await
+            .context(format!(
+                "Failed to set the aisle of ingredient with id: {}",
+                ingredient_id
+            ))?;
+
+        Ok(())
+    }
+
+    // The catalogue entry and aisle of every ingredient of the recipes, by recipe id and
+    // position. Ingredients without an aisle set are guessed from their name, then their profile
+    pub async fn get_recipe_links(
+        pool: &Pool<Postgres>,
+        recipe_ids: &[i32],
+    ) -> Result<HashMap<(i32, i32), IngredientLink>, anyhow::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT ri.recipe_id, ri.position, i.id, i.name, i.plural, i.aisle,
+                CASE
+                    WHEN i.meat OR i.allergens && ARRAY['fish', 'shellfish'] THEN 'meat_and_fish'
+                    WHEN i.allergens && ARRAY['dairy', 'egg'] THEN 'dairy_and_eggs'
+                END AS profile_aisle
+            FROM recipe_ingredients ri
+            INNER JOIN ingredients i
+                ON i.id = ri.ingredient_id
+                    WHERE ri.recipe_id = ANY($1)"#,
+        )
+        .bind(recipe_ids)
+        .fetch_all(pool)
+        .await
+        .context("Failed to get the ingredients of recipes")?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let name: String = row.get("name");
+                let aisle: Option<String> = row.get("aisle");
+                let profile_aisle: Option<String> = row.get("profile_aisle");
+                let aisle = aisle
+                    .and_then(|aisle| Aisle::from_name(&aisle))
+                    .or_else(|| Aisle::from_keywords(&name))
+                    .or(profile_aisle.and_then(|aisle| Aisle::from_name(&aisle)))
+                    .unwrap_or(Aisle::Other);
+
+                (
+                    (row.get("recipe_id"), row.get("position")),
+                    IngredientLink {
+                        id: row.get("id"),
+                        name,
+                        plural: row.get("plural"),
+                        aisle,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    // Sets the food an ingredient's nutrition comes from, None unlinks it
+    pub async fn set_food(
+        pool: &Pool<Postgres>,
+        ingredient_id: i32,
+        fdc_id: Option<i32>,
+        grams_per_piece: Option<f64>,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"UPDATE ingredients SET fdc_id = $2, grams_per_piece = $3 WHERE id = $1"#)
+            .bind(ingredient_id)
+            .bind(fdc_id)
+            .bind(grams_per_piece)
+            .execute(pool)
+            .await
+            .context(format!(
+                "Failed to set the food of ingredient with id: {}",
+                ingredient_id
+            ))?;
+
+        Ok(())
+    }
+
+    // Links every ingredient of a recipe to its catalogue entry, replacing any old links
+    pub async fn link_recipe(
+        conn: &mut PgConnection,
+        recipe_id: i32,
+        ingredients: &[RecipeMeasurements],
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"DELETE FROM recipe_ingredients WHERE recipe_id = $1"#)
+            .bind(recipe_id)
+            .execute(&mut *conn)
+            .await?;
+
+        for (position, ingredient) in ingredients.iter().enumerate() {
+            let ingredient_id = Self::find_or_insert(&mut *conn, &ingredient.ingredient).await?;
+
+            sqlx::query(
+                r#"INSERT INTO recipe_ingredients (recipe_id, position, ingredient_id)
+                VALUES ($1, $2, $3)"#,
+            )
+            .bind(recipe_id)
+            .bind(position as i32)
+            .bind(ingredient_id)
+            .execute(&mut *conn)
+            .await
+            .context(format!(
+                "Failed to link ingredient of recipe with id: {}",
+                recipe_id
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    // Returns false if the alias is already taken by an ingredient or another alias
+    pub async fn add_alias(
+        pool: &Pool<Postgres>,
+        ingredient_id: i32,
+        alias: &str,
+    ) -> Result<bool, anyhow::Error> {
+        let mut conn = pool.acquire().await?;
+        if Self::find_id(&mut conn, alias).await?.is_some() {
+            return Ok(false);
+        }
+
+        sqlx::query(r#"INSERT INTO ingredient_aliases (alias, ingredient_id) VALUES ($1, $2)"#)
+            .bind(ingredient_key(alias))
+            .bind(ingredient_id)
+            .execute(&mut *conn)
+            .await
+            .context(format!(
+                "Failed to add alias to ingredient with id: {}",
+                ingredient_id
+            ))?;
+
+        Ok(true)
+    }
+
+    // Returns false if there was no such alias
+    pub async fn remove_alias(pool: &Pool<Postgres>, alias: &str) -> Result<bool, anyhow::Error> {
+        let rec = sqlx::query(r#"DELETE FROM ingredient_aliases WHERE alias = $1"#)
+            .bind(ingredient_key(alias))
+            .execute(pool)
+            .await
+            .context(format!("Failed to remove alias: {}", alias))?;
+
+        Ok(rec.rows_affected() > 0)
+    }
+
+    // Folds source into target, every recipe and alias pointing at source now points at
+    // target and the source name and plural become aliases of target
+    pub async fn merge(
+        pool: &Pool<Postgres>,
+        source_id: i32,
+        target_id: i32,
+    ) -> Result<(), anyhow::Error> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(r#"UPDATE recipe_ingredients SET ingredient_id = $2 WHERE ingredient_id = $1"#)
+            .bind(source_id)
+            .bind(target_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(r#"UPDATE ingredient_aliases SET ingredient_id = $2 WHERE ingredient_id = $1"#)
+            .bind(source_id)
+            .bind(target_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"INSERT INTO ingredient_aliases (alias, ingredient_id)
+            SELECT alias, $2 FROM ingredients, unnest(ARRAY[name, plural]) alias
+                WHERE id = $1 AND alias IS NOT NULL
+            ON CONFLICT (alias) DO NOTHING"#,
+        )
+        .bind(source_id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(r#"DELETE FROM ingredients WHERE id = $1"#)
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Recipes that used source now take on the profile of target
+        Recipe::reclassify(&mut tx, Some(target_id)).await?;
+
+        tx.commit().await.context(format!(
+            "Failed to merge ingredient {} into {}",
+            source_id, target_id
+        ))?;
+
+        Ok(())
+    }
+}
+
+fn allergen_names(allergens: &[Allergen]) -> Vec<String> {
+    allergens
+        .iter()
+        .map(|allergen| allergen.name().to_string())
+        .collect()
+}
+
+// Ingredients from before allergens were tracked have no profile yet, so one is guessed
+fn profile_from_row(row: &PgRow) -> IngredientProfile {
+    let allergens: Option<Vec<String>> = row.get("allergens");
+    let Some(allergens) = allergens else {
+        return IngredientProfile::guess(row.get("name"));
+    };
+
+    IngredientProfile {
+        allergens: allergens
+            .iter()
+            .filter_map(|name| Allergen::from_name(name))
+            .collect(),
+        meat: row.get("meat"),
+        animal_product: row.get("animal_product"),
+    }
+}
@@ -0,0 +1,253 @@
+use anyhow::Context;
+use chrono::{NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, prelude::FromRow, Pool, Postgres, Row};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MealSlot {
+    Breakfast,
+    Lunch,
+    Dinner,
+}
+
+impl MealSlot {
+    pub const ALL: [MealSlot; 3] = [MealSlot::Breakfast, MealSlot::Lunch, MealSlot::Dinner];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MealSlot::Breakfast => "breakfast",
+            MealSlot::Lunch => "lunch",
+            MealSlot::Dinner => "dinner",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<MealSlot> {
+        Self::ALL
+            .into_iter()
+            .find(|slot| slot.name() == name.trim().to_lowercase())
+    }
+
+    // When the meal shows up in calendar apps, in the user's own timezone
+    pub fn start_time(&self) -> NaiveTime {
+        match self {
+            MealSlot::Breakfast => NaiveTime::from_hms_opt(8, 0, 0),
+            MealSlot::Lunch => NaiveTime::from_hms_opt(12, 30, 0),
+            MealSlot::Dinner => NaiveTime::from_hms_opt(18, 30, 0),
+        }
+        .unwrap_or_default()
+    }
+}
+
+// A recipe placed on a day. It points at the recipe by id, so it always shows the latest
+// revision
+#[derive(Serialize, Deserialize)]
+pub struct MealPlanEntry {
+    pub id: i32,
+    pub recipe_id: i32,
+    // None if the recipe has since been hidden by its poster, and so is the thumbnail
+    pub recipe_title: Option<String>,
+    pub thumbnail: Option<String>,
+    pub planned_on: NaiveDate,
+    pub slot: MealSlot,
+    // Overrides how many servings the recipe makes, None to use the recipe's own
+    pub servings: Option<i16>,
+    pub recipe_servings: Option<i32>,
+    pub date_created: chrono::DateTime<Utc>,
+}
+
+impl FromRow<'_, PgRow> for MealPlanEntry {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let slot: String = row.try_get("slot")?;
+        let slot = MealSlot::from_name(&slot).ok_or(sqlx::Error::ColumnDecode {
+            index: "slot".to_string(),
+            source: format!("Unknown meal slot: {}", slot).into(),
+        })?;
+
+        Ok(MealPlanEntry {
+            id: row.get("id"),
+            recipe_id: row.get("recipe_id"),
+            recipe_title: row.get("recipe_title"),
+            thumbnail: row.try_get("thumbnail_path").unwrap_or(None),
+            planned_on: row.get("planned_on"),
+            slot,
+            servings: row.get("servings"),
+            recipe_servings: row.get("recipe_servings"),
+            date_created: row.get("date_created"),
+        })
+    }
+}
+
+pub struct NewMealPlanEntry {
+    pub recipe_id: i32,
+    pub planned_on: NaiveDate,
+    pub slot: MealSlot,
+    pub servings: Option<i16>,
+}
+
+pub struct MealPlan;
+
+impl MealPlan {
+    pub async fn insert(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        entry: &NewMealPlanEntry,
+    ) -> Result<i32, anyhow::Error> {
+        let rec = sqlx::query(
+            r#"INSERT INTO meal_plan_entries (user_id, recipe_id, planned_on, slot, servings)
+            VALUES ($1, $2, $3, $4, $5) RETURNING id"#,
+        )
+        .bind(user_id)
+        .bind(entry.recipe_id)
+        .bind(entry.planned_on)
+        .bind(entry.slot.name())
+        .bind(entry.servings)
+        .fetch_one(pool)
+        .await
+        .context(format!(
+            "Failed to plan recipe with id: {}",
+            entry.recipe_id
+        ))?;
+
+        Ok(rec.get("id"))
+    }
+
+    // Moves an entry or changes its servings. Returns false if the user has no entry with
+    // that id
+    pub async fn update(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        entry_id: i32,
+        planned_on: NaiveDate,
+        slot: MealSlot,
+        servings: Option<i16>,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(
+            r#"UPDATE meal_plan_entries SET planned_on = $3, slot = $4, servings = $5
+            WHERE id = $1 AND user_id = $2"#,
+        )
+        .bind(entry_id)
+        .bind(user_id)
+        .bind(planned_on)
+        .bind(slot.name())
+        .bind(servings)
+        .execute(pool)
+        .await
+        .context(format!(
+            "Failed to update meal plan entry with id: {}",
+            entry_id
+        ))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Returns false if the user has no entry with that id
+    pub async fn delete(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        entry_id: i32,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(r#"DELETE FROM meal_plan_entries WHERE id = $1 AND user_id = $2"#)
+            .bind(entry_id)
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .context(format!(
+                "Failed to delete meal plan entry with id: {}",
+                entry_id
+            ))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Every entry from one day to another, both included, in the order they are eaten
+    pub async fn get_range(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<MealPlanEntry>, anyhow::Error> {
+        let entries = sqlx::query_as::<_, MealPlanEntry>(
+            r#"
+            SELECT mp.id, mp.recipe_id, mp.planned_on, mp.slot, mp.servings, mp.date_created,
+                CASE WHEN v.viewable THEN rt.thumbnail_path END AS thumbnail_path,
+                CASE WHEN v.viewable THEN r.recipe ->> 'title' END AS recipe_title,
+                (r.recipe ->> 'servings')::integer AS recipe_servings
+            FROM meal_plan_entries mp
+            INNER JOIN recipes r
+                ON r.id = mp.recipe_id
+            CROSS JOIN LATERAL (
+                SELECT r.user_id = $1 OR r.visibility IN ('public', 'unlisted') OR r.publish_at <= CURRENT_TIMESTAMP AS viewable
+            ) v
+            LEFT OUTER JOIN recipe_thumbnails rt
+                ON rt.recipe_id = r.id
+                    WHERE mp.user_id = $1 AND mp.planned_on BETWEEN $2 AND $3
+                    ORDER BY mp.planned_on, array_position(ARRAY['breakfast', 'lunch', 'dinner'], mp.slot), mp.id"#,
+        )
+        .bind(user_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get the meal plan of user with id: {}",
+            user_id
+        ))?;
+
+        Ok(entries)
+    }
+
+    // Gives the user a new secret calendar feed token, the old one stops working
+    pub async fn reset_feed_token(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+    ) -> Result<String, anyhow::Error> {
+        let token = Uuid::new_v4().simple().to_string();
+        sqlx::query(
+            r#"INSERT INTO meal_plan_feeds (user_id, token) VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET token = excluded.token, date_created = CURRENT_TIMESTAMP"#,
+        )
+        .bind(user_id)
+        .bind(&token)
+        .execute(pool)
+        .await
+        .context(format!(
+            "Failed to create a calendar feed for user with id: {}",
+            user_id
+        ))?;
+
+        Ok(token)
+    }
+
+    // Returns false if the user didn't have a feed
+    pub async fn delete_feed_token(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(r#"DELETE FROM meal_plan_feeds WHERE user_id = $1"#)
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .context(format!(
+                "Failed to delete the calendar feed of user with id: {}",
+                user_id
+            ))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // The user the calendar feed token belongs to
+    pub async fn get_feed_user(
+        pool: &Pool<Postgres>,
+        token: &str,
+    ) -> Result<Option<i32>, anyhow::Error> {
+        let rec = sqlx::query(r#"SELECT user_id FROM meal_plan_feeds WHERE token = $1"#)
+            .bind(token)
+            .fetch_optional(pool)
+            .await
+            .context("Failed to get calendar feed")?;
+
+        Ok(rec.map(|rec| rec.get("user_id")))
+    }
+}
@@ -3,3 +3,15 @@ pub mod recipe;
 pub mod user_details;
 pub mod profile_picture;
 pub mod recipe_thumbnails;
+pub mod ingredient;
+pub mod food;
+pub mod recipe_revision;
+pub mod collection;
+pub mod favourite;
+pub mod review;
+pub mod comment;
+pub mod cook_log;
+pub mod follow;
+pub mod notification;
+pub mod meal_plan;
+pub mod shopping_list;
@@ -0,0 +1,286 @@
+use anyhow::Context;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, prelude::FromRow, Pool, Postgres, Row};
+
+use crate::events::{publish, LiveEvent};
+
+use super::recipe::Poster;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationKind {
+    Comment,
+    Review,
+    Favourite,
+    Fork,
+    Follow,
+}
+
+impl NotificationKind {
+    pub const ALL: [NotificationKind; 5] = [
+        NotificationKind::Comment,
+        NotificationKind::Review,
+        NotificationKind::Favourite,
+        NotificationKind::Fork,
+        NotificationKind::Follow,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NotificationKind::Comment => "comment",
+            NotificationKind::Review => "review",
+            NotificationKind::Favourite => "favourite",
+            NotificationKind::Fork => "fork",
+            NotificationKind::Follow => "follow",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<NotificationKind> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name.trim().to_lowercase())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Notification {
+    pub id: i32,
+    pub kind: NotificationKind,
+    // Who did it, None if they have since deleted their account
+    pub actor: Option<Poster>,
+    pub recipe_id: Option<i32>,
+    // None if the recipe has since been deleted or hidden from the user
+    pub recipe_title: Option<String>,
+    pub comment_id: Option<i32>,
+    pub read: bool,
+    pub date_created: chrono::DateTime<Utc>,
+}
+
+impl FromRow<'_, PgRow> for Notification {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let kind: String = row.try_get("kind")?;
+        let kind = NotificationKind::from_name(&kind).ok_or(sqlx::Error::ColumnDecode {
+            index: "kind".to_string(),
+            source: format!("Unknown notification kind: {}", kind).into(),
+        })?;
+
+        let uid: Option<i32> = row.get("uid");
+        let username: Option<String> = row.get("username");
+
+        Ok(Notification {
+            id: row.get("id"),
+            kind,
+            actor: uid.zip(username).map(|(uid, username)| Poster {
+                uid,
+                username,
+                picture: row.try_get("picture_path").unwrap_or(None),
+            }),
+            recipe_id: row.get("recipe_id"),
+            recipe_title: row.get("recipe_title"),
+            comment_id: row.get("comment_id"),
+            read: row.get("read"),
+            date_created: row.get("date_created"),
+        })
+    }
+}
+
+impl Notification {
+    // Tells a user someone did something to them or their recipe, and pushes it to them if
+    // they are connected. Nothing is sent for things users do to themselves, or if they have
+    // turned that kind of notification off
+    pub async fn notify(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        kind: NotificationKind,
+        actor_id: i32,
+        recipe_id: Option<i32>,
+        comment_id: Option<i32>,
+    ) -> Result<(), anyhow::Error> {
+        let mut tx = pool.begin().await?;
+        let rec = sqlx::query(
+            r#"
+            INSERT INTO notifications (user_id, kind, actor_id, recipe_id, comment_id)
+            SELECT $1, $2, $3, $4, $5
+                WHERE $1 <> $3 AND NOT EXISTS (
+                    SELECT 1 FROM notification_opt_outs WHERE user_id = $1 AND kind = $2
+                )
+            RETURNING id"#,
+        )
+        .bind(user_id)
+        .bind(kind.name())
+        .bind(actor_id)
+        .bind(recipe_id)
+        .bind(comment_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .context(format!("Failed to notify user with id: {}", user_id))?;
+
+        let Some(rec) = rec else {
+            tx.rollback().await?;
+
+            return Ok(());
+        };
+
+        let notification = sqlx::query_as::<_, Notification>(
+            r#"
+            SELECT n.id, n.kind, n.recipe_id, n.comment_id, n.read, n.date_created, CASE WHEN r.user_id = n.user_id OR r.visibility IN ('public', 'unlisted') OR r.publish_at <= CURRENT_TIMESTAMP
+                THEN r.recipe ->> 'title' END AS recipe_title, u.uid, u.username, pp.picture_path FROM notifications n
+            LEFT OUTER JOIN users u
+                ON u.uid = n.actor_id
+            LEFT OUTER JOIN profile_pictures pp
+                ON u.uid = pp.user_id
+            LEFT OUTER JOIN recipes r
+                ON r.id = n.recipe_id
+                    WHERE n.id = $1"#,
+        )
+        .bind(rec.get::<i32, _>("id"))
+        .fetch_one(&mut *tx)
+        .await
+        .context(format!("Failed to notify user with id: {}", user_id))?;
+
+        publish(
+            &mut tx,
+            &LiveEvent::Notification {
+                user_id,
+                notification,
+            },
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // Newest first
+    pub async fn get_for_user(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        unread_only: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<Notification>, anyhow::Error> {
+        let notifications = sqlx::query_as::<_, Notification>(
+            r#"
+            SELECT n.id, n.kind, n.recipe_id, n.comment_id, n.read, n.date_created, CASE WHEN r.user_id = n.user_id OR r.visibility IN ('public', 'unlisted') OR r.publish_at <= CURRENT_TIMESTAMP
+                THEN r.recipe ->> 'title' END AS recipe_title, u.uid, u.username, pp.picture_path FROM notifications n
+            LEFT OUTER JOIN users u
+                ON u.uid = n.actor_id
+            LEFT OUTER JOIN profile_pictures pp
+                ON u.uid = pp.user_id
+            LEFT OUTER JOIN recipes r
+                ON r.id = n.recipe_id
+                    WHERE n.user_id = $1 AND (NOT $2 OR NOT n.read)
+                    ORDER BY n.id DESC LIMIT $3 OFFSET $4"#,
+        )
+        .bind(user_id)
+        .bind(unread_only)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get notifications of user with id: {}",
+            user_id
+        ))?;
+
+        Ok(notifications)
+    }
+
+    pub async fn count_unread(pool: &Pool<Postgres>, user_id: i32) -> anyhow::Result<i64> {
+        let row = sqlx::query(
+            r#"SELECT COUNT(*) AS unread FROM notifications WHERE user_id = $1 AND NOT read"#,
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(row.get("unread"))
+    }
+
+    // Returns false if the user has no notification with that id
+    pub async fn mark_read(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        notification_id: i32,
+    ) -> Result<bool, anyhow::Error> {
+        let result =
+            sqlx::query(r#"UPDATE notifications SET read = true WHERE id = $1 AND user_id = $2"#)
+                .bind(notification_id)
+                .bind(user_id)
+                .execute(pool)
+                .await
+                .context(format!(
+                    "Failed to mark notification with id: {} as read",
+                    notification_id
+                ))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Returns how many were marked
+    pub async fn mark_all_read(pool: &Pool<Postgres>, user_id: i32) -> anyhow::Result<u64> {
+        let result =
+            sqlx::query(r#"UPDATE notifications SET read = true WHERE user_id = $1 AND NOT read"#)
+                .bind(user_id)
+                .execute(pool)
+                .await
+                .context(format!(
+                    "Failed to mark notifications of user with id: {} as read",
+                    user_id
+                ))?;
+
+        Ok(result.rows_affected())
+    }
+
+    // The kinds of notification the user has turned off
+    pub async fn get_opt_outs(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+    ) -> Result<Vec<NotificationKind>, anyhow::Error> {
+        let rows = sqlx::query(r#"SELECT kind FROM notification_opt_outs WHERE user_id = $1"#)
+            .bind(user_id)
+            .fetch_all(pool)
+            .await
+            .context(format!(
+                "Failed to get notification preferences of user with id: {}",
+                user_id
+            ))?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| NotificationKind::from_name(row.get("kind")))
+            .collect())
+    }
+
+    pub async fn set_opt_outs(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        opt_outs: &[NotificationKind],
+    ) -> Result<(), anyhow::Error> {
+        let kinds: Vec<&str> = opt_outs.iter().map(|kind| kind.name()).collect();
+
+        let mut tx = pool.begin().await?;
+        sqlx::query(r#"DELETE FROM notification_opt_outs WHERE user_id = $1"#)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"INSERT INTO notification_opt_outs (user_id, kind)
+            SELECT $1, kind FROM unnest($2::text[]) kind
+                ON CONFLICT DO NOTHING"#,
+        )
+        .bind(user_id)
+        .bind(&kinds)
+        .execute(&mut *tx)
+        .await
+        .context(format!(
+            "Failed to set notification preferences of user with id: {}",
+            user_id
+        ))?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
@@ -1,12 +1,23 @@
 use anyhow::Context;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use sqlx::{prelude::FromRow, Pool, Postgres, Row};
+use sqlx::{postgres::PgRow, prelude::FromRow, types::Json, PgConnection, Pool, Postgres, Row};
+
+use crate::{
+    events::{publish, LiveEvent},
+    helpers::escape_html,
+    recipe_io::{
+        dietary::{Dietary, DietaryFilter},
+        RecipeFileJson,
+    },
+};
+
+use super::{ingredient::Ingredient, recipe_revision::RecipeRevision, review::RatingSummary};
 
 #[derive(Serialize, Deserialize, FromRow)]
 pub struct Recipe {
     pub id: i32,
-    pub recipe_file_path: String,
+    pub recipe: Json<RecipeFileJson>,
     pub user_id: i32,
     pub date_created: chrono::DateTime<Utc>,
 }
@@ -18,14 +29,157 @@ pub struct Poster {
     pub picture: Option<String>,
 }
 
+// Where a forked recipe was adapted from
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecipeParent {
+    // None once the parent recipe has been deleted or hidden, the author is still credited
+    pub id: Option<i32>,
+    pub title: Option<String>,
+    // None if the author has since deleted their account
+    pub author: Option<Poster>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    // Still being written, only the poster can see it
+    Draft,
+    // Finished but only the poster can see it
+    Private,
+    // Anyone with the link can see it, but it isn't listed
+    Unlisted,
+    Public,
+}
+
+impl Visibility {
+    pub const ALL: [Visibility; 4] = [
+        Visibility::Draft,
+        Visibility::Private,
+        Visibility::Unlisted,
+        Visibility::Public,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Visibility::Draft => "draft",
+            Visibility::Private => "private",
+            Visibility::Unlisted => "unlisted",
+            Visibility::Public => "public",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Visibility> {
+        Self::ALL
+            .into_iter()
+            .find(|visibility| visibility.name() == name.trim().to_lowercase())
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 // A recipe struct without user id
 pub struct RecipeWithPoster {
     pub poster: Poster,
     pub id: i32,
-    pub recipe_file_path: String,
+    pub recipe: RecipeFileJson,
     pub date_created: chrono::DateTime<Utc>,
     pub thumbnail: Option<String>,
+    pub dietary: Dietary,
+    // Goes up by one on every save, used to catch edits made to an older version
+    pub revision: i32,
+    pub visibility: Visibility,
+    // When the recipe is scheduled to be published, None once it has been
+    pub publish_at: Option<chrono::DateTime<Utc>>,
+    // How many users have favourited the recipe, and whether the viewer is one of them
+    pub favourites: i64,
+    pub favourited: bool,
+    pub rating: RatingSummary,
+}
+
+impl RecipeWithPoster {
+    // Drafts and private recipes can only be seen by their poster
+    pub fn can_view(&self, viewer: Option<i32>) -> bool {
+        viewer == Some(self.poster.uid)
+            || matches!(self.visibility, Visibility::Public | Visibility::Unlisted)
+    }
+}
+
+impl FromRow<'_, PgRow> for RecipeWithPoster {
+    // We use .get on the not null columns as every recipe must have one of these values, the
+    // recipe body is decoded with try_get as it can fail if the stored json is malformed
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let recipe: Json<RecipeFileJson> = row.try_get("recipe")?;
+        let allergens: Vec<String> = row.try_get("allergens")?;
+        let diets: Vec<String> = row.try_get("diets")?;
+        let visibility: String = row.try_get("visibility")?;
+        let publish_at: Option<chrono::DateTime<Utc>> = row.try_get("publish_at")?;
+
+        // A scheduled recipe is public as soon as its time has passed
+        let (visibility, publish_at) = match publish_at {
+            Some(at) if at <= Utc::now() => (Visibility::Public, None),
+            at => (
+                Visibility::from_name(&visibility).unwrap_or(Visibility::Private),
+                at,
+            ),
+        };
+
+        Ok(RecipeWithPoster {
+            poster: Poster {
+                uid: row.get("uid"),
+                username: row.get("username"),
+                picture: row.try_get("picture_path").unwrap_or(None),
+            },
+            id: row.get("id"),
+            recipe: recipe.0,
+            date_created: row.get("date_created"),
+            thumbnail: row.try_get("thumbnail_path").unwrap_or(None),
+            dietary: Dietary::from_names(&allergens, &diets),
+            revision: row.get("revision"),
+            visibility,
+            publish_at,
+            favourites: row.get("favourites"),
+            favourited: row.get("favourited"),
+            rating: RatingSummary {
+                average: row.get("rating"),
+                count: row.get("ratings"),
+            },
+        })
+    }
+}
+
+// Private use characters, they can't be typed into a recipe so they are safe to mark
+// highlights with before the text is escaped
+const HIGHLIGHT_START: char = '\u{E000}';
+const HIGHLIGHT_STOP: char = '\u{E001}';
+
+#[derive(Serialize, Deserialize)]
+pub struct RecipeSearchResult {
+    pub recipe: RecipeWithPoster,
+    pub rank: f32,
+    // Html escaped text with the matched words wrapped in <mark></mark>
+    pub title_highlight: String,
+    pub snippet: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RecipeIngredientMatch {
+    pub recipe: RecipeWithPoster,
+    // How many of the recipe's ingredients are covered by what the user has
+    pub covered: i64,
+    pub total: i64,
+    pub missing: Vec<String>,
+}
+
+// What get_by_ingredients searches for, ingredients are given by name
+pub struct IngredientSearch {
+    pub have: Vec<String>,
+    // Recipes must use every one of these
+    pub include: Vec<String>,
+    // Recipes must not use any of these
+    pub exclude: Vec<String>,
+    pub filter: DietaryFilter,
+    pub offset: u32,
+    pub limit: u32,
+    pub viewer: Option<i32>,
 }
 
 impl Recipe {
@@ -56,133 +210,550 @@ impl Recipe {
 
     pub async fn get_paginated_recipes_with_poster(
         pool: &Pool<Postgres>,
+        filter: &DietaryFilter,
         offset: u32,
         limit: u32,
+        viewer: Option<i32>,
     ) -> Result<Vec<RecipeWithPoster>, anyhow::Error> {
-        let rows = sqlx::query(
+        let recipes = sqlx::query_as::<_, RecipeWithPoster>(
             r#"
-            SELECT u.uid, u.username, pp.picture_path, r.id, r.recipe_file_path, r.date_created, rt.thumbnail_path FROM users u
+            SELECT u.uid, u.username, pp.picture_path, r.id, r.recipe, r.date_created, rt.thumbnail_path, r.allergens, r.diets, r.revision, r.visibility, r.publish_at,
+                stats.favourites, stats.rating, stats.ratings,
+                EXISTS (SELECT 1 FROM favourites f WHERE f.recipe_id = r.id AND f.user_id = $5) AS favourited
+            FROM users u
             RIGHT OUTER JOIN recipes r
                 ON u.uid = r.user_id
             LEFT OUTER JOIN user_details ud
                 ON u.uid = ud.user_id
             LEFT OUTER JOIN profile_pictures pp
                 ON u.uid = pp.user_id
+            INNER JOIN recipe_stats stats
+                ON stats.recipe_id = r.id
             LEFT OUTER JOIN recipe_thumbnails rt
                 ON rt.recipe_id = r.id
-                    ORDER BY r.id LIMIT 10 OFFSET 0;"#,
+                    WHERE r.diets @> $3 AND NOT r.allergens && $4
+                    AND (r.visibility = 'public' OR r.publish_at <= CURRENT_TIMESTAMP)
+                    ORDER BY r.id LIMIT $1 OFFSET $2;"#,
         )
         .bind(limit as i64)
         .bind(offset as i64)
+        .bind(filter.diet_names())
+        .bind(filter.allergen_names())
+        .bind(viewer)
         .fetch_all(pool)
         .await?;
 
-        // We use .get as this should never fail, every recipe must have one of these values as
-        // they are set to not null in the db
-        let recipes: Vec<RecipeWithPoster> = rows
-            .iter()
-            .map(|row| RecipeWithPoster {
-                poster: Poster {
-                    uid: row.get("uid"),
-                    username: row.get("username"),
-                    picture: row.try_get("picture_path").unwrap_or(None),
-                },
-                id: row.get("id"),
-                recipe_file_path: row.get("recipe_file_path"),
-                date_created: row.get("date_created"),
-                thumbnail: row.try_get("thumbnail_path").unwrap_or(None),
-            })
-            .collect();
-
         Ok(recipes)
     }
 
     pub async fn get_by_poster(
         pool: &Pool<Postgres>,
         user_id: i32,
+        filter: &DietaryFilter,
+        viewer: Option<i32>,
     ) -> anyhow::Result<Vec<RecipeWithPoster>> {
-        let rows = sqlx::query(r#"
-        SELECT u.uid, u.username, pp.picture_path, r.id, r.recipe_file_path, r.date_created, rt.thumbnail_path FROM users u
+        let recipes = sqlx::query_as::<_, RecipeWithPoster>(r#"
+        SELECT u.uid, u.username, pp.picture_path, r.id, r.recipe, r.date_created, rt.thumbnail_path, r.allergens, r.diets, r.revision, r.visibility, r.publish_at,
+                stats.favourites, stats.rating, stats.ratings,
+                EXISTS (SELECT 1 FROM favourites f WHERE f.recipe_id = r.id AND f.user_id = $4) AS favourited
+            FROM users u
             RIGHT OUTER JOIN recipes r
                 ON u.uid = r.user_id
             LEFT OUTER JOIN user_details ud
                 ON u.uid = ud.user_id
             LEFT OUTER JOIN profile_pictures pp
                 ON u.uid = pp.user_id
+            INNER JOIN recipe_stats stats
+                ON stats.recipe_id = r.id
             LEFT OUTER JOIN recipe_thumbnails rt
                 ON rt.recipe_id = r.id
-                    WHERE u.uid = $1;"#)
+                    WHERE u.uid = $1 AND r.diets @> $2 AND NOT r.allergens && $3
+                    AND (r.user_id = $4 OR r.visibility = 'public' OR r.publish_at <= CURRENT_TIMESTAMP);"#)
             .bind(user_id)
+            .bind(filter.diet_names())
+            .bind(filter.allergen_names())
+            .bind(viewer)
             .fetch_all(pool)
             .await?;
 
-        Ok(rows
-            .iter()
-            .map(|row| RecipeWithPoster {
-                poster: Poster {
-                    uid: row.get("uid"),
-                    username: row.get("username"),
-                    picture: row.try_get("picture_path").unwrap_or(None),
-                },
-                id: row.get("id"),
-                recipe_file_path: row.get("recipe_file_path"),
-                date_created: row.get("date_created"),
-                thumbnail: row.try_get("thumbnail_path").unwrap_or(None),
-            })
-            .collect())
+        Ok(recipes)
     }
 
     pub async fn get_by_id(
         pool: &Pool<Postgres>,
         recipe_id: i32,
+        viewer: Option<i32>,
     ) -> Result<RecipeWithPoster, anyhow::Error> {
-        let row = sqlx::query(r#"
-        SELECT u.uid, u.username, pp.picture_path, r.id, r.recipe_file_path, r.date_created, rt.thumbnail_path FROM users u
+        let recipe = sqlx::query_as::<_, RecipeWithPoster>(r#"
+        SELECT u.uid, u.username, pp.picture_path, r.id, r.recipe, r.date_created, rt.thumbnail_path, r.allergens, r.diets, r.revision, r.visibility, r.publish_at,
+                stats.favourites, stats.rating, stats.ratings,
+                EXISTS (SELECT 1 FROM favourites f WHERE f.recipe_id = r.id AND f.user_id = $2) AS favourited
+            FROM users u
             RIGHT OUTER JOIN recipes r
                 ON u.uid = r.user_id
             LEFT OUTER JOIN user_details ud
                 ON u.uid = ud.user_id
             LEFT OUTER JOIN profile_pictures pp
                 ON u.uid = pp.user_id
+            INNER JOIN recipe_stats stats
+                ON stats.recipe_id = r.id
             LEFT OUTER JOIN recipe_thumbnails rt
                 ON rt.recipe_id = r.id
                     WHERE r.id = $1;"#)
             .bind(recipe_id)
+            .bind(viewer)
             .fetch_one(pool)
             .await?;
 
-        let recipes = RecipeWithPoster {
-            poster: Poster {
-                uid: row.get("uid"),
-                username: row.get("username"),
-                picture: row.try_get("picture_path").unwrap_or(None),
-            },
-            id: row.get("id"),
-            recipe_file_path: row.get("recipe_file_path"),
-            date_created: row.get("date_created"),
-            thumbnail: row.try_get("thumbnail_path").unwrap_or(None),
-        };
+        Ok(recipe)
+    }
 
-        Ok(recipes)
+    // Leaves out the ids of recipes that don't exist or the viewer can't see
+    pub async fn get_viewable_ids(
+        pool: &Pool<Postgres>,
+        recipe_ids: &[i32],
+        viewer: i32,
+    ) -> Result<Vec<i32>, anyhow::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT r.id FROM recipes r
+                WHERE r.id = ANY($1)
+                    AND (r.user_id = $2 OR r.visibility IN ('public', 'unlisted') OR r.publish_at <= CURRENT_TIMESTAMP)"#,
+        )
+        .bind(recipe_ids)
+        .bind(viewer)
+        .fetch_all(pool)
+        .await
+        .context("Failed to get viewable recipes")?;
+
+        Ok(rows.iter().map(|row| row.get("id")).collect())
     }
 
+    // Full text search over the title, ingredients, description and steps, best match first
+    pub async fn search(
+        pool: &Pool<Postgres>,
+        query: &str,
+        filter: &DietaryFilter,
+        offset: u32,
+        limit: u32,
+        viewer: Option<i32>,
+    ) -> Result<Vec<RecipeSearchResult>, anyhow::Error> {
+        let title_options = format!(
+            "StartSel={}, StopSel={}, HighlightAll=true",
+            HIGHLIGHT_START, HIGHLIGHT_STOP
+        );
+        let snippet_options = format!(
+            r#"StartSel={}, StopSel={}, MaxFragments=2, MinWords=8, MaxWords=20, FragmentDelimiter=" … ""#,
+            HIGHLIGHT_START, HIGHLIGHT_STOP
+        );
+
+        let rows = sqlx::query(
+            r#"
+            SELECT u.uid, u.username, pp.picture_path, r.id, r.recipe, r.date_created, rt.thumbnail_path, r.allergens, r.diets, r.revision, r.visibility, r.publish_at,
+                stats.favourites, stats.rating, stats.ratings,
+                EXISTS (SELECT 1 FROM favourites f WHERE f.recipe_id = r.id AND f.user_id = $8) AS favourited,
+                ts_rank(r.search, query) AS rank,
+                ts_headline('english', r.recipe ->> 'title', query, $2) AS title_highlight,
+                ts_headline('english', concat_ws(' ',
+                    r.recipe ->> 'description',
+                    (SELECT string_agg(ingredient, ', ')
+                        FROM jsonb_array_elements_text(jsonb_path_query_array(r.recipe, '$.ingredients[*].ingredient')) ingredient),
+                    (SELECT string_agg(step, ' ')
+                        FROM jsonb_array_elements_text(jsonb_path_query_array(r.recipe, '$.steps[*].step_details')) step)
+                ), query, $3) AS snippet
+            FROM users u
+            RIGHT OUTER JOIN recipes r
+                ON u.uid = r.user_id
+            CROSS JOIN websearch_to_tsquery('english', $1) query
+            LEFT OUTER JOIN profile_pictures pp
+                ON u.uid = pp.user_id
+            INNER JOIN recipe_stats stats
+                ON stats.recipe_id = r.id
+            LEFT OUTER JOIN recipe_thumbnails rt
+                ON rt.recipe_id = r.id
+                    WHERE r.search @@ query AND r.diets @> $6 AND NOT r.allergens && $7
+                    AND (r.visibility = 'public' OR r.publish_at <= CURRENT_TIMESTAMP)
+                    ORDER BY rank DESC, r.id LIMIT $4 OFFSET $5;"#,
+        )
+        .bind(query)
+        .bind(title_options)
+        .bind(snippet_options)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .bind(filter.diet_names())
+        .bind(filter.allergen_names())
+        .bind(viewer)
+        .fetch_all(pool)
+        .await?;
+
+        let mut results = Vec::new();
+        for row in rows.iter() {
+            let title_highlight: String = row.try_get("title_highlight")?;
+            let snippet: String = row.try_get("snippet")?;
+
+            results.push(RecipeSearchResult {
+                recipe: RecipeWithPoster::from_row(row)?,
+                rank: row.try_get("rank")?,
+                title_highlight: highlight_to_html(&title_highlight),
+                snippet: highlight_to_html(&snippet),
+            });
+        }
+
+        Ok(results)
+    }
+
+    // Ranks recipes by how many of their ingredients are covered by the ones passed in.
+    // Every recipe must contain all of the included ingredients and none of the excluded ones.
+    // Names are matched as english words against the ingredient catalogue and its aliases, so
+    // "garlic" covers "garlic clove", and recipes are then found through their linked
+    // ingredients rather than by reading every recipe body
+    pub async fn get_by_ingredients(
+        pool: &Pool<Postgres>,
+        search: &IngredientSearch,
+    ) -> Result<Vec<RecipeIngredientMatch>, anyhow::Error> {
+        let rows = sqlx::query(
+            r#"
+            WITH resolved AS (
+                SELECT n.name, i.id FROM unnest($1::text[] || $3::text[]) n(name)
+                INNER JOIN ingredients i
+                    ON to_tsvector('english', i.name) @@ plainto_tsquery('english', n.name)
+                UNION
+                SELECT n.name, a.ingredient_id AS id FROM unnest($1::text[] || $3::text[]) n(name)
+                INNER JOIN ingredient_aliases a
+                    ON to_tsvector('english', a.alias) @@ plainto_tsquery('english', n.name)
+            ), have AS (
+                SELECT DISTINCT id FROM resolved WHERE name = ANY($1)
+            ), matched AS (
+                SELECT ri.recipe_id AS id,
+                    count(*) AS total,
+                    count(h.id) AS covered,
+                    coalesce(jsonb_agg(r.recipe -> 'ingredients' -> ri.position ->> 'ingredient' ORDER BY ri.position)
+                        FILTER (WHERE h.id IS NULL), '[]') AS missing
+                FROM recipe_ingredients ri
+                INNER JOIN recipes r
+                    ON r.id = ri.recipe_id
+                LEFT OUTER JOIN have h
+                    ON h.id = ri.ingredient_id
+                    WHERE ri.recipe_id IN (
+                        SELECT recipe_id FROM recipe_ingredients WHERE ingredient_id IN (SELECT id FROM have)
+                    )
+                    GROUP BY ri.recipe_id
+            )
+            SELECT u.uid, u.username, pp.picture_path, r.id, r.recipe, r.date_created, rt.thumbnail_path, r.allergens, r.diets, r.revision, r.visibility, r.publish_at,
+                stats.favourites, stats.rating, stats.ratings,
+                EXISTS (SELECT 1 FROM favourites f WHERE f.recipe_id = r.id AND f.user_id = $8) AS favourited,
+                m.covered, m.total, m.missing
+            FROM users u
+            RIGHT OUTER JOIN recipes r
+                ON u.uid = r.user_id
+            INNER JOIN matched m
+                ON m.id = r.id
+            LEFT OUTER JOIN profile_pictures pp
+                ON u.uid = pp.user_id
+            INNER JOIN recipe_stats stats
+                ON stats.recipe_id = r.id
+            LEFT OUTER JOIN recipe_thumbnails rt
+                ON rt.recipe_id = r.id
+                    WHERE r.diets @> $6 AND NOT r.allergens && $7
+                    AND (r.visibility = 'public' OR r.publish_at <= CURRENT_TIMESTAMP)
+                    AND NOT EXISTS (
+                        SELECT 1 FROM unnest($2::text[]) n(name)
+                        WHERE NOT EXISTS (
+                            SELECT 1 FROM recipe_ingredients ri
+                            INNER JOIN resolved rs
+                                ON rs.id = ri.ingredient_id
+                                    WHERE ri.recipe_id = r.id AND rs.name = n.name
+                        )
+                    )
+                    AND NOT EXISTS (
+                        SELECT 1 FROM recipe_ingredients ri
+                        INNER JOIN resolved rs
+                            ON rs.id = ri.ingredient_id
+                                WHERE ri.recipe_id = r.id AND rs.name = ANY($3)
+                    )
+                    ORDER BY m.covered DESC, m.total - m.covered, r.id LIMIT $4 OFFSET $5;"#,
+        )
+        .bind(&search.have)
+        .bind(&search.include)
+        .bind(&search.exclude)
+        .bind(search.limit as i64)
+        .bind(search.offset as i64)
+        .bind(search.filter.diet_names())
+        .bind(search.filter.allergen_names())
+        .bind(search.viewer)
+        .fetch_all(pool)
+        .await?;
+
+        let mut matches = Vec::new();
+        for row in rows.iter() {
+            let missing: Json<Vec<String>> = row.try_get("missing")?;
+
+            matches.push(RecipeIngredientMatch {
+                recipe: RecipeWithPoster::from_row(row)?,
+                covered: row.try_get("covered")?,
+                total: row.try_get("total")?,
+                missing: missing.0,
+            });
+        }
+
+        Ok(matches)
+    }
+
+    // A recipe forked from another one passes the id of its parent, the parent's author is
+    // recorded with it so they stay credited if the parent is deleted
     pub async fn insert(
         pool: &Pool<Postgres>,
-        file_path: String,
+        recipe: &RecipeFileJson,
         user_id: i32,
+        parent_id: Option<i32>,
+        visibility: Visibility,
     ) -> Result<i32, anyhow::Error> {
+        let mut tx = pool.begin().await?;
         let rec = sqlx::query(
-            r#"INSERT INTO recipes (recipe_file_path, user_id)
-        VALUES ( $1, $2 ) RETURNING id"#,
+            r#"INSERT INTO recipes (recipe, user_id, parent_id, parent_user_id, visibility, published_at)
+        VALUES ( $1, $2, $3, (SELECT user_id FROM recipes WHERE id = $3), $4,
+            CASE WHEN $4 = 'public' THEN CURRENT_TIMESTAMP END ) RETURNING id"#,
         )
-        .bind(file_path)
+        .bind(Json(recipe))
         .bind(user_id)
-        .fetch_one(pool)
+        .bind(parent_id)
+        .bind(visibility.name())
+        .fetch_one(&mut *tx)
         .await?;
 
         // Returns failed insert with message
         let recipe_id: i32 = rec.try_get("id").context("Failed to get recipe id")?;
 
+        Ingredient::link_recipe(&mut tx, recipe_id, &recipe.ingredients).await?;
+        Self::classify(&mut tx, recipe_id, recipe).await?;
+        RecipeRevision::insert(&mut tx, recipe_id, 1, recipe, user_id).await?;
+        tx.commit().await?;
+
         Ok(recipe_id)
     }
+
+    // Saves the recipe as a new revision by user_id and returns its number. If an expected
+    // revision is given and the recipe has moved on since, nothing is saved and None is returned
+    pub async fn update(
+        pool: &Pool<Postgres>,
+        recipe_id: i32,
+        recipe: &RecipeFileJson,
+        user_id: i32,
+        expected_revision: Option<i32>,
+    ) -> Result<Option<i32>, anyhow::Error> {
+        let mut tx = pool.begin().await?;
+        let rec = sqlx::query(
+            r#"UPDATE recipes SET recipe = $1, revision = revision + 1
+            WHERE id = $2 AND ($3::integer IS NULL OR revision = $3) RETURNING revision"#,
+        )
+        .bind(Json(recipe))
+        .bind(recipe_id)
+        .bind(expected_revision)
+        .fetch_optional(&mut *tx)
+        .await
+        .context(format!("Failed to update recipe with id: {}", recipe_id))?;
+
+        let Some(rec) = rec else {
+            tx.rollback().await?;
+
+            return Ok(None);
+        };
+        let revision: i32 = rec.get("revision");
+
+        Ingredient::link_recipe(&mut tx, recipe_id, &recipe.ingredients).await?;
+        Self::classify(&mut tx, recipe_id, recipe).await?;
+        RecipeRevision::insert(&mut tx, recipe_id, revision, recipe, user_id).await?;
+        publish(
+            &mut tx,
+            &LiveEvent::RecipeUpdated {
+                recipe_id,
+                revision,
+            },
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(Some(revision))
+    }
+
+    // Stores the allergens and diets of a recipe from its linked ingredients, so listings can
+    // filter on them
+    pub async fn classify(
+        conn: &mut PgConnection,
+        recipe_id: i32,
+        recipe: &RecipeFileJson,
+    ) -> Result<Dietary, anyhow::Error> {
+        let profiles = Ingredient::get_profiles_for_recipe(&mut *conn, recipe_id).await?;
+        let dietary = recipe.dietary(&profiles);
+
+        sqlx::query(r#"UPDATE recipes SET allergens = $2, diets = $3 WHERE id = $1"#)
+            .bind(recipe_id)
+            .bind(dietary.allergen_names())
+            .bind(dietary.diet_names())
+            .execute(&mut *conn)
+            .await
+            .context(format!("Failed to classify recipe with id: {}", recipe_id))?;
+
+        Ok(dietary)
+    }
+
+    // Classifies every recipe again, or only the ones using an ingredient if one is given,
+    // for when the profile of an ingredient changes. Returns how many were classified
+    pub async fn reclassify(
+        conn: &mut PgConnection,
+        ingredient_id: Option<i32>,
+    ) -> Result<usize, anyhow::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, recipe FROM recipes
+                WHERE $1::integer IS NULL
+                    OR id IN (SELECT recipe_id FROM recipe_ingredients WHERE ingredient_id = $1)
+                ORDER BY id"#,
+        )
+        .bind(ingredient_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        for row in rows.iter() {
+            let recipe: Json<RecipeFileJson> = row.try_get("recipe")?;
+
+            Self::classify(&mut *conn, row.get("id"), &recipe).await?;
+        }
+
+        Ok(rows.len())
+    }
+
+    // Setting the visibility cancels any scheduled publish, unless a new time is given.
+    // Publishing straight away records when it happened, or keeps the time if it already had.
+    // published_at follows along, it is when the recipe went or goes public and orders the feed
+    pub async fn set_visibility(
+        pool: &Pool<Postgres>,
+        recipe_id: i32,
+        visibility: Visibility,
+        publish_at: Option<chrono::DateTime<Utc>>,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+            UPDATE recipes SET visibility = $2,
+                publish_at = CASE WHEN $2 = 'public' AND $3::timestamptz IS NULL
+                    THEN least(coalesce(publish_at, CURRENT_TIMESTAMP), CURRENT_TIMESTAMP)
+                    ELSE $3 END,
+                published_at = CASE WHEN $2 = 'public' AND $3::timestamptz IS NULL
+                    THEN least(coalesce(published_at, CURRENT_TIMESTAMP), CURRENT_TIMESTAMP)
+                    ELSE $3 END
+            WHERE id = $1"#,
+        )
+        .bind(recipe_id)
+        .bind(visibility.name())
+        .bind(publish_at)
+        .execute(pool)
+        .await
+        .context(format!(
+            "Failed to set the visibility of recipe with id: {}",
+            recipe_id
+        ))?;
+
+        Ok(())
+    }
+
+    pub async fn get_parent(
+        pool: &Pool<Postgres>,
+        recipe_id: i32,
+    ) -> Result<Option<RecipeParent>, anyhow::Error> {
+        let row = sqlx::query(
+            r#"
+            SELECT p.id AS parent_id, p.recipe ->> 'title' AS title, u.uid, u.username, pp.picture_path FROM recipes r
+            LEFT OUTER JOIN recipes p
+                ON p.id = r.parent_id
+                    AND (p.visibility IN ('public', 'unlisted') OR p.publish_at <= CURRENT_TIMESTAMP)
+            LEFT OUTER JOIN users u
+                ON u.uid = r.parent_user_id
+            LEFT OUTER JOIN profile_pictures pp
+                ON pp.user_id = u.uid
+                    WHERE r.id = $1 AND (r.parent_id IS NOT NULL OR r.parent_user_id IS NOT NULL)"#,
+        )
+        .bind(recipe_id)
+        .fetch_optional(pool)
+        .await
+        .context(format!(
+            "Failed to get the parent of recipe with id: {}",
+            recipe_id
+        ))?;
+
+        Ok(row.map(|row| {
+            let uid: Option<i32> = row.get("uid");
+            let username: Option<String> = row.get("username");
+
+            RecipeParent {
+                id: row.get("parent_id"),
+                title: row.get("title"),
+                author: uid.zip(username).map(|(uid, username)| Poster {
+                    uid,
+                    username,
+                    picture: row.get("picture_path"),
+                }),
+            }
+        }))
+    }
+
+    pub async fn count_forks(pool: &Pool<Postgres>, recipe_id: i32) -> anyhow::Result<i64> {
+        let row = sqlx::query(r#"SELECT COUNT(*) AS forks FROM recipes r
+            WHERE r.parent_id = $1 AND (r.visibility = 'public' OR r.publish_at <= CURRENT_TIMESTAMP)"#)
+            .bind(recipe_id)
+            .fetch_one(pool)
+            .await?;
+
+        Ok(row.get("forks"))
+    }
+
+    pub async fn get_forks(
+        pool: &Pool<Postgres>,
+        recipe_id: i32,
+        offset: u32,
+        limit: u32,
+        viewer: Option<i32>,
+    ) -> Result<Vec<RecipeWithPoster>, anyhow::Error> {
+        let recipes = sqlx::query_as::<_, RecipeWithPoster>(
+            r#"
+            SELECT u.uid, u.username, pp.picture_path, r.id, r.recipe, r.date_created, rt.thumbnail_path, r.allergens, r.diets, r.revision, r.visibility, r.publish_at,
+                stats.favourites, stats.rating, stats.ratings,
+                EXISTS (SELECT 1 FROM favourites f WHERE f.recipe_id = r.id AND f.user_id = $4) AS favourited
+            FROM users u
+            RIGHT OUTER JOIN recipes r
+                ON u.uid = r.user_id
+            LEFT OUTER JOIN profile_pictures pp
+                ON u.uid = pp.user_id
+            INNER JOIN recipe_stats stats
+                ON stats.recipe_id = r.id
+            LEFT OUTER JOIN recipe_thumbnails rt
+                ON rt.recipe_id = r.id
+                    WHERE r.parent_id = $1 AND (r.visibility = 'public' OR r.publish_at <= CURRENT_TIMESTAMP)
+                    ORDER BY r.id LIMIT $2 OFFSET $3;"#,
+        )
+        .bind(recipe_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .bind(viewer)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(recipes)
+    }
+
+    pub async fn delete(pool: &Pool<Postgres>, recipe_id: i32) -> Result<(), anyhow::Error> {
+        // Thumbnail rows are removed by the cascade on recipe_thumbnails
+        sqlx::query(r#"DELETE FROM recipes WHERE id = $1"#)
+            .bind(recipe_id)
+            .execute(pool)
+            .await
+            .context(format!("Failed to delete recipe with id: {}", recipe_id))?;
+
+        Ok(())
+    }
+}
+
+fn highlight_to_html(highlight: &str) -> String {
+    escape_html(highlight)
+        .replace(HIGHLIGHT_START, "<mark>")
+        .replace(HIGHLIGHT_STOP, "</mark>")
 }
@@ -0,0 +1,136 @@
+use anyhow::Context;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, PgConnection, Pool, Postgres, Row};
+
+use crate::recipe_io::RecipeFileJson;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RevisionAuthor {
+    pub uid: i32,
+    pub username: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RecipeRevision {
+    pub recipe_id: i32,
+    pub revision: i32,
+    pub recipe: RecipeFileJson,
+    // None if the author has since deleted their account
+    pub author: Option<RevisionAuthor>,
+    pub date_created: chrono::DateTime<Utc>,
+}
+
+// A revision without its recipe body, for listing the history
+#[derive(Serialize, Deserialize)]
+pub struct RecipeRevisionSummary {
+    pub revision: i32,
+    pub title: String,
+    pub author: Option<RevisionAuthor>,
+    pub date_created: chrono::DateTime<Utc>,
+}
+
+impl RecipeRevision {
+    // Saves the recipe under the revision number it was given in the recipes table
+    pub async fn insert(
+        conn: &mut PgConnection,
+        recipe_id: i32,
+        revision: i32,
+        recipe: &RecipeFileJson,
+        user_id: i32,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"INSERT INTO recipe_revisions (recipe_id, revision, recipe, user_id)
+            VALUES ($1, $2, $3, $4)"#,
+        )
+        .bind(recipe_id)
+        .bind(revision)
+        .bind(Json(recipe))
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await
+        .context(format!(
+            "Failed to save revision of recipe with id: {}",
+            recipe_id
+        ))?;
+
+        Ok(())
+    }
+
+    // Newest first
+    pub async fn get_all(
+        pool: &Pool<Postgres>,
+        recipe_id: i32,
+    ) -> Result<Vec<RecipeRevisionSummary>, anyhow::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT rr.revision, rr.recipe ->> 'title' AS title, rr.date_created, u.uid, u.username FROM recipe_revisions rr
+            LEFT OUTER JOIN users u
+                ON u.uid = rr.user_id
+                    WHERE rr.recipe_id = $1
+                    ORDER BY rr.revision DESC"#,
+        )
+        .bind(recipe_id)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get revisions of recipe with id: {}",
+            recipe_id
+        ))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| RecipeRevisionSummary {
+                revision: row.get("revision"),
+                title: row.get("title"),
+                author: author_from_row(row),
+                date_created: row.get("date_created"),
+            })
+            .collect())
+    }
+
+    pub async fn get(
+        pool: &Pool<Postgres>,
+        recipe_id: i32,
+        revision: i32,
+    ) -> Result<Option<RecipeRevision>, anyhow::Error> {
+        let row = sqlx::query(
+            r#"
+            SELECT rr.recipe_id, rr.revision, rr.recipe, rr.date_created, u.uid, u.username FROM recipe_revisions rr
+            LEFT OUTER JOIN users u
+                ON u.uid = rr.user_id
+                    WHERE rr.recipe_id = $1 AND rr.revision = $2"#,
+        )
+        .bind(recipe_id)
+        .bind(revision)
+        .fetch_optional(pool)
+        .await
+        .context(format!(
+            "Failed to get revision {} of recipe with id: {}",
+            revision, recipe_id
+        ))?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let recipe: Json<RecipeFileJson> = row.try_get("recipe")?;
+        Ok(Some(RecipeRevision {
+            recipe_id: row.get("recipe_id"),
+            revision: row.get("revision"),
+            recipe: recipe.0,
+            author: author_from_row(&row),
+            date_created: row.get("date_created"),
+        }))
+    }
+}
+
+fn author_from_row(row: &sqlx::postgres::PgRow) -> Option<RevisionAuthor> {
+    let uid: Option<i32> = row.get("uid");
+    let username: Option<String> = row.get("username");
+
+    Some(RevisionAuthor {
+        uid: uid?,
+        username: username?,
+    })
+}
@@ -0,0 +1,120 @@
+use anyhow::Context;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, prelude::FromRow, Pool, Postgres, Row};
+
+use super::recipe::Poster;
+
+// The average rating of a recipe, None until someone rates it
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct RatingSummary {
+    pub average: Option<f64>,
+    pub count: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Review {
+    pub author: Poster,
+    pub recipe_id: i32,
+    pub rating: i16,
+    pub review: Option<String>,
+    pub date_created: chrono::DateTime<Utc>,
+    pub date_updated: chrono::DateTime<Utc>,
+}
+
+impl FromRow<'_, PgRow> for Review {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Review {
+            author: Poster {
+                uid: row.get("uid"),
+                username: row.get("username"),
+                picture: row.try_get("picture_path").unwrap_or(None),
+            },
+            recipe_id: row.get("recipe_id"),
+            rating: row.get("rating"),
+            review: row.get("review"),
+            date_created: row.get("date_created"),
+            date_updated: row.get("date_updated"),
+        })
+    }
+}
+
+impl Review {
+    // A user has one review per recipe, reviewing again replaces it. Returns true if the
+    // review is new
+    pub async fn insert_or_update(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        recipe_id: i32,
+        rating: i16,
+        review: &Option<String>,
+    ) -> Result<bool, anyhow::Error> {
+        let rec = sqlx::query(
+            r#"INSERT INTO reviews (user_id, recipe_id, rating, review)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id, recipe_id) DO UPDATE
+                SET rating = excluded.rating,
+                    review = excluded.review,
+                    date_updated = CURRENT_TIMESTAMP
+            RETURNING (xmax = 0) AS created"#,
+        )
+        .bind(user_id)
+        .bind(recipe_id)
+        .bind(rating)
+        .bind(review)
+        .fetch_one(pool)
+        .await
+        .context(format!("Failed to review recipe with id: {}", recipe_id))?;
+
+        Ok(rec.get("created"))
+    }
+
+    // Returns false if the user hadn't reviewed the recipe
+    pub async fn delete(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        recipe_id: i32,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(r#"DELETE FROM reviews WHERE user_id = $1 AND recipe_id = $2"#)
+            .bind(user_id)
+            .bind(recipe_id)
+            .execute(pool)
+            .await
+            .context(format!(
+                "Failed to delete review of recipe with id: {}",
+                recipe_id
+            ))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Most recently written or edited first
+    pub async fn get_for_recipe(
+        pool: &Pool<Postgres>,
+        recipe_id: i32,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<Review>, anyhow::Error> {
+        let reviews = sqlx::query_as::<_, Review>(
+            r#"
+            SELECT u.uid, u.username, pp.picture_path, rv.recipe_id, rv.rating, rv.review, rv.date_created, rv.date_updated FROM reviews rv
+            INNER JOIN users u
+                ON u.uid = rv.user_id
+            LEFT OUTER JOIN profile_pictures pp
+                ON u.uid = pp.user_id
+                    WHERE rv.recipe_id = $1
+                    ORDER BY rv.date_updated DESC, u.uid LIMIT $2 OFFSET $3"#,
+        )
+        .bind(recipe_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get reviews of recipe with id: {}",
+            recipe_id
+        ))?;
+
+        Ok(reviews)
+    }
+}
@@ -0,0 +1,251 @@
+use anyhow::Context;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, prelude::FromRow, types::Json, Pool, Postgres, Row};
+
+use crate::recipe_io::shopping::{Aisle, Quantity};
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct ShoppingList {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub date_created: chrono::DateTime<Utc>,
+    // How many items are on the list, and how many of them are checked off
+    pub items: i64,
+    pub checked: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ShoppingListItem {
+    pub id: i32,
+    // The catalogue entry the item was worked out from, None for items added by hand
+    pub ingredient_id: Option<i32>,
+    pub name: String,
+    // None for items added by hand without saying how much
+    pub quantity: Option<Quantity>,
+    // How the quantity reads, e.g. "1½ kg", filled in for API responses
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub quantity_display: Option<String>,
+    pub aisle: Aisle,
+    pub checked: bool,
+    // Added by hand rather than worked out from a recipe
+    pub manual: bool,
+}
+
+impl FromRow<'_, PgRow> for ShoppingListItem {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let quantity: Option<Json<Quantity>> = row.try_get("quantity")?;
+        let aisle: String = row.try_get("aisle")?;
+
+        Ok(ShoppingListItem {
+            id: row.get("id"),
+            ingredient_id: row.get("ingredient_id"),
+            name: row.get("name"),
+            quantity: quantity.map(|quantity| quantity.0),
+            quantity_display: None,
+            aisle: Aisle::from_name(&aisle).unwrap_or(Aisle::Other),
+            checked: row.get("checked"),
+            manual: row.get("manual"),
+        })
+    }
+}
+
+pub struct NewShoppingListItem {
+    pub ingredient_id: Option<i32>,
+    pub name: String,
+    pub quantity: Option<Quantity>,
+    pub aisle: Aisle,
+    pub manual: bool,
+}
+
+impl ShoppingList {
+    pub async fn insert(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        name: &str,
+        items: &[NewShoppingListItem],
+    ) -> Result<i32, anyhow::Error> {
+        let mut tx = pool.begin().await?;
+        let rec = sqlx::query(
+            r#"INSERT INTO shopping_lists (user_id, name) VALUES ($1, $2) RETURNING id"#,
+        )
+        .bind(user_id)
+        .bind(name)
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to create shopping list")?;
+        let list_id: i32 = rec.get("id");
+
+        for item in items {
+            sqlx::query(
+                r#"INSERT INTO shopping_list_items (list_id, ingredient_id, name, quantity, aisle, manual)
+                VALUES ($1, $2, $3, $4, $5, $6)"#,
+            )
+            .bind(list_id)
+            .bind(item.ingredient_id)
+            .bind(&item.name)
+            .bind(item.quantity.map(Json))
+            .bind(item.aisle.name())
+            .bind(item.manual)
+            .execute(&mut *tx)
+            .await
+            .context(format!(
+                "Failed to add item to shopping list with id: {}",
+                list_id
+            ))?;
+        }
+        tx.commit().await?;
+
+        Ok(list_id)
+    }
+
+    pub async fn get(
+        pool: &Pool<Postgres>,
+        list_id: i32,
+    ) -> Result<Option<ShoppingList>, anyhow::Error> {
+        let list = sqlx::query_as::<_, ShoppingList>(
+            r#"
+            SELECT sl.*,
+                (SELECT COUNT(*) FROM shopping_list_items sli WHERE sli.list_id = sl.id) AS items,
+                (SELECT COUNT(*) FROM shopping_list_items sli WHERE sli.list_id = sl.id AND sli.checked) AS checked
+            FROM shopping_lists sl
+                WHERE sl.id = $1"#,
+        )
+        .bind(list_id)
+        .fetch_optional(pool)
+        .await
+        .context(format!("Failed to get shopping list with id: {}", list_id))?;
+
+        Ok(list)
+    }
+
+    // Newest first
+    pub async fn get_by_user(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<ShoppingList>, anyhow::Error> {
+        let lists = sqlx::query_as::<_, ShoppingList>(
+            r#"
+            SELECT sl.*,
+                (SELECT COUNT(*) FROM shopping_list_items sli WHERE sli.list_id = sl.id) AS items,
+                (SELECT COUNT(*) FROM shopping_list_items sli WHERE sli.list_id = sl.id AND sli.checked) AS checked
+            FROM shopping_lists sl
+                WHERE sl.user_id = $1
+                ORDER BY sl.id DESC LIMIT $2 OFFSET $3"#,
+        )
+        .bind(user_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get shopping lists of user with id: {}",
+            user_id
+        ))?;
+
+        Ok(lists)
+    }
+
+    pub async fn delete(pool: &Pool<Postgres>, list_id: i32) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"DELETE FROM shopping_lists WHERE id = $1"#)
+            .bind(list_id)
+            .execute(pool)
+            .await
+            .context(format!(
+                "Failed to delete shopping list with id: {}",
+                list_id
+            ))?;
+
+        Ok(())
+    }
+
+    // In the order they were added
+    pub async fn get_items(
+        pool: &Pool<Postgres>,
+        list_id: i32,
+    ) -> Result<Vec<ShoppingListItem>, anyhow::Error> {
+        let items = sqlx::query_as::<_, ShoppingListItem>(
+            r#"SELECT * FROM shopping_list_items WHERE list_id = $1 ORDER BY id"#,
+        )
+        .bind(list_id)
+        .fetch_all(pool)
+        .await
+        .context(format!(
+            "Failed to get the items of shopping list with id: {}",
+            list_id
+        ))?;
+
+        Ok(items)
+    }
+
+    pub async fn add_item(
+        pool: &Pool<Postgres>,
+        list_id: i32,
+        item: &NewShoppingListItem,
+    ) -> Result<i32, anyhow::Error> {
+        let rec = sqlx::query(
+            r#"INSERT INTO shopping_list_items (list_id, ingredient_id, name, quantity, aisle, manual)
+            VALUES ($1, $2, $3, $4, $5, $6) RETURNING id"#,
+        )
+        .bind(list_id)
+        .bind(item.ingredient_id)
+        .bind(&item.name)
+        .bind(item.quantity.map(Json))
+        .bind(item.aisle.name())
+        .bind(item.manual)
+        .fetch_one(pool)
+        .await
+        .context(format!(
+            "Failed to add item to shopping list with id: {}",
+            list_id
+        ))?;
+
+        Ok(rec.get("id"))
+    }
+
+    // Returns false if the list has no item with that id
+    pub async fn set_checked(
+        pool: &Pool<Postgres>,
+        list_id: i32,
+        item_id: i32,
+        checked: bool,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(
+            r#"UPDATE shopping_list_items SET checked = $3 WHERE id = $1 AND list_id = $2"#,
+        )
+        .bind(item_id)
+        .bind(list_id)
+        .bind(checked)
+        .execute(pool)
+        .await
+        .context(format!(
+            "Failed to check off shopping list item with id: {}",
+            item_id
+        ))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Returns false if the list has no item with that id
+    pub async fn delete_item(
+        pool: &Pool<Postgres>,
+        list_id: i32,
+        item_id: i32,
+    ) -> Result<bool, anyhow::Error> {
+        let result =
+            sqlx::query(r#"DELETE FROM shopping_list_items WHERE id = $1 AND list_id = $2"#)
+                .bind(item_id)
+                .bind(list_id)
+                .execute(pool)
+                .await
+                .context(format!(
+                    "Failed to delete shopping list item with id: {}",
+                    item_id
+                ))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
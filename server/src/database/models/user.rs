@@ -168,7 +168,10 @@ impl User {
         id: i32,
     ) -> Result<Option<Value>, anyhow::Error> {
         let rows = sqlx::query(
-            r#"SELECT u.uid, u.username, ud.*, pp.picture_path FROM users u 
+            r#"SELECT u.uid, u.username, ud.*, pp.picture_path,
+                (SELECT COUNT(*) FROM follows f WHERE f.followee_id = u.uid) AS followers,
+                (SELECT COUNT(*) FROM follows f WHERE f.follower_id = u.uid) AS following
+            FROM users u 
             LEFT OUTER JOIN user_details ud 
             ON u.uid = ud.user_id 
             LEFT OUTER JOIN profile_pictures pp
@@ -195,6 +198,8 @@ impl User {
         let pronouns: Option<String> = user.try_get("pronouns").unwrap_or(None);
         let location: Option<String> = user.try_get("location").unwrap_or(None);
         let picture_path: Option<String> = user.try_get("picture_path").unwrap_or(None);
+        let followers: i64 = user.get("followers");
+        let following: i64 = user.get("following");
 
         let value = json!({
             "uid": uid,
@@ -202,12 +207,24 @@ impl User {
             "bio": bio,
             "pronouns": pronouns,
             "location": location,
-            "picture": picture_path
+            "picture": picture_path,
+            "followers": followers,
+            "following": following
         });
 
         Ok(Some(value))
     }
 
+    pub async fn is_admin(pool: &Pool<Postgres>, id: i32) -> Result<bool, anyhow::Error> {
+        let row = sqlx::query(r#"SELECT admin FROM users WHERE uid = $1"#)
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+            .context(format!("Failed to find user with id: {}", id))?;
+
+        Ok(row.map(|row| row.get("admin")).unwrap_or(false))
+    }
+
     // Queries for a user with that name and checks if we get a result
     pub async fn has_username_been_used(pool: &Pool<Postgres>, name: &str) -> bool {
         let user = Self::get_by_name(&pool, name).await.unwrap_or(None);
@@ -0,0 +1,261 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use anyhow::Context;
+use serde::{de::DeserializeOwned, Deserialize};
+use sqlx::{PgConnection, Pool, Postgres};
+
+use crate::recipe_io::nutrition::TRACKED_NUTRIENTS;
+
+use super::models::ingredient::Ingredient;
+
+// Rows are inserted this many at a time, the branded food exports have millions of them
+const BATCH_SIZE: usize = 5000;
+
+pub struct NutrientImport {
+    pub foods: usize,
+    pub food_nutrients: usize,
+    pub food_portions: usize,
+    // Ingredients that got linked to a food by their name
+    pub matched_ingredients: u64,
+}
+
+#[derive(Deserialize)]
+struct NutrientRecord {
+    id: i32,
+    name: String,
+    unit_name: String,
+}
+
+#[derive(Deserialize)]
+struct FoodRecord {
+    fdc_id: i32,
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct FoodNutrientRecord {
+    fdc_id: i32,
+    nutrient_id: i32,
+    amount: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct MeasureUnitRecord {
+    id: i32,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct FoodPortionRecord {
+    fdc_id: i32,
+    amount: Option<f64>,
+    measure_unit_id: Option<i32>,
+    modifier: Option<String>,
+    gram_weight: Option<f64>,
+}
+
+// Imports an unzipped USDA FoodData Central csv export, run with `server import-nutrients <dir>`
+// after sql/nutrition_migration.sql. Only the nutrients we report on are kept. Importing a newer
+// export over an older one updates it in place, and everything happens in one transaction
+pub async fn import_fdc_csv(pool: &Pool<Postgres>, dir: &Path) -> anyhow::Result<NutrientImport> {
+    let tracked: HashSet<i32> = TRACKED_NUTRIENTS.iter().map(|(id, ..)| *id).collect();
+    let mut tx = pool.begin().await?;
+
+    for nutrient in read_csv::<NutrientRecord>(dir, "nutrient.csv")? {
+        let nutrient = nutrient?;
+        if !tracked.contains(&nutrient.id) {
+            continue;
+        }
+
+        sqlx::query(
+            r#"INSERT INTO nutrients (id, name, unit_name) VALUES ($1, $2, $3)
+            ON CONFLICT (id) DO UPDATE SET name = excluded.name, unit_name = excluded.unit_name"#,
+        )
+        .bind(nutrient.id)
+        .bind(nutrient.name)
+        .bind(nutrient.unit_name.to_lowercase())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let mut foods = 0;
+    let mut fdc_ids = Vec::new();
+    let mut descriptions = Vec::new();
+    for food in read_csv::<FoodRecord>(dir, "food.csv")? {
+        let food = food?;
+        fdc_ids.push(food.fdc_id);
+        descriptions.push(food.description);
+
+        if fdc_ids.len() == BATCH_SIZE {
+            foods += insert_foods(&mut tx, &mut fdc_ids, &mut descriptions).await?;
+        }
+    }
+    foods += insert_foods(&mut tx, &mut fdc_ids, &mut descriptions).await?;
+
+    let mut food_nutrients = 0;
+    let mut batch = Vec::new();
+    for food_nutrient in read_csv::<FoodNutrientRecord>(dir, "food_nutrient.csv")? {
+        let food_nutrient = food_nutrient?;
+        let Some(amount) = food_nutrient.amount else {
+            continue;
+        };
+
+        if tracked.contains(&food_nutrient.nutrient_id) {
+            batch.push((food_nutrient.fdc_id, food_nutrient.nutrient_id, amount));
+        }
+
+        if batch.len() == BATCH_SIZE {
+            food_nutrients += insert_food_nutrients(&mut tx, &mut batch).await?;
+        }
+    }
+    food_nutrients += insert_food_nutrients(&mut tx, &mut batch).await?;
+
+    let mut measure_units = HashMap::new();
+    for measure_unit in read_csv::<MeasureUnitRecord>(dir, "measure_unit.csv")? {
+        let measure_unit = measure_unit?;
+        measure_units.insert(measure_unit.id, measure_unit.name.to_lowercase());
+    }
+
+    // Portions have no key of their own, so they're replaced rather than updated
+    sqlx::query(r#"DELETE FROM food_portions"#)
+        .execute(&mut *tx)
+        .await?;
+
+    let mut food_portions = 0;
+    let mut batch = Vec::new();
+    for portion in read_csv::<FoodPortionRecord>(dir, "food_portion.csv")? {
+        let portion = portion?;
+        let (Some(amount), Some(gram_weight)) = (portion.amount, portion.gram_weight) else {
+            continue;
+        };
+
+        // Older foods leave the unit undetermined and put it in the modifier instead, e.g.
+        // "cup, chopped"
+        let measure_unit = portion
+            .measure_unit_id
+            .and_then(|id| measure_units.get(&id))
+            .filter(|name| name.as_str() != "undetermined")
+            .cloned()
+            .or(portion.modifier.map(|modifier| modifier.to_lowercase()))
+            .filter(|name| !name.is_empty());
+
+        let Some(measure_unit) = measure_unit else {
+            continue;
+        };
+
+        batch.push((portion.fdc_id, measure_unit, amount, gram_weight));
+        if batch.len() == BATCH_SIZE {
+            food_portions += insert_food_portions(&mut tx, &mut batch).await?;
+        }
+    }
+    food_portions += insert_food_portions(&mut tx, &mut batch).await?;
+
+    let matched_ingredients = Ingredient::match_foods(&mut tx).await?;
+
+    tx.commit().await?;
+
+    Ok(NutrientImport {
+        foods,
+        food_nutrients,
+        food_portions,
+        matched_ingredients,
+    })
+}
+
+fn read_csv<T: DeserializeOwned>(
+    dir: &Path,
+    file_name: &'static str,
+) -> anyhow::Result<impl Iterator<Item = anyhow::Result<T>>> {
+    let path = dir.join(file_name);
+    let reader =
+        csv::Reader::from_path(&path).context(format!("Failed to open {}", path.display()))?;
+
+    Ok(reader
+        .into_deserialize::<T>()
+        .map(move |record| record.context(format!("Failed to read {}", file_name))))
+}
+
+async fn insert_foods(
+    conn: &mut PgConnection,
+    fdc_ids: &mut Vec<i32>,
+    descriptions: &mut Vec<String>,
+) -> anyhow::Result<usize> {
+    sqlx::query(
+        r#"
+        INSERT INTO foods (fdc_id, description)
+        SELECT * FROM UNNEST($1::integer[], $2::text[])
+        ON CONFLICT (fdc_id) DO UPDATE SET description = excluded.description"#,
+    )
+    .bind(&*fdc_ids)
+    .bind(&*descriptions)
+    .execute(&mut *conn)
+    .await
+    .context("Failed to import foods")?;
+
+    let inserted = fdc_ids.len();
+    fdc_ids.clear();
+    descriptions.clear();
+
+    Ok(inserted)
+}
+
+async fn insert_food_nutrients(
+    conn: &mut PgConnection,
+    batch: &mut Vec<(i32, i32, f64)>,
+) -> anyhow::Result<usize> {
+    let (fdc_ids, (nutrient_ids, amounts)): (Vec<i32>, (Vec<i32>, Vec<f64>)) = batch
+        .drain(..)
+        .map(|(fdc_id, nutrient_id, amount)| (fdc_id, (nutrient_id, amount)))
+        .unzip();
+
+    sqlx::query(
+        r#"
+        INSERT INTO food_nutrients (fdc_id, nutrient_id, amount)
+        SELECT * FROM UNNEST($1::integer[], $2::integer[], $3::double precision[])
+        ON CONFLICT (fdc_id, nutrient_id) DO UPDATE SET amount = excluded.amount"#,
+    )
+    .bind(&fdc_ids)
+    .bind(&nutrient_ids)
+    .bind(&amounts)
+    .execute(&mut *conn)
+    .await
+    .context("Failed to import food nutrients")?;
+
+    Ok(fdc_ids.len())
+}
+
+async fn insert_food_portions(
+    conn: &mut PgConnection,
+    batch: &mut Vec<(i32, String, f64, f64)>,
+) -> anyhow::Result<usize> {
+    let inserted = batch.len();
+    let mut fdc_ids = Vec::with_capacity(inserted);
+    let mut measure_units = Vec::with_capacity(inserted);
+    let mut amounts = Vec::with_capacity(inserted);
+    let mut gram_weights = Vec::with_capacity(inserted);
+
+    for (fdc_id, measure_unit, amount, gram_weight) in batch.drain(..) {
+        fdc_ids.push(fdc_id);
+        measure_units.push(measure_unit);
+        amounts.push(amount);
+        gram_weights.push(gram_weight);
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO food_portions (fdc_id, measure_unit, amount, gram_weight)
+        SELECT * FROM UNNEST($1::integer[], $2::text[], $3::double precision[], $4::double precision[])"#,
+    )
+    .bind(&fdc_ids)
+    .bind(&measure_units)
+    .bind(&amounts)
+    .bind(&gram_weights)
+    .execute(&mut *conn)
+    .await
+    .context("Failed to import food portions")?;
+
+    Ok(inserted)
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgListener, PgConnection, Pool, Postgres};
+use tokio::sync::broadcast;
+
+use crate::database::models::notification::Notification;
+
+// Every server instance listens on this channel, so an event published by one reaches the
+// clients connected to all of them
+pub const CHANNEL: &str = "live_events";
+
+// How many events a slow client can fall behind by before it misses some
+const BUFFER_SIZE: usize = 256;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    Notification {
+        user_id: i32,
+        notification: Notification,
+    },
+    RecipeUpdated {
+        recipe_id: i32,
+        revision: i32,
+    },
+}
+
+impl LiveEvent {
+    // The name sent as the sse event type
+    pub fn name(&self) -> &'static str {
+        match self {
+            LiveEvent::Notification { .. } => "notification",
+            LiveEvent::RecipeUpdated { .. } => "recipe_updated",
+        }
+    }
+}
+
+// Sends the event to every instance. Inside a transaction it is only sent once the
+// transaction commits, and not at all if it rolls back
+pub async fn publish(conn: &mut PgConnection, event: &LiveEvent) -> anyhow::Result<()> {
+    sqlx::query(r#"SELECT pg_notify($1, $2)"#)
+        .bind(CHANNEL)
+        .bind(serde_json::to_string(event)?)
+        .execute(conn)
+        .await
+        .context(format!("Failed to publish {} event", event.name()))?;
+
+    Ok(())
+}
+
+// Hands out the events this instance receives to its connected clients
+#[derive(Clone)]
+pub struct LiveEvents {
+    sender: broadcast::Sender<LiveEvent>,
+}
+
+impl LiveEvents {
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveEvent> {
+        self.sender.subscribe()
+    }
+}
+
+// Starts listening on CHANNEL in the background. The listener reconnects on its own if the
+// connection to postgres drops
+pub async fn listen(pool: &Pool<Postgres>) -> anyhow::Result<LiveEvents> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener
+        .listen(CHANNEL)
+        .await
+        .context(format!("Failed to listen on channel: {}", CHANNEL))?;
+
+    let (sender, _) = broadcast::channel(BUFFER_SIZE);
+    let events = LiveEvents { sender };
+
+    let sender = events.sender.clone();
+    actix_web::rt::spawn(async move {
+        loop {
+            match listener.recv().await {
+                Ok(notification) => {
+                    // Nobody being subscribed isn't an error
+                    if let Ok(event) = serde_json::from_str::<LiveEvent>(notification.payload()) {
+                        let _ = sender.send(event);
+                    }
+                }
+                Err(sqlx::Error::PoolClosed) => break,
+                // The listener reconnects on the next recv, give postgres a moment first
+                Err(..) => actix_web::rt::time::sleep(Duration::from_secs(1)).await,
+            }
+        }
+    });
+
+    Ok(events)
+}
@@ -1,7 +1,7 @@
 use actix_web::{error::ErrorUnauthorized, FromRequest, HttpMessage, HttpResponse};
 use futures::future::{self, Ready};
 
-use crate::middleware::auth::AuthenticationExtension;
+use crate::{auth::helpers::verify_jwt_token, middleware::auth::AuthenticationExtension};
 
 pub enum Authorized {
     // Returns UID and Username
@@ -32,3 +32,31 @@ impl FromRequest for Authorized {
         future::ready(Ok(Authorized::Passed(uid, auth.username.clone())))
     }
 }
+
+// The uid of whoever is making the request on routes that don't require authentication, None
+// if no valid bearer token was passed
+pub struct Viewer(pub Option<i32>);
+
+impl FromRequest for Viewer {
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Error = actix_web::Error;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        _payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        if let Some(auth) = req.extensions().get::<AuthenticationExtension>() {
+            return future::ready(Ok(Viewer(auth.uid.parse::<i32>().ok())));
+        }
+
+        let uid = req
+            .headers()
+            .get("Authorization")
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .and_then(|token| verify_jwt_token(token).ok())
+            .and_then(|(uid, _username)| uid.parse::<i32>().ok());
+
+        future::ready(Ok(Viewer(uid)))
+    }
+}
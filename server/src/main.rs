@@ -11,25 +11,64 @@ use actix_web::{
     web::{self, scope, to, Data},
     App, HttpServer,
 };
+use database::{
+    migrations::{classify_recipes, import_recipe_files, link_recipe_ingredients},
+    nutrient_import::import_fdc_csv,
+};
 use dotenv::dotenv;
 use middleware::auth::Authentication;
 use routes::{
     account::services::*,
+    collections::services::{
+        add_collection_recipe, create_collection, delete_collection, get_collection,
+        get_own_collections, get_user_collections, remove_collection_recipe, reorder_collection,
+        update_collection,
+    },
+    comments::services::{comment_on_recipe, delete_comment, edit_comment, get_recipe_comments},
+    cook_log::services::{delete_cook_log_entry, get_cook_log, get_recipe_cook_photos, log_cook},
+    events::services::stream_events,
+    ingredients::services::{
+        add_ingredient_alias, get_ingredients, match_ingredient_foods, merge_ingredients,
+        remove_ingredient_alias, search_foods, set_ingredient_aisle, set_ingredient_food,
+        set_ingredient_profile,
+    },
+    meal_plan::services::{
+        create_meal_plan_feed, delete_meal_plan_entry, delete_meal_plan_feed, edit_meal_plan_entry,
+        get_meal_plan, get_meal_plan_feed, plan_meal,
+    },
+    notifications::services::{
+        get_notification_preferences, get_notifications, get_unread_count, read_all_notifications,
+        read_notification, update_notification_preferences,
+    },
     recipes::services::{
-        can_edit, create_recipe, edit_recipe, get_recipe, get_recipe_by_poster, get_recipes,
+        can_edit, create_recipe, delete_recipe, edit_recipe, favourite_recipe, fork_recipe,
+        get_recipe, get_recipe_by_poster, get_recipe_forks, get_recipe_revision,
+        get_recipe_revision_diff, get_recipe_revisions, get_recipes, restore_recipe_revision,
+        search_recipes, search_recipes_by_ingredients, set_recipe_visibility, unfavourite_recipe,
+    },
+    reviews::services::{delete_review, get_recipe_reviews, review_recipe},
+    shopping_lists::services::{
+        add_shopping_list_item, check_shopping_list_item, create_shopping_list,
+        delete_shopping_list, delete_shopping_list_item, export_shopping_list, get_shopping_list,
+        get_shopping_lists,
+    },
+    users::services::{
+        follow_user, get_all_users, get_user_by_id, login_user, register_user, unfollow_user,
     },
-    users::services::{get_all_users, get_user_by_id, login_user, register_user},
 };
 use sqlx::postgres::PgPoolOptions;
+use static_files::services::{get_cook_photo, get_profile_picture_file, get_thumbnail};
 
 pub mod auth;
 pub mod database;
+pub mod events;
 pub mod extractors;
 pub mod helpers;
 pub mod middleware;
 pub mod recipe_io;
 pub mod routes;
 pub mod static_files;
+pub mod storage;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -42,6 +81,90 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("Couldnt conect to postgres db");
 
+    let storage = storage::from_env().expect("Couldnt configure storage");
+
+    // One shot import of the old recipe files, see sql/recipe_jsonb_migration.sql
+    if std::env::args().nth(1).as_deref() == Some("import-recipes") {
+        match import_recipe_files(&pool, &*storage).await {
+            Ok(import) => {
+                println!("Imported {} recipe file(s)", import.imported);
+                for file_name in import.unreferenced.iter() {
+                    println!("No recipe row references the file: {}", file_name);
+                }
+
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("{:#}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Links existing recipes to the ingredient catalogue, see sql/ingredient_catalogue_migration.sql
+    if std::env::args().nth(1).as_deref() == Some("link-ingredients") {
+        match link_recipe_ingredients(&pool).await {
+            Ok(linked) => {
+                println!("Linked the ingredients of {} recipe(s)", linked);
+
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("{:#}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Works out the allergens and diets of existing recipes, see sql/dietary_migration.sql
+    if std::env::args().nth(1).as_deref() == Some("classify-recipes") {
+        match classify_recipes(&pool).await {
+            Ok((ingredients, recipes)) => {
+                println!(
+                    "Classified {} ingredient(s) and {} recipe(s)",
+                    ingredients, recipes
+                );
+
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("{:#}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Loads a FoodData Central csv export, see sql/nutrition_migration.sql
+    if std::env::args().nth(1).as_deref() == Some("import-nutrients") {
+        let Some(dir) = std::env::args().nth(2) else {
+            eprintln!("Usage: server import-nutrients <FoodData Central csv directory>");
+            std::process::exit(1);
+        };
+
+        match import_fdc_csv(&pool, std::path::Path::new(&dir)).await {
+            Ok(import) => {
+                println!(
+                    "Imported {} food(s), {} nutrient value(s) and {} portion(s)",
+                    import.foods, import.food_nutrients, import.food_portions
+                );
+                println!(
+                    "Linked {} ingredient(s) to a food by name",
+                    import.matched_ingredients
+                );
+
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("{:#}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let live_events = events::listen(&pool)
+        .await
+        .expect("Couldnt listen for live events");
+
     let backend = InMemoryBackend::builder().build();
 
     //    let store = MemoryStore::new();
@@ -70,20 +193,25 @@ async fn main() -> std::io::Result<()> {
             //                   .with_max_requests(100),
             //           )
             .app_data(Data::new(pool.clone()))
+            .app_data(Data::from(storage.clone()))
+            .app_data(Data::new(live_events.clone()))
             .service(
                 scope("/v1")
-                    .service(
-                        actix_files::Files::new("/thumbnails", "./thumbnails").show_files_listing(),
-                    )
-                    .service(
-                        actix_files::Files::new("/pfp", "./profile_pictures").show_files_listing(),
-                    )
+                    .service(get_thumbnail)
+                    .service(get_cook_photo)
+                    .service(get_profile_picture_file)
                     .service(
                         scope("/users")
                             .service(get_all_users)
                             .service(get_user_by_id)
                             .service(register_user)
-                            .service(login_user),
+                            .service(login_user)
+                            .service(
+                                web::resource("/{user_id}/follow")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(follow_user))
+                                    .route(web::delete().to(unfollow_user)),
+                            ),
                     )
                     .service(
                         scope("/account")
@@ -97,6 +225,147 @@ async fn main() -> std::io::Result<()> {
                             .service(
                                 web::resource("/delete_pfp")
                                     .route(web::get().to(delete_profile_picture)),
+                            )
+                            .service(
+                                web::resource("/favourites").route(web::get().to(get_favourites)),
+                            )
+                            .service(web::resource("/feed").route(web::get().to(get_feed)))
+                            .service(web::resource("/events").route(web::get().to(stream_events)))
+                            .service(
+                                web::resource("/notifications")
+                                    .route(web::get().to(get_notifications)),
+                            )
+                            .service(
+                                web::resource("/notifications/unread")
+                                    .route(web::get().to(get_unread_count)),
+                            )
+                            .service(
+                                web::resource("/notifications/read")
+                                    .route(web::post().to(read_all_notifications)),
+                            )
+                            .service(
+                                web::resource("/notifications/preferences")
+                                    .route(web::get().to(get_notification_preferences))
+                                    .route(web::post().to(update_notification_preferences)),
+                            )
+                            .service(
+                                web::resource("/notifications/{notification_id}/read")
+                                    .route(web::post().to(read_notification)),
+                            )
+                            .service(
+                                web::resource("/meal_plan")
+                                    .route(web::get().to(get_meal_plan))
+                                    .route(web::post().to(plan_meal)),
+                            )
+                            .service(
+                                web::resource("/meal_plan/feed")
+                                    .route(web::post().to(create_meal_plan_feed))
+                                    .route(web::delete().to(delete_meal_plan_feed)),
+                            )
+                            .service(
+                                web::resource("/meal_plan/{entry_id}")
+                                    .route(web::post().to(edit_meal_plan_entry))
+                                    .route(web::delete().to(delete_meal_plan_entry)),
+                            )
+                            .service(
+                                web::resource("/shopping_lists")
+                                    .route(web::get().to(get_shopping_lists))
+                                    .route(web::post().to(create_shopping_list)),
+                            )
+                            .service(
+                                web::resource("/shopping_lists/{list_id}")
+                                    .route(web::get().to(get_shopping_list))
+                                    .route(web::delete().to(delete_shopping_list)),
+                            )
+                            .service(
+                                web::resource("/shopping_lists/{list_id}/export")
+                                    .route(web::get().to(export_shopping_list)),
+                            )
+                            .service(
+                                web::resource("/shopping_lists/{list_id}/items")
+                                    .route(web::post().to(add_shopping_list_item)),
+                            )
+                            .service(
+                                web::resource("/shopping_lists/{list_id}/items/{item_id}")
+                                    .route(web::post().to(check_shopping_list_item))
+                                    .route(web::delete().to(delete_shopping_list_item)),
+                            )
+                            .service(
+                                web::resource("/cook_log")
+                                    .route(web::get().to(get_cook_log))
+                                    .route(web::post().to(log_cook)),
+                            )
+                            .service(
+                                web::resource("/cook_log/{entry_id}")
+                                    .route(web::delete().to(delete_cook_log_entry)),
+                            )
+                            .service(
+                                web::resource("/collections")
+                                    .route(web::get().to(get_own_collections))
+                                    .route(web::post().to(create_collection)),
+                            )
+                            .service(
+                                web::resource("/collections/{collection_id}")
+                                    .route(web::post().to(update_collection))
+                                    .route(web::delete().to(delete_collection)),
+                            )
+                            .service(
+                                web::resource("/collections/{collection_id}/recipes")
+                                    .route(web::post().to(add_collection_recipe)),
+                            )
+                            .service(
+                                web::resource("/collections/{collection_id}/recipes/{recipe_id}")
+                                    .route(web::delete().to(remove_collection_recipe)),
+                            )
+                            .service(
+                                web::resource("/collections/{collection_id}/order")
+                                    .route(web::post().to(reorder_collection)),
+                            ),
+                    )
+                    .service(scope("/meal_plan").service(get_meal_plan_feed))
+                    .service(
+                        scope("/collections")
+                            .service(get_user_collections)
+                            .service(get_collection),
+                    )
+                    .service(
+                        scope("/ingredients")
+                            .service(get_ingredients)
+                            .service(search_foods)
+                            .service(
+                                web::resource("/merge")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(merge_ingredients)),
+                            )
+                            .service(
+                                web::resource("/match_foods")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(match_ingredient_foods)),
+                            )
+                            .service(
+                                web::resource("/aliases/{alias}")
+                                    .wrap(Authentication)
+                                    .route(web::delete().to(remove_ingredient_alias)),
+                            )
+                            .service(
+                                web::resource("/{ingredient_id}/aliases")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(add_ingredient_alias)),
+                            )
+                            .service(
+                                web::resource("/{ingredient_id}/food")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(set_ingredient_food)),
+                            )
+                            .service(
+                                web::resource("/{ingredient_id}/aisle")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(set_ingredient_aisle)),
+                            )
+                            .service(
+                                web::resource("/{ingredient_id}/profile")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(set_ingredient_profile)),
                             ),
                     )
                     .service(
@@ -116,9 +385,61 @@ async fn main() -> std::io::Result<()> {
                                     .wrap(Authentication)
                                     .route(web::post().to(edit_recipe)),
                             )
+                            .service(search_recipes)
+                            .service(search_recipes_by_ingredients)
                             .service(get_recipe_by_poster)
                             .service(get_recipes)
-                            .service(get_recipe),
+                            .service(get_recipe_revisions)
+                            .service(get_recipe_revision)
+                            .service(get_recipe_revision_diff)
+                            .service(
+                                web::resource("/{recipe_id}/revisions/{revision}/restore")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(restore_recipe_revision)),
+                            )
+                            .service(get_recipe_forks)
+                            .service(get_recipe_reviews)
+                            .service(get_recipe_comments)
+                            .service(get_recipe_cook_photos)
+                            .service(
+                                web::resource("/{recipe_id}/comments")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(comment_on_recipe)),
+                            )
+                            .service(
+                                web::resource("/{recipe_id}/comments/{comment_id}")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(edit_comment))
+                                    .route(web::delete().to(delete_comment)),
+                            )
+                            .service(
+                                web::resource("/{recipe_id}/review")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(review_recipe))
+                                    .route(web::delete().to(delete_review)),
+                            )
+                            .service(
+                                web::resource("/{recipe_id}/favourite")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(favourite_recipe))
+                                    .route(web::delete().to(unfavourite_recipe)),
+                            )
+                            .service(
+                                web::resource("/{recipe_id}/visibility")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(set_recipe_visibility)),
+                            )
+                            .service(
+                                web::resource("/{recipe_id}/fork")
+                                    .wrap(Authentication)
+                                    .route(web::post().to(fork_recipe)),
+                            )
+                            .service(get_recipe)
+                            .service(
+                                web::resource("/{recipe_id}")
+                                    .wrap(Authentication)
+                                    .route(web::delete().to(delete_recipe)),
+                            ),
                     )
                     .wrap(Logger::default()),
             )
@@ -0,0 +1,314 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::anyhow;
+use serde::{
+    de::{MapAccess, Visitor},
+    ser::SerializeStruct,
+    Deserialize, Serialize,
+};
+
+// How close a value has to be to a fraction for it to be shown as one, anything finer than
+// this isn't measurable in a kitchen anyway
+const FRACTION_TOLERANCE: f64 = 0.01;
+
+const FRACTIONS: [(char, f64); 15] = [
+    ('⅛', 1.0 / 8.0),
+    ('⅙', 1.0 / 6.0),
+    ('⅕', 1.0 / 5.0),
+    ('¼', 1.0 / 4.0),
+    ('⅓', 1.0 / 3.0),
+    ('⅜', 3.0 / 8.0),
+    ('⅖', 2.0 / 5.0),
+    ('½', 1.0 / 2.0),
+    ('⅗', 3.0 / 5.0),
+    ('⅝', 5.0 / 8.0),
+    ('⅔', 2.0 / 3.0),
+    ('¾', 3.0 / 4.0),
+    ('⅘', 4.0 / 5.0),
+    ('⅚', 5.0 / 6.0),
+    ('⅞', 7.0 / 8.0),
+];
+
+// The amount of an ingredient, either a single quantity or a min-max range.
+//
+// A single quantity is stored as a plain json number, so the old u32 amounts read back
+// unchanged, and a range as {"min": 2, "max": 3}. Values are kept exactly as they are, the
+// rounded display string, e.g. "1½" or "2–3", is only made for API responses. Display strings
+// are still accepted when reading, from clients and from recipes stored before this
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Amount {
+    Single(f64),
+    Range(f64, f64),
+}
+
+impl Amount {
+    pub fn min(&self) -> f64 {
+        match self {
+            Amount::Single(value) => *value,
+            Amount::Range(min, _) => *min,
+        }
+    }
+
+    pub fn max(&self) -> f64 {
+        match self {
+            Amount::Single(value) => *value,
+            Amount::Range(_, max) => *max,
+        }
+    }
+
+    // Applies f to every quantity in the amount, a range that ends up with the same min and
+    // max becomes a single quantity
+    pub fn map(&self, f: impl Fn(f64) -> f64) -> Amount {
+        match self {
+            Amount::Single(value) => Amount::Single(f(*value)),
+            Amount::Range(min, max) => {
+                let (min, max) = (f(*min), f(*max));
+                if min == max {
+                    Amount::Single(min)
+                } else {
+                    Amount::Range(min, max)
+                }
+            }
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Amount::Single(value) => value.is_finite() && *value >= 0.0,
+            Amount::Range(min, max) => {
+                min.is_finite() && max.is_finite() && *min >= 0.0 && min < max
+            }
+        }
+    }
+
+    fn is_whole(&self) -> bool {
+        match self {
+            Amount::Single(value) => value.fract() == 0.0,
+            Amount::Range(..) => false,
+        }
+    }
+}
+
+impl From<u32> for Amount {
+    fn from(value: u32) -> Self {
+        Amount::Single(value as f64)
+    }
+}
+
+impl Display for Amount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Amount::Single(value) => write!(f, "{}", format_quantity(*value)),
+            Amount::Range(min, max) => {
+                write!(f, "{}–{}", format_quantity(*min), format_quantity(*max))
+            }
+        }
+    }
+}
+
+impl FromStr for Amount {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        // "2-3", "2–3" and "2 to 3" are all ranges
+        let range = s
+            .split_once('–')
+            .or_else(|| s.split_once('-'))
+            .or_else(|| s.split_once(" to "));
+
+        let amount = match range {
+            Some((min, max)) => {
+                let (min, max) = (parse_quantity(min)?, parse_quantity(max)?);
+                // A range that rounds to a single quantity is just that quantity
+                if min == max {
+                    Amount::Single(min)
+                } else {
+                    Amount::Range(min, max)
+                }
+            }
+            None => Amount::Single(parse_quantity(s)?),
+        };
+
+        if !amount.is_valid() {
+            return Err(anyhow!("The amount '{}' is invalid", s));
+        }
+
+        Ok(amount)
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Amount::Single(value) if self.is_whole() => serializer.serialize_u64(*value as u64),
+            Amount::Single(value) => serializer.serialize_f64(*value),
+            Amount::Range(min, max) => {
+                let mut range = serializer.serialize_struct("Amount", 2)?;
+                range.serialize_field("min", min)?;
+                range.serialize_field("max", max)?;
+                range.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+struct AmountVisitor;
+
+impl<'de> Visitor<'de> for AmountVisitor {
+    type Value = Amount;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(
+            "a positive number, fraction or range such as \"1½\", \"2-3\" or {\"min\": 2, \"max\": 3}",
+        )
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(Amount::Single(v as f64))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        if v < 0 {
+            return Err(E::custom("amounts can not be negative"));
+        }
+
+        Ok(Amount::Single(v as f64))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        let amount = Amount::Single(v);
+        if !amount.is_valid() {
+            return Err(E::custom("amounts can not be negative"));
+        }
+
+        Ok(amount)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        v.parse::<Amount>().map_err(E::custom)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let (mut min, mut max) = (None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "min" => min = Some(map.next_value::<f64>()?),
+                "max" => max = Some(map.next_value::<f64>()?),
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+
+        let min = min.ok_or_else(|| serde::de::Error::missing_field("min"))?;
+        let max = max.ok_or_else(|| serde::de::Error::missing_field("max"))?;
+        let amount = if min == max {
+            Amount::Single(min)
+        } else {
+            Amount::Range(min, max)
+        };
+
+        if !amount.is_valid() {
+            return Err(serde::de::Error::custom(
+                "amounts can not be negative and ranges must go from low to high",
+            ));
+        }
+
+        Ok(amount)
+    }
+}
+
+// Parses "2", "1.5", "1/2", "1 1/2", "½" and "1½"
+fn parse_quantity(s: &str) -> anyhow::Result<f64> {
+    let s = s.trim();
+    let invalid = || anyhow!("The quantity '{}' is invalid", s);
+
+    if let Some(last) = s.chars().last() {
+        if let Some((_, fraction)) = FRACTIONS.iter().find(|(c, _)| *c == last) {
+            let whole = s[..s.len() - last.len_utf8()].trim();
+            if whole.is_empty() {
+                return Ok(*fraction);
+            }
+
+            return Ok(whole.parse::<u32>().map_err(|_| invalid())? as f64 + fraction);
+        }
+    }
+
+    if let Some((numerator, denominator)) = s.split_once('/') {
+        let (whole, numerator) = match numerator.trim().split_once(' ') {
+            Some((whole, numerator)) => (whole.parse::<u32>().map_err(|_| invalid())?, numerator),
+            None => (0, numerator),
+        };
+
+        let numerator = numerator.trim().parse::<u32>().map_err(|_| invalid())?;
+        let denominator = denominator.trim().parse::<u32>().map_err(|_| invalid())?;
+        if denominator == 0 {
+            return Err(invalid());
+        }
+
+        return Ok(whole as f64 + numerator as f64 / denominator as f64);
+    }
+
+    s.parse::<f64>().map_err(|_| invalid())
+}
+
+// Shows the value as a whole number and unicode fraction when it is close enough to one,
+// otherwise as a decimal with at most 2 places
+fn format_quantity(value: f64) -> String {
+    let whole = value.trunc();
+    let fract = value - whole;
+
+    if fract < FRACTION_TOLERANCE {
+        return format!("{}", whole as u64);
+    }
+
+    if 1.0 - fract < FRACTION_TOLERANCE {
+        return format!("{}", whole as u64 + 1);
+    }
+
+    if let Some((c, _)) = FRACTIONS
+        .iter()
+        .find(|(_, fraction)| (fract - fraction).abs() < FRACTION_TOLERANCE)
+    {
+        if whole == 0.0 {
+            return c.to_string();
+        }
+
+        return format!("{}{}", whole as u64, c);
+    }
+
+    let rounded = format!("{:.2}", value);
+    rounded
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
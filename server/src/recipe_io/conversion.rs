@@ -0,0 +1,233 @@
+use anyhow::anyhow;
+use serde::Deserialize;
+
+use super::{amount::Amount, Measurement, RecipeFileJson, RecipeMeasurements, RecipeStep};
+
+// Volumes are in US customary units, as that is who reads pints and gallons
+const ML_PER_TEASPOON: f64 = 4.92892;
+const ML_PER_TABLESPOON: f64 = 14.7868;
+const ML_PER_FLUID_OZ: f64 = 29.5735;
+const ML_PER_PINT: f64 = 473.176;
+const ML_PER_GALLON: f64 = 3785.41;
+const G_PER_OUNCE: f64 = 28.3495;
+const G_PER_POUND: f64 = 453.592;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    Volume,
+    Mass,
+    Temperature,
+    Count,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitSystem {
+    #[serde(rename = "metric")]
+    Metric,
+    #[serde(rename = "us")]
+    UsCustomary,
+}
+
+impl Measurement {
+    pub fn dimension(&self) -> Dimension {
+        match self {
+            Measurement::Millilitre
+            | Measurement::Litre
+            | Measurement::Teaspoon
+            | Measurement::Tablespoon
+            | Measurement::FluidOz
+            | Measurement::Pint
+            | Measurement::Gallon => Dimension::Volume,
+            Measurement::Milligram
+            | Measurement::Gram
+            | Measurement::Kilogram
+            | Measurement::Pound
+            | Measurement::Ounce => Dimension::Mass,
+            Measurement::Celsius | Measurement::Fahrenheit => Dimension::Temperature,
+            Measurement::Piece => Dimension::Count,
+        }
+    }
+
+    // Converts a value in this unit to the base unit of its dimension, millilitres, grams,
+    // degrees celsius or pieces
+    pub fn to_base(&self, value: f64) -> f64 {
+        match self {
+            Measurement::Millilitre => value,
+            Measurement::Litre => value * 1000.0,
+            Measurement::Teaspoon => value * ML_PER_TEASPOON,
+            Measurement::Tablespoon => value * ML_PER_TABLESPOON,
+            Measurement::FluidOz => value * ML_PER_FLUID_OZ,
+            Measurement::Pint => value * ML_PER_PINT,
+            Measurement::Gallon => value * ML_PER_GALLON,
+            Measurement::Milligram => value / 1000.0,
+            Measurement::Gram => value,
+            Measurement::Kilogram => value * 1000.0,
+            Measurement::Pound => value * G_PER_POUND,
+            Measurement::Ounce => value * G_PER_OUNCE,
+            Measurement::Celsius => value,
+            Measurement::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+            Measurement::Piece => value,
+        }
+    }
+
+    // The inverse of to_base
+    pub fn from_base(&self, value: f64) -> f64 {
+        match self {
+            Measurement::Millilitre => value,
+            Measurement::Litre => value / 1000.0,
+            Measurement::Teaspoon => value / ML_PER_TEASPOON,
+            Measurement::Tablespoon => value / ML_PER_TABLESPOON,
+            Measurement::FluidOz => value / ML_PER_FLUID_OZ,
+            Measurement::Pint => value / ML_PER_PINT,
+            Measurement::Gallon => value / ML_PER_GALLON,
+            Measurement::Milligram => value * 1000.0,
+            Measurement::Gram => value,
+            Measurement::Kilogram => value / 1000.0,
+            Measurement::Pound => value / G_PER_POUND,
+            Measurement::Ounce => value / G_PER_OUNCE,
+            Measurement::Celsius => value,
+            Measurement::Fahrenheit => value * 9.0 / 5.0 + 32.0,
+            Measurement::Piece => value,
+        }
+    }
+
+    pub fn convert(&self, value: f64, to: Measurement) -> anyhow::Result<f64> {
+        if self.dimension() != to.dimension() {
+            return Err(anyhow!("Can't convert {:?} to {:?}", self, to));
+        }
+
+        Ok(to.from_base(self.to_base(value)))
+    }
+
+    // The smallest step worth measuring for a value in this unit
+    pub fn precision(&self, value: f64) -> f64 {
+        match self {
+            Measurement::Millilitre | Measurement::Milligram | Measurement::Gram => {
+                if value < 10.0 {
+                    0.5
+                } else if value < 100.0 {
+                    1.0
+                } else {
+                    5.0
+                }
+            }
+            Measurement::Litre | Measurement::Kilogram => 0.05,
+            Measurement::Teaspoon => 0.125,
+            // Under an ounce is where yeast and salt are, which need the finer steps
+            Measurement::Ounce if value < 1.0 => 0.125,
+            Measurement::Tablespoon
+            | Measurement::FluidOz
+            | Measurement::Ounce
+            | Measurement::Piece => 0.5,
+            Measurement::Pint | Measurement::Gallon | Measurement::Pound => 0.25,
+            Measurement::Celsius | Measurement::Fahrenheit => 1.0,
+        }
+    }
+
+    // Rounds to the precision of the unit. Amounts smaller than the precision are kept as they
+    // are, rounding them either way would be off by more than the amount itself
+    pub fn round(&self, value: f64) -> f64 {
+        let precision = self.precision(value);
+        if value < precision {
+            return value;
+        }
+
+        (value / precision).round() * precision
+    }
+
+    // The unit a value in the base unit reads best in for the unit system. Spoons are left
+    // alone as everyone measures with them
+    fn preferred_unit(&self, base_value: f64, system: UnitSystem) -> Measurement {
+        if let Measurement::Teaspoon | Measurement::Tablespoon = self {
+            return *self;
+        }
+
+        match (self.dimension(), system) {
+            (Dimension::Volume, UnitSystem::Metric) => {
+                if base_value >= 1000.0 {
+                    Measurement::Litre
+                } else {
+                    Measurement::Millilitre
+                }
+            }
+            (Dimension::Volume, UnitSystem::UsCustomary) => {
+                if base_value >= ML_PER_GALLON {
+                    Measurement::Gallon
+                } else if base_value >= ML_PER_PINT {
+                    Measurement::Pint
+                } else if base_value >= ML_PER_FLUID_OZ {
+                    Measurement::FluidOz
+                } else if base_value >= ML_PER_TABLESPOON {
+                    Measurement::Tablespoon
+                } else {
+                    Measurement::Teaspoon
+                }
+            }
+            (Dimension::Mass, UnitSystem::Metric) => {
+                if base_value >= 1000.0 {
+                    Measurement::Kilogram
+                } else if base_value < 1.0 {
+                    Measurement::Milligram
+                } else {
+                    Measurement::Gram
+                }
+            }
+            (Dimension::Mass, UnitSystem::UsCustomary) => {
+                if base_value >= G_PER_POUND {
+                    Measurement::Pound
+                } else {
+                    Measurement::Ounce
+                }
+            }
+            (Dimension::Temperature, UnitSystem::Metric) => Measurement::Celsius,
+            (Dimension::Temperature, UnitSystem::UsCustomary) => Measurement::Fahrenheit,
+            (Dimension::Count, _) => Measurement::Piece,
+        }
+    }
+}
+
+impl RecipeMeasurements {
+    // Converts the measurement to the unit that reads best in the unit system, a range is
+    // kept in one unit picked by its upper bound
+    pub fn to_system(&self, system: UnitSystem) -> RecipeMeasurements {
+        let base_max = self.measurement.to_base(self.amount.max());
+        let unit = self.measurement.preferred_unit(base_max, system);
+        if unit == self.measurement {
+            return self.clone();
+        }
+
+        let amount: Amount = self
+            .amount
+            .map(|value| unit.round(unit.from_base(self.measurement.to_base(value))));
+
+        RecipeMeasurements {
+            measurement: unit,
+            amount,
+            ..self.clone()
+        }
+    }
+}
+
+impl RecipeFileJson {
+    pub fn to_system(&self, system: UnitSystem) -> RecipeFileJson {
+        RecipeFileJson {
+            ingredients: self
+                .ingredients
+                .iter()
+                .map(|ingredient| ingredient.to_system(system))
+                .collect(),
+            steps: self
+                .steps
+                .iter()
+                .map(|step| RecipeStep {
+                    measurements: step
+                        .measurements
+                        .as_ref()
+                        .map(|measurement| measurement.to_system(system)),
+                    ..step.clone()
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
@@ -0,0 +1,411 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    normalise::{normalise_ingredient, singular},
+    RecipeFileJson,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Allergen {
+    Gluten,
+    Dairy,
+    Egg,
+    Peanut,
+    TreeNut,
+    Soy,
+    Fish,
+    Shellfish,
+    Sesame,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Diet {
+    Vegan,
+    Vegetarian,
+    Pescatarian,
+    GlutenFree,
+    DairyFree,
+    NutFree,
+}
+
+impl Allergen {
+    pub const ALL: [Allergen; 9] = [
+        Allergen::Gluten,
+        Allergen::Dairy,
+        Allergen::Egg,
+        Allergen::Peanut,
+        Allergen::TreeNut,
+        Allergen::Soy,
+        Allergen::Fish,
+        Allergen::Shellfish,
+        Allergen::Sesame,
+    ];
+
+    // The name used in the api and the database
+    pub fn name(&self) -> &'static str {
+        match self {
+            Allergen::Gluten => "gluten",
+            Allergen::Dairy => "dairy",
+            Allergen::Egg => "egg",
+            Allergen::Peanut => "peanut",
+            Allergen::TreeNut => "tree_nut",
+            Allergen::Soy => "soy",
+            Allergen::Fish => "fish",
+            Allergen::Shellfish => "shellfish",
+            Allergen::Sesame => "sesame",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Allergen> {
+        Self::ALL
+            .into_iter()
+            .find(|allergen| allergen.name() == name)
+    }
+
+    // Words in an ingredient name that mean it contains the allergen, in singular form
+    fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            Allergen::Gluten => &[
+                "flour",
+                "wheat",
+                "bread",
+                "breadcrumb",
+                "pasta",
+                "spaghetti",
+                "macaroni",
+                "noodle",
+                "barley",
+                "rye",
+                "couscous",
+                "semolina",
+                "bulgur",
+                "spelt",
+                "seitan",
+                "cracker",
+                "bun",
+                "pastry",
+                "biscuit",
+                "beer",
+            ],
+            Allergen::Dairy => &[
+                "milk",
+                "butter",
+                "buttermilk",
+                "cheese",
+                "cream",
+                "yogurt",
+                "yoghurt",
+                "ghee",
+                "parmesan",
+                "mozzarella",
+                "cheddar",
+                "ricotta",
+                "feta",
+                "mascarpone",
+                "whey",
+            ],
+            Allergen::Egg => &["egg", "mayonnaise", "mayo", "meringue"],
+            Allergen::Peanut => &["peanut"],
+            Allergen::TreeNut => &[
+                "nut",
+                "almond",
+                "walnut",
+                "cashew",
+                "pecan",
+                "pistachio",
+                "hazelnut",
+                "macadamia",
+                "praline",
+                "marzipan",
+            ],
+            Allergen::Soy => &["soy", "soya", "tofu", "tempeh", "edamame", "miso"],
+            Allergen::Fish => &[
+                "fish",
+                "salmon",
+                "tuna",
+                "cod",
+                "anchovy",
+                "sardine",
+                "mackerel",
+                "trout",
+                "haddock",
+                "tilapia",
+                "worcestershire",
+            ],
+            Allergen::Shellfish => &[
+                "shrimp", "prawn", "crab", "lobster", "mussel", "clam", "oyster", "scallop",
+                "squid", "calamari",
+            ],
+            Allergen::Sesame => &["sesame", "tahini"],
+        }
+    }
+}
+
+impl Diet {
+    pub const ALL: [Diet; 6] = [
+        Diet::Vegan,
+        Diet::Vegetarian,
+        Diet::Pescatarian,
+        Diet::GlutenFree,
+        Diet::DairyFree,
+        Diet::NutFree,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Diet::Vegan => "vegan",
+            Diet::Vegetarian => "vegetarian",
+            Diet::Pescatarian => "pescatarian",
+            Diet::GlutenFree => "gluten_free",
+            Diet::DairyFree => "dairy_free",
+            Diet::NutFree => "nut_free",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Diet> {
+        Self::ALL.into_iter().find(|diet| diet.name() == name)
+    }
+}
+
+// Words that mean the ingredient is meat, or made from it
+const MEAT_KEYWORDS: [&str; 22] = [
+    "chicken",
+    "beef",
+    "pork",
+    "lamb",
+    "mutton",
+    "veal",
+    "venison",
+    "bacon",
+    "ham",
+    "sausage",
+    "turkey",
+    "duck",
+    "mince",
+    "steak",
+    "chorizo",
+    "salami",
+    "prosciutto",
+    "pepperoni",
+    "pancetta",
+    "lard",
+    "gelatin",
+    "gelatine",
+];
+
+// Animal products that aren't meat, dairy or egg
+const ANIMAL_PRODUCT_KEYWORDS: [&str; 1] = ["honey"];
+
+// Plant words that turn milk, butter, cream, cheese or flour into something else, e.g.
+// "almond milk" or "rice flour"
+const PLANT_QUALIFIERS: [&str; 14] = [
+    "almond",
+    "oat",
+    "soy",
+    "soya",
+    "coconut",
+    "rice",
+    "cashew",
+    "peanut",
+    "nut",
+    "corn",
+    "chickpea",
+    "buckwheat",
+    "potato",
+    "tapioca",
+];
+
+// What an ingredient of the catalogue contains
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct IngredientProfile {
+    pub allergens: Vec<Allergen>,
+    pub meat: bool,
+    pub animal_product: bool,
+}
+
+impl IngredientProfile {
+    // A best guess from the words in the ingredient's name, admins correct it in the catalogue
+    // and authors can override the result for their recipe
+    pub fn guess(name: &str) -> IngredientProfile {
+        let normalised = normalise_ingredient(name);
+        let words: Vec<String> = normalised.split(' ').map(singular).collect();
+
+        let vegan = words
+            .iter()
+            .any(|word| word == "vegan" || word == "plant-based");
+        let free_from = |word: &str| words.iter().any(|w| *w == format!("{}-free", word));
+
+        let has_keyword = |keywords: &[&str]| {
+            words.iter().enumerate().any(|(i, word)| {
+                let qualified = i > 0 && PLANT_QUALIFIERS.contains(&words[i - 1].as_str());
+                let plant_based = qualified
+                    && [
+                        "milk", "butter", "cream", "cheese", "flour", "noodle", "pasta",
+                    ]
+                    .contains(&word.as_str());
+
+                keywords.contains(&word.as_str()) && !plant_based
+            })
+        };
+
+        let mut allergens: Vec<Allergen> = Allergen::ALL
+            .into_iter()
+            .filter(|allergen| has_keyword(allergen.keywords()))
+            .filter(|allergen| !free_from(allergen.name()))
+            .collect();
+
+        // Soy sauce is brewed with wheat, and anything vegan has no dairy or egg in it
+        if words
+            .windows(2)
+            .any(|pair| pair[0] == "soy" && pair[1] == "sauce")
+            && !free_from("gluten")
+            && !allergens.contains(&Allergen::Gluten)
+        {
+            allergens.push(Allergen::Gluten);
+        }
+        if vegan || free_from("dairy") {
+            allergens.retain(|allergen| *allergen != Allergen::Dairy);
+        }
+        if vegan || free_from("egg") {
+            allergens.retain(|allergen| *allergen != Allergen::Egg);
+        }
+        if free_from("nut") {
+            allergens
+                .retain(|allergen| *allergen != Allergen::Peanut && *allergen != Allergen::TreeNut);
+        }
+        allergens.sort();
+
+        IngredientProfile {
+            allergens,
+            meat: !vegan && has_keyword(&MEAT_KEYWORDS),
+            animal_product: !vegan && has_keyword(&ANIMAL_PRODUCT_KEYWORDS),
+        }
+    }
+}
+
+// The author's own say on the allergens and diets of their recipe, each replaces what would
+// be worked out from the ingredients when set
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DietaryOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allergens: Option<Vec<Allergen>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diets: Option<Vec<Diet>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dietary {
+    pub allergens: Vec<Allergen>,
+    pub diets: Vec<Diet>,
+}
+
+impl Dietary {
+    // Reads back the allergen and diet names stored on a recipe, names we no longer know are
+    // dropped
+    pub fn from_names(allergens: &[String], diets: &[String]) -> Dietary {
+        Dietary {
+            allergens: allergens
+                .iter()
+                .filter_map(|name| Allergen::from_name(name))
+                .collect(),
+            diets: diets
+                .iter()
+                .filter_map(|name| Diet::from_name(name))
+                .collect(),
+        }
+    }
+
+    pub fn allergen_names(&self) -> Vec<String> {
+        self.allergens
+            .iter()
+            .map(|allergen| allergen.name().to_string())
+            .collect()
+    }
+
+    pub fn diet_names(&self) -> Vec<String> {
+        self.diets
+            .iter()
+            .map(|diet| diet.name().to_string())
+            .collect()
+    }
+}
+
+impl RecipeFileJson {
+    // Works out the allergens and diets of the recipe from the profiles of its ingredients,
+    // then applies the author's override
+    pub fn dietary(&self, profiles: &[IngredientProfile]) -> Dietary {
+        let mut allergens: Vec<Allergen> = profiles
+            .iter()
+            .flat_map(|profile| profile.allergens.iter().copied())
+            .collect();
+
+        if let Some(overridden) = self.dietary.as_ref().and_then(|o| o.allergens.clone()) {
+            allergens = overridden;
+        }
+        allergens.sort();
+        allergens.dedup();
+
+        let meat = profiles.iter().any(|profile| profile.meat);
+        let animal_product = profiles.iter().any(|profile| profile.animal_product);
+        let contains = |allergen: Allergen| allergens.contains(&allergen);
+
+        let mut diets = match self.dietary.as_ref().and_then(|o| o.diets.clone()) {
+            Some(diets) => diets,
+            None => {
+                let seafood = contains(Allergen::Fish) || contains(Allergen::Shellfish);
+                let vegetarian = !meat && !seafood;
+
+                [
+                    (
+                        Diet::Vegan,
+                        vegetarian
+                            && !animal_product
+                            && !contains(Allergen::Dairy)
+                            && !contains(Allergen::Egg),
+                    ),
+                    (Diet::Vegetarian, vegetarian),
+                    (Diet::Pescatarian, !meat),
+                    (Diet::GlutenFree, !contains(Allergen::Gluten)),
+                    (Diet::DairyFree, !contains(Allergen::Dairy)),
+                    (
+                        Diet::NutFree,
+                        !contains(Allergen::Peanut) && !contains(Allergen::TreeNut),
+                    ),
+                ]
+                .into_iter()
+                .filter(|(_, holds)| *holds)
+                .map(|(diet, _)| diet)
+                .collect()
+            }
+        };
+        diets.sort();
+        diets.dedup();
+
+        Dietary { allergens, diets }
+    }
+}
+
+// Narrows recipe listings to ones that fit every diet and contain none of the allergens
+#[derive(Debug, Clone, Default)]
+pub struct DietaryFilter {
+    pub diets: Vec<Diet>,
+    pub exclude_allergens: Vec<Allergen>,
+}
+
+impl DietaryFilter {
+    pub fn diet_names(&self) -> Vec<String> {
+        self.diets
+            .iter()
+            .map(|diet| diet.name().to_string())
+            .collect()
+    }
+
+    pub fn allergen_names(&self) -> Vec<String> {
+        self.exclude_allergens
+            .iter()
+            .map(|allergen| allergen.name().to_string())
+            .collect()
+    }
+}
@@ -0,0 +1,97 @@
+use serde::Serialize;
+
+use super::{normalise::ingredient_key, RecipeFileJson, RecipeMeasurements, RecipeStep};
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Change<T> {
+    pub from: T,
+    pub to: T,
+}
+
+// What changed between two versions of a recipe, fields that didn't change are left out
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct RecipeDiff {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<Change<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<Change<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub servings: Option<Change<Option<u32>>>,
+    pub ingredients_added: Vec<RecipeMeasurements>,
+    pub ingredients_removed: Vec<RecipeMeasurements>,
+    // Same ingredient with a different amount or unit
+    pub ingredients_changed: Vec<Change<RecipeMeasurements>>,
+    pub steps_added: Vec<RecipeStep>,
+    pub steps_removed: Vec<RecipeStep>,
+    pub steps_changed: Vec<Change<RecipeStep>>,
+}
+
+impl RecipeFileJson {
+    // Ingredients are matched up by their catalogue key, so "Onions" and "onion" are the same
+    // ingredient, and steps are matched up by their order
+    pub fn diff(&self, to: &RecipeFileJson) -> RecipeDiff {
+        let mut diff = RecipeDiff {
+            title: changed(&self.title, &to.title),
+            description: changed(&self.description, &to.description),
+            servings: changed(&self.servings, &to.servings),
+            ..Default::default()
+        };
+
+        let mut unmatched: Vec<&RecipeMeasurements> = to.ingredients.iter().collect();
+        for ingredient in self.ingredients.iter() {
+            let key = ingredient_key(&ingredient.ingredient);
+            let found = unmatched
+                .iter()
+                .position(|other| ingredient_key(&other.ingredient) == key);
+
+            match found {
+                Some(index) => {
+                    let other = unmatched.remove(index);
+                    if other.amount != ingredient.amount
+                        || other.measurement != ingredient.measurement
+                    {
+                        diff.ingredients_changed.push(Change {
+                            from: ingredient.clone(),
+                            to: other.clone(),
+                        });
+                    }
+                }
+                None => diff.ingredients_removed.push(ingredient.clone()),
+            }
+        }
+        diff.ingredients_added = unmatched.into_iter().cloned().collect();
+
+        for step in self.steps.iter() {
+            match to.steps.iter().find(|other| other.order == step.order) {
+                Some(other) => {
+                    if other != step {
+                        diff.steps_changed.push(Change {
+                            from: step.clone(),
+                            to: other.clone(),
+                        });
+                    }
+                }
+                None => diff.steps_removed.push(step.clone()),
+            }
+        }
+        diff.steps_added = to
+            .steps
+            .iter()
+            .filter(|step| !self.steps.iter().any(|other| other.order == step.order))
+            .cloned()
+            .collect();
+
+        diff
+    }
+}
+
+fn changed<T: PartialEq + Clone>(from: &T, to: &T) -> Option<Change<T>> {
+    if from == to {
+        return None;
+    }
+
+    Some(Change {
+        from: from.clone(),
+        to: to.clone(),
+    })
+}
@@ -5,12 +5,29 @@ use serde::{Deserialize, Serialize};
 
 use crate::helpers::is_alnum_whitespace_and_ex_chars;
 
+use self::{amount::Amount, dietary::DietaryOverride};
+
+pub mod amount;
+pub mod conversion;
+pub mod dietary;
+pub mod diff;
+pub mod normalise;
+pub mod nutrition;
+pub mod scaling;
+pub mod shopping;
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct RecipeFileJson {
     pub title: String,
     pub description: String,
+    // How many servings the recipe makes, older recipes don't have one
+    #[serde(default)]
+    pub servings: Option<u32>,
     pub ingredients: Vec<RecipeMeasurements>,
     pub steps: Vec<RecipeStep>,
+    // The author's corrections to the allergens and diets worked out from the ingredients
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dietary: Option<DietaryOverride>,
 }
 
 impl RecipeFileJson {
@@ -21,6 +38,36 @@ impl RecipeFileJson {
             ));
         };
 
+        if self.servings == Some(0) {
+            return Err(anyhow::Error::msg(
+                "A recipe must make at least one serving",
+            ));
+        }
+
+        for ingredient in self.ingredients.iter() {
+            let name = normalise::normalise_ingredient(&ingredient.ingredient);
+            if name.is_empty() || name.len() > 100 {
+                return Err(anyhow::Error::msg(
+                    "Ingredient names must be between 1 and 100 characters",
+                ));
+            }
+        }
+
+        let measurements = self.ingredients.iter().chain(
+            self.steps
+                .iter()
+                .filter_map(|step| step.measurements.as_ref()),
+        );
+
+        for measurement in measurements {
+            if !measurement.amount.is_valid() {
+                return Err(anyhow::Error::msg(format!(
+                    "The amount of {} is invalid, ranges must go from low to high",
+                    measurement.ingredient
+                )));
+            }
+        }
+
         let mut seen_orders = HashMap::new();
         for step in self.steps.iter() {
             if !step.validate_step_details() {
@@ -39,6 +86,19 @@ impl RecipeFileJson {
         Ok(())
     }
 
+    // Fills in how every amount reads, call it on a recipe right before it is sent back
+    pub fn format_amounts(&mut self) {
+        let measurements = self.ingredients.iter_mut().chain(
+            self.steps
+                .iter_mut()
+                .filter_map(|step| step.measurements.as_mut()),
+        );
+
+        for measurement in measurements {
+            measurement.amount_display = Some(measurement.amount.to_string());
+        }
+    }
+
     fn validate_title(&self) -> bool {
         is_alnum_whitespace_and_ex_chars(&self.title)
     }
@@ -48,14 +108,18 @@ impl RecipeFileJson {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct RecipeMeasurements {
     pub ingredient: String,
     pub measurement: Measurement,
-    pub amount: u32,
+    pub amount: Amount,
+    // The amount as it reads in a recipe, e.g. "1½". Only filled in for API responses by
+    // format_amounts, it is never read from clients or stored
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub amount_display: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Measurement {
     Millilitre,
     Litre,
@@ -74,7 +138,7 @@ pub enum Measurement {
     Piece,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct RecipeStep {
     pub order: u32,
     pub step_details: String,
@@ -83,6 +147,11 @@ pub struct RecipeStep {
 
 impl RecipeStep {
     pub fn validate_step_details(&self) -> bool {
-        is_alnum_whitespace_and_ex_chars(&self.step_details)
+        Self::is_valid_details(&self.step_details)
+    }
+
+    // Also used for other free text written about a recipe, like reviews
+    pub fn is_valid_details(details: &str) -> bool {
+        is_alnum_whitespace_and_ex_chars(details)
     }
 }
@@ -0,0 +1,137 @@
+// Lowercases the name, drops punctuation and collapses whitespace, so "Burger  Buns!" and
+// "burger buns" are the same thing
+pub fn normalise_ingredient(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace() || *c == '-')
+        .collect::<String>()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+// The key an ingredient is looked up in the catalogue by, the normalised name with its last
+// word made singular, e.g. "Burger Buns" becomes "burger bun"
+pub fn ingredient_key(name: &str) -> String {
+    let name = normalise_ingredient(name);
+
+    match name.rsplit_once(' ') {
+        Some((head, last)) => format!("{} {}", head, singular(last)),
+        None => singular(&name),
+    }
+}
+
+// Words that read as plurals but are the same either way, or only come in the plural
+const INVARIANT: [&str; 14] = [
+    "asparagus",
+    "bass",
+    "citrus",
+    "couscous",
+    "cress",
+    "grits",
+    "hummus",
+    "molasses",
+    "octopus",
+    "series",
+    "species",
+    "swiss",
+    "tapas",
+    "watercress",
+];
+
+// Plurals the suffix rules below get wrong
+const IRREGULAR: [(&str, &str); 11] = [
+    ("brownies", "brownie"),
+    ("calves", "calf"),
+    ("chilies", "chili"),
+    ("cookies", "cookie"),
+    ("geese", "goose"),
+    ("halves", "half"),
+    ("leaves", "leaf"),
+    ("loaves", "loaf"),
+    ("pies", "pie"),
+    ("smoothies", "smoothie"),
+    ("veggies", "veggie"),
+];
+
+// Good enough english singulars for ingredient names, anything else irregular is left to
+// aliases
+pub fn singular(word: &str) -> String {
+    if INVARIANT.contains(&word) {
+        return word.to_string();
+    }
+
+    if let Some((_, singular)) = IRREGULAR.iter().find(|(plural, _)| *plural == word) {
+        return singular.to_string();
+    }
+
+    if word.len() <= 3 {
+        return word.to_string();
+    }
+
+    // "berries" and "anchovies", words ending in -ie like "pies" are in IRREGULAR
+    if let Some(stem) = word.strip_suffix("ies") {
+        return stem.to_string() + "y";
+    }
+
+    for suffix in ["oes", "ches", "shes", "sses", "xes"] {
+        if word.ends_with(suffix) {
+            return word[..word.len() - 2].to_string();
+        }
+    }
+
+    if word.ends_with("ss") || word.ends_with("us") || word.ends_with("is") {
+        return word.to_string();
+    }
+
+    match word.strip_suffix('s') {
+        Some(stem) => stem.to_string(),
+        None => word.to_string(),
+    }
+}
+
+// The other way round to singular, e.g. "Red Onion" becomes "Red Onions". Only the last word
+// changes and its case is kept
+pub fn plural_name(name: &str) -> String {
+    match name.rsplit_once(' ') {
+        Some((head, last)) => format!("{} {}", head, plural(last)),
+        None => plural(name),
+    }
+}
+
+// Words ending in -o that take -es in the plural
+const ES_PLURALS: [&str; 3] = ["mango", "potato", "tomato"];
+
+fn plural(word: &str) -> String {
+    let lowercase = word.to_lowercase();
+    if INVARIANT.contains(&lowercase.as_str()) {
+        return word.to_string();
+    }
+
+    if let Some((plural, _)) = IRREGULAR
+        .iter()
+        .find(|(_, singular)| *singular == lowercase)
+    {
+        return plural.to_string();
+    }
+
+    // "berry" but not "turkey"
+    if let Some(stem) = word.strip_suffix('y') {
+        if !stem.ends_with(['a', 'e', 'o', 'u']) {
+            return stem.to_string() + "ies";
+        }
+    }
+
+    // Most words ending in -o just take an -s, like "avocados"
+    if ES_PLURALS.contains(&lowercase.as_str()) {
+        return word.to_string() + "es";
+    }
+
+    for suffix in ["ch", "sh", "ss", "x"] {
+        if lowercase.ends_with(suffix) {
+            return word.to_string() + "es";
+        }
+    }
+
+    word.to_string() + "s"
+}
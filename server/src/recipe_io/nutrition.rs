@@ -0,0 +1,191 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::{conversion::Dimension, Measurement, RecipeFileJson};
+
+// The FoodData Central nutrients we report, as (nutrient id, key, unit)
+pub const TRACKED_NUTRIENTS: [(i32, &str, &str); 13] = [
+    (1008, "calories", "kcal"),
+    (1003, "protein", "g"),
+    (1004, "fat", "g"),
+    (1258, "saturated_fat", "g"),
+    (1005, "carbohydrate", "g"),
+    (1079, "fiber", "g"),
+    (2000, "sugars", "g"),
+    (1253, "cholesterol", "mg"),
+    (1093, "sodium", "mg"),
+    (1087, "calcium", "mg"),
+    (1089, "iron", "mg"),
+    (1092, "potassium", "mg"),
+    (1162, "vitamin_c", "mg"),
+];
+
+// Portion units that mean one of the thing, best first
+const PIECE_UNITS: [&str; 8] = [
+    "medium", "piece", "whole", "each", "unit", "item", "large", "small",
+];
+
+// A household measure of a food, e.g. 1 cup weighs 128 grams
+pub struct FoodPortion {
+    pub measure_unit: String,
+    pub amount: f64,
+    pub gram_weight: f64,
+}
+
+// What we know about the food a recipe ingredient is linked to
+pub struct IngredientFood {
+    pub grams_per_ml: Option<f64>,
+    pub grams_per_piece: Option<f64>,
+    // Nutrient id to amount per 100g
+    pub nutrients: HashMap<i32, f64>,
+}
+
+impl IngredientFood {
+    // Works out the density and piece weight of a food from its portions, a piece weight set
+    // on the ingredient wins over the portions
+    pub fn new(
+        grams_per_piece: Option<f64>,
+        portions: &[FoodPortion],
+        nutrients: HashMap<i32, f64>,
+    ) -> IngredientFood {
+        let portions: Vec<&FoodPortion> = portions
+            .iter()
+            .filter(|portion| portion.amount > 0.0 && portion.gram_weight > 0.0)
+            .collect();
+
+        let densities: Vec<f64> = portions
+            .iter()
+            .filter_map(|portion| {
+                portion_millilitres(&portion.measure_unit)
+                    .map(|ml| portion.gram_weight / (portion.amount * ml))
+            })
+            .collect();
+
+        let grams_per_ml = if densities.is_empty() {
+            None
+        } else {
+            Some(densities.iter().sum::<f64>() / densities.len() as f64)
+        };
+
+        let grams_per_piece = grams_per_piece.or_else(|| {
+            PIECE_UNITS.iter().find_map(|unit| {
+                portions
+                    .iter()
+                    .find(|portion| portion_unit(&portion.measure_unit) == *unit)
+                    .map(|portion| portion.gram_weight / portion.amount)
+            })
+        });
+
+        IngredientFood {
+            grams_per_ml,
+            grams_per_piece,
+            nutrients,
+        }
+    }
+}
+
+// FoodData Central units often carry a note, e.g. "cup, chopped"
+fn portion_unit(measure_unit: &str) -> String {
+    measure_unit
+        .split(',')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+// How many millilitres a portion unit holds, None if it isn't a volume
+fn portion_millilitres(measure_unit: &str) -> Option<f64> {
+    let ml = match portion_unit(measure_unit).as_str() {
+        "cup" | "cups" => Measurement::Tablespoon.to_base(16.0),
+        "tbsp" | "tablespoon" | "tablespoons" => Measurement::Tablespoon.to_base(1.0),
+        "tsp" | "teaspoon" | "teaspoons" => Measurement::Teaspoon.to_base(1.0),
+        "fl oz" | "fluid ounce" => Measurement::FluidOz.to_base(1.0),
+        "ml" | "milliliter" | "millilitre" => Measurement::Millilitre.to_base(1.0),
+        "l" | "liter" | "litre" => Measurement::Litre.to_base(1.0),
+        "pint" | "pt" => Measurement::Pint.to_base(1.0),
+        "gallon" | "gal" => Measurement::Gallon.to_base(1.0),
+        _ => return None,
+    };
+
+    Some(ml)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NutrientAmount {
+    pub amount: f64,
+    pub unit: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Nutrition {
+    pub per_recipe: BTreeMap<String, NutrientAmount>,
+    // Only there when the recipe says how many servings it makes
+    pub per_serving: Option<BTreeMap<String, NutrientAmount>>,
+    // Ingredients that couldn't be counted, either because they aren't linked to a food or
+    // we don't know how much their unit weighs
+    pub unmapped: Vec<String>,
+}
+
+impl RecipeFileJson {
+    // Totals the tracked nutrients of every ingredient, foods are keyed by the ingredient's
+    // position in the recipe. A range amount counts as its midpoint
+    pub fn nutrition(&self, foods: &HashMap<usize, IngredientFood>) -> Nutrition {
+        let mut totals: HashMap<i32, f64> = HashMap::new();
+        let mut unmapped = Vec::new();
+
+        for (position, ingredient) in self.ingredients.iter().enumerate() {
+            let Some(food) = foods.get(&position) else {
+                unmapped.push(ingredient.ingredient.clone());
+                continue;
+            };
+
+            let amount = (ingredient.amount.min() + ingredient.amount.max()) / 2.0;
+            let base = ingredient.measurement.to_base(amount);
+            let grams = match ingredient.measurement.dimension() {
+                Dimension::Mass => Some(base),
+                Dimension::Volume => food.grams_per_ml.map(|density| base * density),
+                Dimension::Count => food.grams_per_piece.map(|weight| base * weight),
+                Dimension::Temperature => None,
+            };
+
+            let Some(grams) = grams else {
+                unmapped.push(ingredient.ingredient.clone());
+                continue;
+            };
+
+            for (nutrient_id, per_100g) in food.nutrients.iter() {
+                *totals.entry(*nutrient_id).or_insert(0.0) += per_100g * grams / 100.0;
+            }
+        }
+
+        let per_recipe = nutrient_amounts(&totals, 1.0);
+        let per_serving = self
+            .servings
+            .map(|servings| nutrient_amounts(&totals, servings as f64));
+
+        Nutrition {
+            per_recipe,
+            per_serving,
+            unmapped,
+        }
+    }
+}
+
+fn nutrient_amounts(totals: &HashMap<i32, f64>, divisor: f64) -> BTreeMap<String, NutrientAmount> {
+    TRACKED_NUTRIENTS
+        .iter()
+        .map(|(id, key, unit)| {
+            let amount = totals.get(id).copied().unwrap_or(0.0) / divisor;
+
+            (
+                key.to_string(),
+                NutrientAmount {
+                    amount: (amount * 10.0).round() / 10.0,
+                    unit: unit.to_string(),
+                },
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,52 @@
+use super::{conversion::Dimension, RecipeFileJson, RecipeMeasurements, RecipeStep};
+
+impl RecipeMeasurements {
+    // Multiplies the amount by factor and rounds it to the precision of its unit.
+    // Temperatures don't change with the size of a recipe so they are left as is
+    pub fn scale(&self, factor: f64) -> RecipeMeasurements {
+        if self.measurement.dimension() == Dimension::Temperature {
+            return self.clone();
+        }
+
+        RecipeMeasurements {
+            amount: self
+                .amount
+                .map(|value| self.measurement.round(value * factor)),
+            ..self.clone()
+        }
+    }
+}
+
+impl RecipeFileJson {
+    pub fn scale(&self, factor: f64) -> RecipeFileJson {
+        RecipeFileJson {
+            servings: self
+                .servings
+                .map(|servings| ((servings as f64 * factor).round() as u32).max(1)),
+            ingredients: self
+                .ingredients
+                .iter()
+                .map(|ingredient| ingredient.scale(factor))
+                .collect(),
+            steps: self
+                .steps
+                .iter()
+                .map(|step| RecipeStep {
+                    measurements: step
+                        .measurements
+                        .as_ref()
+                        .map(|measurement| measurement.scale(factor)),
+                    ..step.clone()
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    // The factor to scale the recipe by so that it makes the given number of servings, none
+    // if the recipe doesn't say how many it serves
+    pub fn scale_for_servings(&self, servings: u32) -> Option<f64> {
+        let yields = self.servings?;
+        Some(servings as f64 / yields as f64)
+    }
+}
@@ -0,0 +1,523 @@
+use std::{collections::HashMap, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    amount::Amount,
+    conversion::{Dimension, UnitSystem},
+    dietary::{Allergen, IngredientProfile},
+    normalise::{ingredient_key, normalise_ingredient, plural_name, singular},
+    Measurement, RecipeMeasurements,
+};
+
+// Where an ingredient is found in a shop, lists are grouped by these in this order
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Aisle {
+    Produce,
+    Bakery,
+    MeatAndFish,
+    DairyAndEggs,
+    Frozen,
+    Pantry,
+    Spices,
+    Drinks,
+    Other,
+}
+
+impl Aisle {
+    pub const ALL: [Aisle; 9] = [
+        Aisle::Produce,
+        Aisle::Bakery,
+        Aisle::MeatAndFish,
+        Aisle::DairyAndEggs,
+        Aisle::Frozen,
+        Aisle::Pantry,
+        Aisle::Spices,
+        Aisle::Drinks,
+        Aisle::Other,
+    ];
+
+    // The name used in the api and the database
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aisle::Produce => "produce",
+            Aisle::Bakery => "bakery",
+            Aisle::MeatAndFish => "meat_and_fish",
+            Aisle::DairyAndEggs => "dairy_and_eggs",
+            Aisle::Frozen => "frozen",
+            Aisle::Pantry => "pantry",
+            Aisle::Spices => "spices",
+            Aisle::Drinks => "drinks",
+            Aisle::Other => "other",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Aisle> {
+        Self::ALL
+            .into_iter()
+            .find(|aisle| aisle.name() == name.trim().to_lowercase())
+    }
+
+    // The heading used in exported lists
+    pub fn title(&self) -> &'static str {
+        match self {
+            Aisle::Produce => "Produce",
+            Aisle::Bakery => "Bakery",
+            Aisle::MeatAndFish => "Meat & fish",
+            Aisle::DairyAndEggs => "Dairy & eggs",
+            Aisle::Frozen => "Frozen",
+            Aisle::Pantry => "Pantry",
+            Aisle::Spices => "Spices",
+            Aisle::Drinks => "Drinks",
+            Aisle::Other => "Other",
+        }
+    }
+
+    // Words in an ingredient name that mean it is found in the aisle, in singular form. A
+    // keyword can be two words, like "bay leaf". Meat, fish, dairy and eggs come from the
+    // ingredient's profile instead
+    fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            Aisle::Produce => &[
+                "apple",
+                "banana",
+                "lemon",
+                "lime",
+                "orange",
+                "grapefruit",
+                "grape",
+                "mango",
+                "pineapple",
+                "pear",
+                "peach",
+                "plum",
+                "cherry",
+                "melon",
+                "watermelon",
+                "strawberry",
+                "raspberry",
+                "blueberry",
+                "blackberry",
+                "berry",
+                "avocado",
+                "tomato",
+                "onion",
+                "shallot",
+                "garlic",
+                "leek",
+                "carrot",
+                "potato",
+                "parsnip",
+                "turnip",
+                "swede",
+                "beetroot",
+                "radish",
+                "celery",
+                "cucumber",
+                "courgette",
+                "zucchini",
+                "aubergine",
+                "eggplant",
+                "squash",
+                "pumpkin",
+                "lettuce",
+                "rocket",
+                "spinach",
+                "kale",
+                "cabbage",
+                "broccoli",
+                "cauliflower",
+                "asparagus",
+                "mushroom",
+                "pea",
+                "green bean",
+                "sweetcorn",
+                "ginger",
+                "chilli",
+                "chili",
+                "bell pepper",
+                "red pepper",
+                "green pepper",
+                "yellow pepper",
+                "basil",
+                "parsley",
+                "coriander",
+                "cilantro",
+                "mint",
+                "rosemary",
+                "thyme",
+                "dill",
+                "chive",
+                "sage",
+                "herb",
+                "salad",
+                "fruit",
+                "vegetable",
+            ],
+            Aisle::Bakery => &[
+                "bread",
+                "bread roll",
+                "bun",
+                "baguette",
+                "bagel",
+                "brioche",
+                "ciabatta",
+                "croissant",
+                "crumpet",
+                "focaccia",
+                "loaf",
+                "muffin",
+                "naan",
+                "pita",
+                "pitta",
+                "sourdough",
+                "tortilla",
+                "wrap",
+            ],
+            Aisle::Frozen => &["frozen", "ice", "ice cream", "sorbet"],
+            Aisle::Pantry => &[
+                "flour",
+                "cornflour",
+                "cornstarch",
+                "sugar",
+                "rice",
+                "pasta",
+                "spaghetti",
+                "macaroni",
+                "penne",
+                "fusilli",
+                "lasagne",
+                "noodle",
+                "couscous",
+                "quinoa",
+                "oat",
+                "lentil",
+                "chickpea",
+                "oil",
+                "vinegar",
+                "stock",
+                "broth",
+                "bouillon",
+                "honey",
+                "syrup",
+                "jam",
+                "sauce",
+                "ketchup",
+                "mayonnaise",
+                "mustard",
+                "paste",
+                "tinned",
+                "canned",
+                "baking powder",
+                "baking soda",
+                "bicarbonate",
+                "yeast",
+                "cocoa",
+                "chocolate",
+                "vanilla",
+                "breadcrumb",
+                "cereal",
+                "coconut milk",
+                "peanut butter",
+                "almond",
+                "cashew",
+                "hazelnut",
+                "pecan",
+                "pistachio",
+                "walnut",
+                "raisin",
+                "sultana",
+                "biscuit",
+                "cracker",
+                "crisp",
+            ],
+            Aisle::Spices => &[
+                "salt",
+                "black pepper",
+                "white pepper",
+                "ground pepper",
+                "peppercorn",
+                "paprika",
+                "cumin",
+                "cinnamon",
+                "nutmeg",
+                "turmeric",
+                "cayenne",
+                "cardamom",
+                "saffron",
+                "allspice",
+                "oregano",
+                "bay leaf",
+                "star anise",
+                "chilli flake",
+                "chili flake",
+                "chilli powder",
+                "chili powder",
+                "curry powder",
+                "garlic powder",
+                "onion powder",
+                "garam masala",
+                "seasoning",
+                "spice",
+            ],
+            Aisle::Drinks => &[
+                "beer",
+                "lager",
+                "cider",
+                "wine",
+                "coffee",
+                "espresso",
+                "tea",
+                "juice",
+                "lemonade",
+                "cola",
+                "soda",
+                "tonic",
+                "sparkling water",
+                "mineral water",
+            ],
+            Aisle::MeatAndFish | Aisle::DairyAndEggs | Aisle::Other => &[],
+        }
+    }
+
+    // The aisle the words in an ingredient's name point to, None if none of them do. Aisles
+    // are tried from the most to the least specific, so "frozen peas" are frozen and "tomato
+    // sauce" is in the pantry
+    pub fn from_keywords(name: &str) -> Option<Aisle> {
+        let normalised = normalise_ingredient(name);
+        let words: Vec<String> = normalised.split(' ').map(singular).collect();
+        let pairs: Vec<String> = words
+            .windows(2)
+            .map(|pair| format!("{} {}", pair[0], pair[1]))
+            .collect();
+
+        GUESS_ORDER.into_iter().find(|aisle| {
+            words
+                .iter()
+                .chain(pairs.iter())
+                .any(|word| aisle.keywords().contains(&word.as_str()))
+        })
+    }
+
+    // Meat, fish, dairy and eggs, from what the ingredient contains
+    pub fn from_profile(profile: &IngredientProfile) -> Option<Aisle> {
+        let has = |allergen: Allergen| profile.allergens.contains(&allergen);
+
+        if profile.meat || has(Allergen::Fish) || has(Allergen::Shellfish) {
+            Some(Aisle::MeatAndFish)
+        } else if has(Allergen::Dairy) || has(Allergen::Egg) {
+            Some(Aisle::DairyAndEggs)
+        } else {
+            None
+        }
+    }
+
+    // A best guess for ingredients that aren't in the catalogue
+    pub fn guess(name: &str) -> Aisle {
+        Self::from_keywords(name)
+            .or_else(|| Self::from_profile(&IngredientProfile::guess(name)))
+            .unwrap_or(Aisle::Other)
+    }
+}
+
+const GUESS_ORDER: [Aisle; 6] = [
+    Aisle::Frozen,
+    Aisle::Pantry,
+    Aisle::Spices,
+    Aisle::Bakery,
+    Aisle::Drinks,
+    Aisle::Produce,
+];
+
+// How much of something to buy
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    pub measurement: Measurement,
+    pub amount: Amount,
+}
+
+impl Quantity {
+    // Whether there is more than one of something, which reads in the plural
+    pub fn is_several(&self) -> bool {
+        self.measurement.dimension() == Dimension::Count && self.amount.max() > 1.0
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unit = match self.measurement {
+            Measurement::Millilitre => "ml",
+            Measurement::Litre => "l",
+            Measurement::Teaspoon => "tsp",
+            Measurement::Tablespoon => "tbsp",
+            Measurement::FluidOz => "fl oz",
+            Measurement::Pint => "pt",
+            Measurement::Gallon => "gal",
+            Measurement::Milligram => "mg",
+            Measurement::Gram => "g",
+            Measurement::Kilogram => "kg",
+            Measurement::Pound => "lb",
+            Measurement::Ounce => "oz",
+            Measurement::Celsius => "°C",
+            Measurement::Fahrenheit => "°F",
+            // Pieces read best without a unit, e.g. "3 onions"
+            Measurement::Piece => return write!(f, "{}", self.amount),
+        };
+
+        write!(f, "{} {}", self.amount, unit)
+    }
+}
+
+// One ingredient of a recipe on its way to a shopping list
+#[derive(Debug, Clone)]
+pub struct ShoppingIngredient {
+    // The catalogue entry, ingredients linked to the same entry are combined
+    pub ingredient_id: Option<i32>,
+    pub name: String,
+    // The catalogue's plural of the name, if it has one
+    pub plural: Option<String>,
+    pub aisle: Aisle,
+    pub quantity: Quantity,
+}
+
+impl ShoppingIngredient {
+    pub fn from_measurements(
+        measurements: &RecipeMeasurements,
+        ingredient_id: Option<i32>,
+        plural: Option<String>,
+        aisle: Aisle,
+    ) -> ShoppingIngredient {
+        ShoppingIngredient {
+            ingredient_id,
+            name: measurements.ingredient.trim().to_string(),
+            plural,
+            aisle,
+            quantity: Quantity {
+                measurement: measurements.measurement,
+                amount: measurements.amount,
+            },
+        }
+    }
+
+    // The name to put on the list, in the plural when buying more than one of something.
+    // Names the recipe already gives in the plural are left alone
+    pub fn list_name(&self) -> String {
+        if !self.quantity.is_several()
+            || ingredient_key(&self.name) != normalise_ingredient(&self.name)
+        {
+            return self.name.clone();
+        }
+
+        self.plural
+            .clone()
+            .unwrap_or_else(|| plural_name(&self.name))
+    }
+}
+
+// What makes two ingredients the same thing to buy
+#[derive(PartialEq, Eq, Hash)]
+enum CombineKey {
+    // The catalogue entry they are linked to
+    Catalogue(i32),
+    // The catalogue key of their name, for ingredients that aren't linked
+    Name(String),
+}
+
+// Adds up the same ingredient across recipes. Amounts in units that convert to each other
+// are summed, kept in their unit if they all share one, otherwise given in the unit that
+// reads best in the unit system. Amounts that can't be converted, like pieces and grams,
+// stay separate. Temperatures aren't something you buy so they are left out. Items come out
+// in the order they were first seen
+pub fn combine(ingredients: &[ShoppingIngredient], system: UnitSystem) -> Vec<ShoppingIngredient> {
+    let mut groups: Vec<Vec<&ShoppingIngredient>> = Vec::new();
+    let mut positions: HashMap<(CombineKey, Dimension), usize> = HashMap::new();
+
+    for ingredient in ingredients {
+        let dimension = ingredient.quantity.measurement.dimension();
+        if dimension == Dimension::Temperature {
+            continue;
+        }
+
+        let key = match ingredient.ingredient_id {
+            Some(id) => CombineKey::Catalogue(id),
+            None => CombineKey::Name(ingredient_key(&ingredient.name)),
+        };
+
+        let position = *positions.entry((key, dimension)).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[position].push(ingredient);
+    }
+
+    groups
+        .iter()
+        .map(|group| combine_group(group, system))
+        .collect()
+}
+
+fn combine_group(group: &[&ShoppingIngredient], system: UnitSystem) -> ShoppingIngredient {
+    let first = group[0];
+    let unit = first.quantity.measurement;
+
+    if group
+        .iter()
+        .all(|ingredient| ingredient.quantity.measurement == unit)
+    {
+        let (min, max) = group.iter().fold((0.0, 0.0), |(min, max), ingredient| {
+            (
+                min + ingredient.quantity.amount.min(),
+                max + ingredient.quantity.amount.max(),
+            )
+        });
+
+        return ShoppingIngredient {
+            quantity: Quantity {
+                measurement: unit,
+                amount: amount_between(min, max).map(|value| unit.round(value)),
+            },
+            ..first.clone()
+        };
+    }
+
+    let (min, max) = group.iter().fold((0.0, 0.0), |(min, max), ingredient| {
+        let measurement = ingredient.quantity.measurement;
+        (
+            min + measurement.to_base(ingredient.quantity.amount.min()),
+            max + measurement.to_base(ingredient.quantity.amount.max()),
+        )
+    });
+
+    let base_unit = match unit.dimension() {
+        Dimension::Volume => Measurement::Millilitre,
+        Dimension::Mass => Measurement::Gram,
+        Dimension::Temperature => Measurement::Celsius,
+        Dimension::Count => Measurement::Piece,
+    };
+
+    let converted = RecipeMeasurements {
+        ingredient: first.name.clone(),
+        measurement: base_unit,
+        amount: amount_between(min, max),
+        amount_display: None,
+    }
+    .to_system(system);
+    let measurement = converted.measurement;
+
+    ShoppingIngredient {
+        quantity: Quantity {
+            measurement,
+            amount: converted.amount.map(|value| measurement.round(value)),
+        },
+        ..first.clone()
+    }
+}
+
+fn amount_between(min: f64, max: f64) -> Amount {
+    if min == max {
+        Amount::Single(min)
+    } else {
+        Amount::Range(min, max)
+    }
+}
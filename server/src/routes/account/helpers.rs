@@ -3,7 +3,10 @@ use actix_web::HttpResponse;
 use serde::Deserialize;
 
 use crate::{
-    database::models::user_details::UserDetails, helpers::is_alnum_whitespace_and_ex_chars, pretty_error, routes::error::PrettyErrorResponse
+    database::models::{follow::FeedCursor, user_details::UserDetails},
+    helpers::is_alnum_whitespace_and_ex_chars,
+    pretty_error,
+    routes::{error::PrettyErrorResponse, recipes::helpers::GetRecipeQueryParams},
 };
 
 #[derive(Deserialize)]
@@ -55,6 +58,42 @@ impl UpdateUserDetailsPayload {
     }
 }
 
+// The feed is paged with the cursor returned alongside the previous page
+#[derive(Deserialize)]
+pub struct FeedQueryParams {
+    pub cursor: Option<String>,
+    pub limit: Option<u32>,
+}
+
+impl FeedQueryParams {
+    #[allow(clippy::result_large_err)]
+    pub fn cursor(&self) -> Result<Option<FeedCursor>, HttpResponse> {
+        let Some(cursor) = &self.cursor else {
+            return Ok(None);
+        };
+
+        match FeedCursor::decode(cursor) {
+            Some(cursor) => Ok(Some(cursor)),
+            None => {
+                pretty_error!(
+                    "Invalid cursor".to_string(),
+                    "Please pass the cursor given with the previous page",
+                    error
+                );
+
+                Err(HttpResponse::BadRequest().json(error))
+            }
+        }
+    }
+
+    pub fn pagination(&self) -> GetRecipeQueryParams {
+        GetRecipeQueryParams {
+            offset: None,
+            limit: self.limit,
+        }
+    }
+}
+
 #[derive(Debug, MultipartForm)]
 pub struct UploadPictureForm {
     #[multipart(limit = "2MB")]
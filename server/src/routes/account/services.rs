@@ -1,18 +1,23 @@
 use actix_multipart::form::MultipartForm;
-use actix_web::web::Data;
+use actix_web::web::{self, Data};
 use actix_web::{HttpResponse, Responder};
 use serde_json::json;
 use sqlx::{Pool, Postgres};
 
+use crate::database::models::favourite::Favourite;
+use crate::database::models::follow::Follow;
 use crate::database::models::profile_picture::ProfilePicture;
 use crate::database::models::user::User;
 use crate::database::models::user_details::UserDetails;
 use crate::extractors::auth::Authorized;
 use crate::pretty_error;
 use crate::routes::error::PrettyErrorResponse;
-use crate::static_files::helpers::rename_temp_file;
+use crate::routes::recipes::helpers::GetRecipeQueryParams;
+use crate::static_files::helpers::save_temp_file;
+use crate::storage::constants::PROFILE_PICTURE_DIR;
+use crate::storage::{key, Storage};
 
-use super::helpers::{UpdateUserDetailsPayload, UploadPictureForm};
+use super::helpers::{FeedQueryParams, UpdateUserDetailsPayload, UploadPictureForm};
 
 pub async fn verify_jwt(authorized: Authorized) -> impl Responder {
     if let Authorized::Failed(reason) = authorized {
@@ -68,6 +73,7 @@ pub async fn get_account_details(
 pub async fn update_account_details(
     MultipartForm(form): MultipartForm<UploadPictureForm>,
     pool: Data<Pool<Postgres>>,
+    storage: Data<dyn Storage>,
     authorized: Authorized,
 ) -> impl Responder {
     if let Authorized::Failed(reason) = authorized {
@@ -110,7 +116,7 @@ pub async fn update_account_details(
     };
 
     if let Some(picture) = form.picture {
-        match rename_temp_file(picture, "./profile_pictures", &uid.to_string()) {
+        match save_temp_file(&**storage, picture, PROFILE_PICTURE_DIR, &uid.to_string()).await {
             Ok(file_name) => {
                 if let Err(e) =
                     ProfilePicture::insert_or_update(&pool, uid, file_name.clone()).await
@@ -162,6 +168,7 @@ pub async fn get_profile_picture(
 pub async fn delete_profile_picture(
     authorized: Authorized,
     pool: Data<Pool<Postgres>>,
+    storage: Data<dyn Storage>,
 ) -> impl Responder {
     if let Authorized::Failed(reason) = authorized {
         pretty_error!("Unauthorized", reason, error);
@@ -174,8 +181,24 @@ pub async fn delete_profile_picture(
         panic!("Despite the if let authorized::failed, we still panicked");
     };
 
+    let pfp = ProfilePicture::get_by_user_id(&pool, uid).await;
+    if let Err(e) = pfp {
+        pretty_error!("Failed to delete profile picture", e.to_string(), error);
+
+        return HttpResponse::InternalServerError().json(error);
+    }
+
     match ProfilePicture::delete_by_user_id(&pool, uid).await {
-        Ok(..) => HttpResponse::Ok().body("Succesfully deleted profile picture"),
+        Ok(..) => {
+            // The row is already gone, so a file we fail to remove is only wasted space
+            if let Some(picture_path) = pfp.unwrap().and_then(|pfp| pfp.picture_path) {
+                let _ = storage
+                    .delete(&key(PROFILE_PICTURE_DIR, &picture_path))
+                    .await;
+            }
+
+            HttpResponse::Ok().body("Succesfully deleted profile picture")
+        }
         Err(e) => {
             pretty_error!("Failed to delete profile picture", e.to_string(), error);
 
@@ -183,3 +206,105 @@ pub async fn delete_profile_picture(
         }
     }
 }
+
+pub async fn get_favourites(
+    authorized: Authorized,
+    pagination: web::Query<GetRecipeQueryParams>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let recipes = Favourite::get_recipes(&pool, uid, pagination.offset(), pagination.limit()).await;
+    if let Err(e) = recipes {
+        pretty_error!("Failed to get favourites", e.to_string(), error);
+
+        return HttpResponse::InternalServerError().json(error);
+    }
+    let recipes = recipes.unwrap();
+
+    let mut json_values: Vec<serde_json::Value> = Vec::new();
+    for recipe in recipes.iter() {
+        let value = json!({
+            "poster": recipe.poster,
+            "id": recipe.id,
+            "title": recipe.recipe.title,
+            "description": recipe.recipe.description,
+            "thumbnail": recipe.thumbnail,
+            "dietary": recipe.dietary,
+            "favourites": recipe.favourites,
+            "favourited": recipe.favourited,
+            "rating": recipe.rating,
+        });
+
+        json_values.push(value);
+    }
+
+    HttpResponse::Ok().json(json_values)
+}
+
+pub async fn get_feed(
+    authorized: Authorized,
+    params: web::Query<FeedQueryParams>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let cursor = match params.cursor() {
+        Ok(cursor) => cursor,
+        Err(e) => return e,
+    };
+
+    let limit = params.pagination().limit();
+    let feed = Follow::get_feed(&pool, uid, cursor, limit).await;
+    if let Err(e) = feed {
+        pretty_error!("Failed to get feed", e.to_string(), error);
+
+        return HttpResponse::InternalServerError().json(error);
+    }
+    let feed = feed.unwrap();
+
+    // A short page means there is nothing left to get
+    let next_cursor = match feed.last() {
+        Some((_, cursor)) if feed.len() as u32 == limit => Some(cursor.encode()),
+        _ => None,
+    };
+
+    let mut json_values: Vec<serde_json::Value> = Vec::new();
+    for (recipe, cursor) in feed.iter() {
+        let value = json!({
+            "poster": recipe.poster,
+            "id": recipe.id,
+            "title": recipe.recipe.title,
+            "description": recipe.recipe.description,
+            "thumbnail": recipe.thumbnail,
+            "dietary": recipe.dietary,
+            "favourites": recipe.favourites,
+            "favourited": recipe.favourited,
+            "rating": recipe.rating,
+            "published": cursor.published,
+        });
+
+        json_values.push(value);
+    }
+
+    HttpResponse::Ok().json(json!({
+        "recipes": json_values,
+        "next_cursor": next_cursor,
+    }))
+}
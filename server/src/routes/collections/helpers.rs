@@ -0,0 +1,92 @@
+use actix_web::HttpResponse;
+use serde::Deserialize;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    database::models::collection::Collection, helpers::is_alnum_whitespace_and_ex_chars,
+    pretty_error, routes::error::PrettyErrorResponse,
+};
+
+#[derive(Deserialize)]
+pub struct CollectionPayload {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub public: bool,
+}
+
+impl CollectionPayload {
+    #[allow(clippy::result_large_err)]
+    pub fn verify(&self) -> Result<(), HttpResponse> {
+        // The name is stored trimmed, so that is what has to be valid
+        let name = self.name.trim();
+        if name.chars().count() > 50 || !is_alnum_whitespace_and_ex_chars(name) {
+            pretty_error!(
+                "This collection name is invalid".to_string(),
+                "Please only use alphanumerical characters, up to 50 of them",
+                error
+            );
+
+            return Err(HttpResponse::BadRequest().json(error));
+        }
+
+        if let Some(description) = &self.description {
+            if description.chars().count() > 255 || !is_alnum_whitespace_and_ex_chars(description) {
+                pretty_error!(
+                    "This collection description is invalid".to_string(),
+                    "Please only use alphanumerical characters, up to 255 of them",
+                    error
+                );
+
+                return Err(HttpResponse::BadRequest().json(error));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CollectionRecipePayload {
+    pub recipe_id: i32,
+}
+
+#[derive(Deserialize)]
+pub struct ReorderCollectionPayload {
+    // Every recipe in the collection, in the new order
+    pub recipe_ids: Vec<i32>,
+}
+
+// Gets a collection for changing it, only its owner is allowed to
+pub async fn find_own_collection(
+    pool: &Pool<Postgres>,
+    collection_id: i32,
+    uid: i32,
+) -> Result<Collection, HttpResponse> {
+    match Collection::get(pool, collection_id, Some(uid)).await {
+        Ok(Some(collection)) if collection.user_id == uid => Ok(collection),
+        Ok(Some(..)) => {
+            pretty_error!(
+                "Failed to change collection",
+                "Owner id and submitter id do not match",
+                error
+            );
+
+            Err(HttpResponse::Unauthorized().json(error))
+        }
+        Ok(None) => {
+            pretty_error!(
+                "No collection found".to_string(),
+                format!("Couldn't find collection with the id: {}", collection_id),
+                error
+            );
+
+            Err(HttpResponse::NotFound().json(error))
+        }
+        Err(e) => {
+            pretty_error!("Failed to get collection", e.to_string(), error);
+
+            Err(HttpResponse::InternalServerError().json(error))
+        }
+    }
+}
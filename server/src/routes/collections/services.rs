@@ -0,0 +1,368 @@
+use actix_web::{
+    get,
+    web::{self, Data, Path},
+    HttpResponse, Responder,
+};
+use serde_json::json;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    database::models::{collection::Collection, recipe::Recipe},
+    extractors::auth::{Authorized, Viewer},
+    pretty_error,
+    routes::error::PrettyErrorResponse,
+};
+
+use super::helpers::{
+    find_own_collection, CollectionPayload, CollectionRecipePayload, ReorderCollectionPayload,
+};
+
+// #[get(/collections)]
+pub async fn get_own_collections(
+    authorized: Authorized,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match Collection::get_by_user(&pool, uid, true, Some(uid)).await {
+        Ok(collections) => HttpResponse::Ok().json(collections),
+        Err(e) => {
+            pretty_error!("Failed to get collections", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/collections)]
+pub async fn create_collection(
+    authorized: Authorized,
+    payload: web::Json<CollectionPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = payload.verify() {
+        return e;
+    }
+
+    match Collection::insert(
+        &pool,
+        uid,
+        payload.name.trim(),
+        &payload.description,
+        payload.public,
+    )
+    .await
+    {
+        Ok(collection_id) => HttpResponse::Ok().body(collection_id.to_string()),
+        Err(e) => {
+            pretty_error!("Failed to create collection", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/collections/{collection_id})]
+pub async fn update_collection(
+    authorized: Authorized,
+    path: Path<i32>,
+    payload: web::Json<CollectionPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let collection_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = find_own_collection(&pool, collection_id, uid).await {
+        return e;
+    }
+
+    if let Err(e) = payload.verify() {
+        return e;
+    }
+
+    match Collection::update(
+        &pool,
+        collection_id,
+        payload.name.trim(),
+        &payload.description,
+        payload.public,
+    )
+    .await
+    {
+        Ok(..) => HttpResponse::Ok().body("Succesfully updated collection"),
+        Err(e) => {
+            pretty_error!("Failed to update collection", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[delete(/collections/{collection_id})]
+pub async fn delete_collection(
+    authorized: Authorized,
+    path: Path<i32>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let collection_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = find_own_collection(&pool, collection_id, uid).await {
+        return e;
+    }
+
+    match Collection::delete(&pool, collection_id).await {
+        Ok(..) => HttpResponse::Ok().body("Succesfully deleted collection"),
+        Err(e) => {
+            pretty_error!("Failed to delete collection", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/collections/{collection_id}/recipes)]
+pub async fn add_collection_recipe(
+    authorized: Authorized,
+    path: Path<i32>,
+    payload: web::Json<CollectionRecipePayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let collection_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = find_own_collection(&pool, collection_id, uid).await {
+        return e;
+    }
+
+    // Someone else's drafts and private recipes can't be collected
+    match Recipe::get_by_id(&pool, payload.recipe_id, Some(uid)).await {
+        Ok(recipe) if recipe.can_view(Some(uid)) => {}
+        _ => {
+            pretty_error!(
+                "No recipe found".to_string(),
+                format!("Couldn't find recipe with the id: {}", payload.recipe_id),
+                error
+            );
+
+            return HttpResponse::NotFound().json(error);
+        }
+    }
+
+    match Collection::add_recipe(&pool, collection_id, payload.recipe_id).await {
+        Ok(..) => HttpResponse::Ok().body("Succesfully added recipe to collection"),
+        Err(e) => {
+            pretty_error!("Failed to add recipe to collection", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[delete(/collections/{collection_id}/recipes/{recipe_id})]
+pub async fn remove_collection_recipe(
+    authorized: Authorized,
+    path: Path<(i32, i32)>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let (collection_id, recipe_id) = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = find_own_collection(&pool, collection_id, uid).await {
+        return e;
+    }
+
+    match Collection::remove_recipe(&pool, collection_id, recipe_id).await {
+        Ok(true) => HttpResponse::Ok().body("Succesfully removed recipe from collection"),
+        Ok(false) => {
+            pretty_error!(
+                "No recipe found".to_string(),
+                format!(
+                    "Couldn't find recipe with the id: {} in the collection",
+                    recipe_id
+                ),
+                error
+            );
+
+            HttpResponse::NotFound().json(error)
+        }
+        Err(e) => {
+            pretty_error!(
+                "Failed to remove recipe from collection",
+                e.to_string(),
+                error
+            );
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/collections/{collection_id}/order)]
+pub async fn reorder_collection(
+    authorized: Authorized,
+    path: Path<i32>,
+    payload: web::Json<ReorderCollectionPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let collection_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = find_own_collection(&pool, collection_id, uid).await {
+        return e;
+    }
+
+    match Collection::reorder(&pool, collection_id, uid, &payload.recipe_ids).await {
+        Ok(true) => HttpResponse::Ok().body("Succesfully reordered collection"),
+        Ok(false) => {
+            pretty_error!(
+                "Invalid order",
+                "Every recipe in the collection you can see must be given exactly once",
+                error
+            );
+
+            HttpResponse::BadRequest().json(error)
+        }
+        Err(e) => {
+            pretty_error!("Failed to reorder collection", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+#[get("/{collection_id}")]
+pub async fn get_collection(
+    pool: Data<Pool<Postgres>>,
+    path: Path<i32>,
+    viewer: Viewer,
+) -> impl Responder {
+    let collection_id = path.into_inner();
+
+    // Private collections are reported as missing to anyone but their owner
+    let collection = match Collection::get(&pool, collection_id, viewer.0).await {
+        Ok(Some(collection)) if collection.public || viewer.0 == Some(collection.user_id) => {
+            collection
+        }
+        Ok(_) => {
+            pretty_error!(
+                "No collection found".to_string(),
+                format!("Couldn't find collection with the id: {}", collection_id),
+                error
+            );
+
+            return HttpResponse::NotFound().json(error);
+        }
+        Err(e) => {
+            pretty_error!("Failed to get collection", e.to_string(), error);
+
+            return HttpResponse::InternalServerError().json(error);
+        }
+    };
+
+    let recipes = Collection::get_recipes(&pool, collection_id, viewer.0).await;
+    if let Err(e) = recipes {
+        pretty_error!("Failed to get collection", e.to_string(), error);
+
+        return HttpResponse::InternalServerError().json(error);
+    }
+    let recipes = recipes.unwrap();
+
+    let mut json_values: Vec<serde_json::Value> = Vec::new();
+    for recipe in recipes.iter() {
+        let value = json!({
+            "poster": recipe.poster,
+            "id": recipe.id,
+            "title": recipe.recipe.title,
+            "description": recipe.recipe.description,
+            "thumbnail": recipe.thumbnail,
+            "dietary": recipe.dietary,
+            "favourites": recipe.favourites,
+            "favourited": recipe.favourited,
+            "rating": recipe.rating,
+        });
+
+        json_values.push(value);
+    }
+
+    HttpResponse::Ok().json(json!({
+        "collection": collection,
+        "recipes": json_values,
+    }))
+}
+
+#[get("/user/{user_id}")]
+pub async fn get_user_collections(
+    pool: Data<Pool<Postgres>>,
+    path: Path<i32>,
+    viewer: Viewer,
+) -> impl Responder {
+    let user_id = path.into_inner();
+
+    match Collection::get_by_user(&pool, user_id, viewer.0 == Some(user_id), viewer.0).await {
+        Ok(collections) => HttpResponse::Ok().json(collections),
+        Err(e) => {
+            pretty_error!("Failed to get collections", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use actix_web::HttpResponse;
+use serde::Deserialize;
+
+use crate::{
+    helpers::is_alnum_whitespace_and_ex_chars,
+    pretty_error,
+    routes::{error::PrettyErrorResponse, recipes::helpers::GetRecipeQueryParams},
+};
+
+const MAX_COMMENT_LENGTH: usize = 1000;
+
+#[derive(Deserialize)]
+pub struct CommentPayload {
+    pub body: String,
+    // The comment being replied to
+    pub parent_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct EditCommentPayload {
+    pub body: String,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentOrder {
+    #[default]
+    Oldest,
+    Newest,
+}
+
+#[derive(Deserialize)]
+pub struct GetCommentsQueryParams {
+    pub order: Option<CommentOrder>,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+impl GetCommentsQueryParams {
+    pub fn pagination(&self) -> GetRecipeQueryParams {
+        GetRecipeQueryParams {
+            offset: self.offset,
+            limit: self.limit,
+        }
+    }
+}
+
+#[allow(clippy::result_large_err)]
+pub fn verify_comment_body(body: &str) -> Result<(), HttpResponse> {
+    if body.chars().count() > MAX_COMMENT_LENGTH || !is_alnum_whitespace_and_ex_chars(body) {
+        pretty_error!(
+            "This comment is invalid".to_string(),
+            format!(
+                "Please only use alphanumerical characters, up to {} of them",
+                MAX_COMMENT_LENGTH
+            ),
+            error
+        );
+
+        return Err(HttpResponse::BadRequest().json(error));
+    }
+
+    Ok(())
+}
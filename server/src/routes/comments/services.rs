@@ -0,0 +1,278 @@
+use actix_web::{
+    get,
+    web::{self, Data, Path},
+    HttpResponse, Responder,
+};
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    database::models::{
+        comment::{Comment, CommentOwner},
+        notification::{Notification, NotificationKind},
+        recipe::Recipe,
+    },
+    extractors::auth::{Authorized, Viewer},
+    pretty_error,
+    routes::{error::PrettyErrorResponse, recipes::helpers::find_viewable_recipe},
+};
+
+use super::helpers::{
+    verify_comment_body, CommentOrder, CommentPayload, EditCommentPayload, GetCommentsQueryParams,
+};
+
+#[get("/{recipe_id}/comments")]
+pub async fn get_recipe_comments(
+    pool: Data<Pool<Postgres>>,
+    path: Path<i32>,
+    params: web::Query<GetCommentsQueryParams>,
+    viewer: Viewer,
+) -> impl Responder {
+    let recipe_id = path.into_inner();
+    if let Err(e) = find_viewable_recipe(&pool, recipe_id, viewer.0).await {
+        return e;
+    }
+
+    let newest_first = matches!(params.order.unwrap_or_default(), CommentOrder::Newest);
+    let pagination = params.pagination();
+    match Comment::get_threads(
+        &pool,
+        recipe_id,
+        newest_first,
+        pagination.offset(),
+        pagination.limit(),
+    )
+    .await
+    {
+        Ok(threads) => HttpResponse::Ok().json(threads),
+        Err(e) => {
+            pretty_error!("Failed to get comments", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/{recipe_id}/comments)]
+pub async fn comment_on_recipe(
+    authorized: Authorized,
+    path: Path<i32>,
+    payload: web::Json<CommentPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let recipe_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let recipe = match find_viewable_recipe(&pool, recipe_id, Some(uid)).await {
+        Ok(recipe) => recipe,
+        Err(e) => return e,
+    };
+
+    let body = payload.body.trim();
+    if let Err(e) = verify_comment_body(body) {
+        return e;
+    }
+
+    match Comment::insert(&pool, recipe_id, uid, payload.parent_id, body).await {
+        Ok(Some(comment_id)) => {
+            // The comment is saved either way, so a missed notification doesn't fail it
+            let _ = Notification::notify(
+                &pool,
+                recipe.poster.uid,
+                NotificationKind::Comment,
+                uid,
+                Some(recipe_id),
+                Some(comment_id),
+            )
+            .await;
+
+            // Whoever was replied to hears about it too
+            if let Some(parent_id) = payload.parent_id {
+                if let Ok(Some(parent)) = Comment::get_owner(&pool, parent_id).await {
+                    if parent.user_id != recipe.poster.uid && !parent.deleted {
+                        let _ = Notification::notify(
+                            &pool,
+                            parent.user_id,
+                            NotificationKind::Comment,
+                            uid,
+                            Some(recipe_id),
+                            Some(comment_id),
+                        )
+                        .await;
+                    }
+                }
+            }
+
+            HttpResponse::Ok().body(comment_id.to_string())
+        }
+        Ok(None) => {
+            pretty_error!(
+                "No comment found".to_string(),
+                "Couldn't find the comment being replied to on this recipe",
+                error
+            );
+
+            HttpResponse::NotFound().json(error)
+        }
+        Err(e) => {
+            pretty_error!("Failed to comment on recipe", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/{recipe_id}/comments/{comment_id})]
+pub async fn edit_comment(
+    authorized: Authorized,
+    path: Path<(i32, i32)>,
+    payload: web::Json<EditCommentPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let (recipe_id, comment_id) = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let comment = match find_comment(&pool, recipe_id, comment_id).await {
+        Ok(comment) => comment,
+        Err(e) => return e,
+    };
+
+    if comment.user_id != uid {
+        pretty_error!(
+            "Failed to edit comment",
+            "Author id and submitter id do not match",
+            error
+        );
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    if comment.deleted {
+        pretty_error!(
+            "Failed to edit comment",
+            "The comment has been deleted",
+            error
+        );
+
+        return HttpResponse::BadRequest().json(error);
+    }
+
+    let body = payload.body.trim();
+    if let Err(e) = verify_comment_body(body) {
+        return e;
+    }
+
+    match Comment::update(&pool, comment_id, body).await {
+        Ok(..) => HttpResponse::Ok().body("Succesfully edited comment"),
+        Err(e) => {
+            pretty_error!("Failed to edit comment", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[delete(/{recipe_id}/comments/{comment_id})]
+pub async fn delete_comment(
+    authorized: Authorized,
+    path: Path<(i32, i32)>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let (recipe_id, comment_id) = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let comment = match find_comment(&pool, recipe_id, comment_id).await {
+        Ok(comment) => comment,
+        Err(e) => return e,
+    };
+
+    // The author's own deletes leave the replies in place, the recipe's poster can still
+    // remove the whole thread afterwards
+    if comment.user_id == uid && !comment.deleted {
+        return match Comment::soft_delete(&pool, comment_id).await {
+            Ok(..) => HttpResponse::Ok().body("Succesfully deleted comment"),
+            Err(e) => {
+                pretty_error!("Failed to delete comment", e.to_string(), error);
+
+                HttpResponse::InternalServerError().json(error)
+            }
+        };
+    }
+
+    let poster_id = Recipe::get_poster(&pool, recipe_id).await;
+    if let Err(e) = poster_id {
+        pretty_error!(
+            format!("Failed to get poster of recipe with id: {}", recipe_id),
+            e.to_string(),
+            error
+        );
+
+        return HttpResponse::InternalServerError().json(error);
+    }
+
+    if poster_id.unwrap() != uid {
+        pretty_error!(
+            "Failed to delete comment",
+            "Only the author of the comment or the poster of the recipe can delete it",
+            error
+        );
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    match Comment::delete(&pool, comment_id).await {
+        Ok(..) => HttpResponse::Ok().body("Succesfully deleted comment"),
+        Err(e) => {
+            pretty_error!("Failed to delete comment", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+async fn find_comment(
+    pool: &Pool<Postgres>,
+    recipe_id: i32,
+    comment_id: i32,
+) -> Result<CommentOwner, HttpResponse> {
+    match Comment::get_owner(pool, comment_id).await {
+        Ok(Some(comment)) if comment.recipe_id == recipe_id => Ok(comment),
+        Ok(_) => {
+            pretty_error!(
+                "No comment found".to_string(),
+                format!("Couldn't find comment with the id: {}", comment_id),
+                error
+            );
+
+            Err(HttpResponse::NotFound().json(error))
+        }
+        Err(e) => {
+            pretty_error!("Failed to get comment", e.to_string(), error);
+
+            Err(HttpResponse::InternalServerError().json(error))
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use actix_multipart::form::{tempfile::TempFile, text::Text, MultipartForm};
+use actix_web::HttpResponse;
+use chrono::{Days, NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::{
+    helpers::is_alnum_whitespace_and_ex_chars, pretty_error, routes::error::PrettyErrorResponse,
+};
+
+const MAX_NOTES_LENGTH: usize = 2000;
+
+#[derive(Debug, MultipartForm)]
+pub struct CookLogForm {
+    #[multipart(limit = "2MB")]
+    pub photo: Option<TempFile>,
+    pub entry: Text<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CookLogPayload {
+    pub recipe_id: i32,
+    // Today if not given
+    pub cooked_on: Option<NaiveDate>,
+    pub notes: Option<String>,
+    // 1 to 5 stars
+    pub rating: Option<i16>,
+    // Public if not given
+    pub public: Option<bool>,
+}
+
+impl CookLogPayload {
+    #[allow(clippy::result_large_err)]
+    pub fn verify(&self) -> Result<(), HttpResponse> {
+        if let Some(rating) = self.rating {
+            if !(1..=5).contains(&rating) {
+                pretty_error!(
+                    "This rating is invalid".to_string(),
+                    "Please rate the recipe from 1 to 5 stars",
+                    error
+                );
+
+                return Err(HttpResponse::BadRequest().json(error));
+            }
+        }
+
+        // A day of leeway for cooks ahead of the server's timezone
+        if let Some(cooked_on) = self.cooked_on {
+            let tomorrow = Utc::now().date_naive() + Days::new(1);
+            if cooked_on > tomorrow {
+                pretty_error!(
+                    "This date is invalid".to_string(),
+                    "The date cooked can't be in the future",
+                    error
+                );
+
+                return Err(HttpResponse::BadRequest().json(error));
+            }
+        }
+
+        if let Some(notes) = self.notes() {
+            if notes.chars().count() > MAX_NOTES_LENGTH || !is_alnum_whitespace_and_ex_chars(&notes)
+            {
+                pretty_error!(
+                    "These notes are invalid".to_string(),
+                    format!(
+                        "Please only use alphanumerical characters, up to {} of them",
+                        MAX_NOTES_LENGTH
+                    ),
+                    error
+                );
+
+                return Err(HttpResponse::BadRequest().json(error));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn cooked_on(&self) -> NaiveDate {
+        self.cooked_on.unwrap_or_else(|| Utc::now().date_naive())
+    }
+
+    pub fn notes(&self) -> Option<String> {
+        self.notes
+            .as_ref()
+            .map(|notes| notes.trim().to_string())
+            .filter(|notes| !notes.is_empty())
+    }
+}
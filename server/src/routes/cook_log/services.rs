@@ -0,0 +1,216 @@
+use actix_multipart::form::MultipartForm;
+use actix_web::{
+    get,
+    web::{self, Data, Path},
+    HttpResponse, Responder,
+};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    database::models::cook_log::{CookLog, NewCookLog},
+    extractors::auth::{Authorized, Viewer},
+    pretty_error,
+    routes::{
+        error::PrettyErrorResponse,
+        recipes::helpers::{find_viewable_recipe, GetRecipeQueryParams},
+    },
+    static_files::helpers::save_temp_file,
+    storage::{constants::COOK_PHOTO_DIR, key, Storage},
+};
+
+use super::helpers::{CookLogForm, CookLogPayload};
+
+// #[post(/cook_log)]
+pub async fn log_cook(
+    authorized: Authorized,
+    MultipartForm(form): MultipartForm<CookLogForm>,
+    pool: Data<Pool<Postgres>>,
+    storage: Data<dyn Storage>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let payload = serde_json::from_str::<CookLogPayload>(&form.entry.to_string());
+    if let Err(err) = payload {
+        pretty_error!("Invalid cook log entry", err.to_string(), error);
+
+        return HttpResponse::BadRequest().json(error);
+    }
+
+    let payload = payload.unwrap();
+    if let Err(e) = payload.verify() {
+        return e;
+    }
+
+    if let Err(e) = find_viewable_recipe(&pool, payload.recipe_id, Some(uid)).await {
+        return e;
+    }
+
+    // The photo is saved first so a failed upload doesn't leave an entry behind without it
+    let mut photo_path = None;
+    if let Some(temp_photo_file) = form.photo {
+        let Some(mime_type) = &temp_photo_file.content_type else {
+            pretty_error!("Invalid photo", "Couldn't get mime type", error);
+
+            return HttpResponse::BadRequest().json(error);
+        };
+
+        if mime_type != &"image/jpeg" && mime_type != &"image/png" {
+            pretty_error!(
+                "Invalid photo",
+                "An invalid mime type was passed, only jpeg/png",
+                error
+            );
+
+            return HttpResponse::BadRequest().json(error);
+        }
+
+        let file_name = uid.to_string() + "-" + &Uuid::new_v4().to_string();
+        match save_temp_file(&**storage, temp_photo_file, COOK_PHOTO_DIR, &file_name).await {
+            Ok(file_name) => photo_path = Some(file_name),
+            Err(e) => {
+                pretty_error!("Invalid upload", e.to_string(), error);
+
+                return HttpResponse::BadRequest().json(error);
+            }
+        }
+    }
+
+    let entry = NewCookLog {
+        recipe_id: payload.recipe_id,
+        cooked_on: payload.cooked_on(),
+        notes: payload.notes(),
+        rating: payload.rating,
+        photo_path,
+        public: payload.public.unwrap_or(true),
+    };
+
+    match CookLog::insert(&pool, uid, &entry).await {
+        Ok(entry_id) => HttpResponse::Ok().body(entry_id.to_string()),
+        Err(e) => {
+            if let Some(photo_path) = &entry.photo_path {
+                let _ = storage.delete(&key(COOK_PHOTO_DIR, photo_path)).await;
+            }
+
+            pretty_error!("Failed to log cook", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[get(/cook_log)]
+pub async fn get_cook_log(
+    authorized: Authorized,
+    pagination: web::Query<GetRecipeQueryParams>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match CookLog::get_by_user(&pool, uid, pagination.offset(), pagination.limit()).await {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(e) => {
+            pretty_error!("Failed to get cook log", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[delete(/cook_log/{entry_id})]
+pub async fn delete_cook_log_entry(
+    authorized: Authorized,
+    path: Path<i32>,
+    pool: Data<Pool<Postgres>>,
+    storage: Data<dyn Storage>,
+) -> impl Responder {
+    let entry_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let entry = match CookLog::get_owner(&pool, entry_id).await {
+        Ok(Some(entry)) => entry,
+        Ok(None) => {
+            pretty_error!(
+                "No cook log entry found".to_string(),
+                format!("Couldn't find cook log entry with the id: {}", entry_id),
+                error
+            );
+
+            return HttpResponse::NotFound().json(error);
+        }
+        Err(e) => {
+            pretty_error!("Failed to delete cook log entry", e.to_string(), error);
+
+            return HttpResponse::InternalServerError().json(error);
+        }
+    };
+
+    if entry.user_id != uid {
+        pretty_error!(
+            "Failed to delete cook log entry",
+            "Cook id and submitter id do not match",
+            error
+        );
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    if let Err(e) = CookLog::delete(&pool, entry_id).await {
+        pretty_error!("Failed to delete cook log entry", e.to_string(), error);
+
+        return HttpResponse::InternalServerError().json(error);
+    }
+
+    // The row is already gone, so a file we fail to remove is only wasted space
+    if let Some(photo_path) = &entry.photo_path {
+        let _ = storage.delete(&key(COOK_PHOTO_DIR, photo_path)).await;
+    }
+
+    HttpResponse::Ok().body("Succesfully deleted cook log entry")
+}
+
+#[get("/{recipe_id}/cooks")]
+pub async fn get_recipe_cook_photos(
+    pool: Data<Pool<Postgres>>,
+    path: Path<i32>,
+    pagination: web::Query<GetRecipeQueryParams>,
+    viewer: Viewer,
+) -> impl Responder {
+    let recipe_id = path.into_inner();
+    if let Err(e) = find_viewable_recipe(&pool, recipe_id, viewer.0).await {
+        return e;
+    }
+
+    match CookLog::get_gallery(&pool, recipe_id, pagination.offset(), pagination.limit()).await {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(e) => {
+            pretty_error!("Failed to get cook photos", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use actix_web::{web::Bytes, HttpResponse};
+use serde::Deserialize;
+
+use crate::{events::LiveEvent, pretty_error, routes::error::PrettyErrorResponse};
+
+const MAX_WATCHED_RECIPES: usize = 50;
+
+// Recipes to get recipe_updated events for, passed comma separated, e.g. ?recipes=1,5,12
+#[derive(Deserialize)]
+pub struct EventsQueryParams {
+    pub recipes: Option<String>,
+}
+
+impl EventsQueryParams {
+    #[allow(clippy::result_large_err)]
+    pub fn recipe_ids(&self) -> Result<Vec<i32>, HttpResponse> {
+        let Some(recipes) = &self.recipes else {
+            return Ok(Vec::new());
+        };
+
+        let mut recipe_ids = Vec::new();
+        for id in recipes
+            .split(',')
+            .map(|id| id.trim())
+            .filter(|id| !id.is_empty())
+        {
+            let Ok(id) = id.parse::<i32>() else {
+                pretty_error!(
+                    "Invalid recipes".to_string(),
+                    format!("{} is not a recipe id", id),
+                    error
+                );
+
+                return Err(HttpResponse::BadRequest().json(error));
+            };
+
+            recipe_ids.push(id);
+        }
+
+        if recipe_ids.len() > MAX_WATCHED_RECIPES {
+            pretty_error!(
+                "Invalid recipes".to_string(),
+                format!("You can watch up to {} recipes", MAX_WATCHED_RECIPES),
+                error
+            );
+
+            return Err(HttpResponse::BadRequest().json(error));
+        }
+
+        Ok(recipe_ids)
+    }
+}
+
+// Whether a connected user should be sent the event
+pub fn is_for(event: &LiveEvent, uid: i32, recipe_ids: &[i32]) -> bool {
+    match event {
+        LiveEvent::Notification { user_id, .. } => *user_id == uid,
+        LiveEvent::RecipeUpdated { recipe_id, .. } => recipe_ids.contains(recipe_id),
+    }
+}
+
+pub fn sse_message(event: &LiveEvent) -> Bytes {
+    let data = serde_json::to_string(event).unwrap_or_default();
+
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event.name(), data))
+}
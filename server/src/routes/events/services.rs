@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use actix_web::{
+    http::header,
+    rt::time::timeout,
+    web::{self, Bytes, Data},
+    HttpResponse, Responder,
+};
+use sqlx::{Pool, Postgres};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    database::models::recipe::Recipe, events::LiveEvents, extractors::auth::Authorized,
+    pretty_error, routes::error::PrettyErrorResponse,
+};
+
+use super::helpers::{is_for, sse_message, EventsQueryParams};
+
+// Comments are sent this often while nothing is happening, so proxies don't drop the connection
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+// #[get(/events)]
+pub async fn stream_events(
+    authorized: Authorized,
+    query: web::Query<EventsQueryParams>,
+    pool: Data<Pool<Postgres>>,
+    live_events: Data<LiveEvents>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let recipe_ids = match query.recipe_ids() {
+        Ok(recipe_ids) => recipe_ids,
+        Err(e) => return e,
+    };
+
+    // Recipes the user can't see are quietly left out
+    let recipe_ids = match Recipe::get_viewable_ids(&pool, &recipe_ids, uid).await {
+        Ok(recipe_ids) => recipe_ids,
+        Err(e) => {
+            pretty_error!("Failed to get events", e.to_string(), error);
+
+            return HttpResponse::InternalServerError().json(error);
+        }
+    };
+
+    let receiver = live_events.subscribe();
+    let stream = futures::stream::unfold(receiver, move |mut receiver| {
+        let recipe_ids = recipe_ids.clone();
+        async move {
+            loop {
+                let message = match timeout(KEEP_ALIVE, receiver.recv()).await {
+                    Ok(Ok(event)) if is_for(&event, uid, &recipe_ids) => sse_message(&event),
+                    // Events missed by a slow client are skipped
+                    Ok(Ok(..)) | Ok(Err(RecvError::Lagged(..))) => continue,
+                    Ok(Err(RecvError::Closed)) => return None,
+                    Err(..) => Bytes::from_static(b": keep-alive\n\n"),
+                };
+
+                return Some((Ok::<_, actix_web::Error>(message), receiver));
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, "text/event-stream"))
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(stream)
+}
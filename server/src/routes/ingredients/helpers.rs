@@ -0,0 +1,69 @@
+use actix_web::HttpResponse;
+use serde::Deserialize;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    database::models::user::User, pretty_error, recipe_io::shopping::Aisle,
+    routes::error::PrettyErrorResponse,
+};
+
+#[derive(Deserialize)]
+pub struct GetIngredientsQueryParams {
+    pub q: Option<String>,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct MergeIngredientsPayload {
+    // The ingredient that gets folded into the target and removed
+    pub source_id: i32,
+    pub target_id: i32,
+}
+
+#[derive(Deserialize)]
+pub struct SearchFoodsQueryParams {
+    pub q: String,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct IngredientFoodPayload {
+    // None unlinks the ingredient from its food
+    pub fdc_id: Option<i32>,
+    // What one piece weighs, for ingredients measured in pieces whose food has no portion for it
+    pub grams_per_piece: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct IngredientAislePayload {
+    // None goes back to guessing the aisle from the ingredient's profile
+    pub aisle: Option<Aisle>,
+}
+
+#[derive(Deserialize)]
+pub struct AliasPayload {
+    pub alias: String,
+}
+
+// Only admins can change the catalogue
+pub async fn verify_admin(pool: &Pool<Postgres>, uid: i32) -> Result<(), HttpResponse> {
+    match User::is_admin(pool, uid).await {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            pretty_error!(
+                "Forbidden",
+                "Only admins can change the ingredient catalogue",
+                error
+            );
+
+            Err(HttpResponse::Forbidden().json(error))
+        }
+        Err(e) => {
+            pretty_error!("Failed to verify admin", e.to_string(), error);
+
+            Err(HttpResponse::InternalServerError().json(error))
+        }
+    }
+}
@@ -0,0 +1,484 @@
+use actix_web::{
+    get,
+    web::{self, Data, Path},
+    HttpResponse, Responder,
+};
+use serde_json::json;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    database::models::{food::Food, ingredient::Ingredient},
+    extractors::auth::Authorized,
+    pretty_error,
+    recipe_io::{dietary::IngredientProfile, normalise::normalise_ingredient},
+    routes::{error::PrettyErrorResponse, recipes::helpers::GetRecipeQueryParams},
+};
+
+use super::helpers::{
+    verify_admin, AliasPayload, GetIngredientsQueryParams, IngredientAislePayload,
+    IngredientFoodPayload, MergeIngredientsPayload, SearchFoodsQueryParams,
+};
+
+#[get("/all")]
+pub async fn get_ingredients(
+    pool: Data<Pool<Postgres>>,
+    params: web::Query<GetIngredientsQueryParams>,
+) -> impl Responder {
+    let pagination = GetRecipeQueryParams {
+        offset: params.offset,
+        limit: params.limit,
+    };
+
+    let ingredients = Ingredient::get_paginated(
+        &pool,
+        params.q.as_deref(),
+        pagination.offset(),
+        pagination.limit(),
+    )
+    .await;
+
+    match ingredients {
+        Ok(ingredients) => HttpResponse::Ok().json(ingredients),
+        Err(e) => {
+            pretty_error!(
+                "Failed to get ingredients".to_string(),
+                e.to_string(),
+                error
+            );
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// Foods from the nutrient database, for picking the food of an ingredient
+#[get("/foods")]
+pub async fn search_foods(
+    pool: Data<Pool<Postgres>>,
+    params: web::Query<SearchFoodsQueryParams>,
+) -> impl Responder {
+    let pagination = GetRecipeQueryParams {
+        offset: params.offset,
+        limit: params.limit,
+    };
+
+    let query = params.q.trim();
+    if query.is_empty() {
+        pretty_error!(
+            "Invalid search",
+            "Please enter something to search for",
+            error
+        );
+
+        return HttpResponse::BadRequest().json(error);
+    }
+
+    match Food::search(&pool, query, pagination.offset(), pagination.limit()).await {
+        Ok(foods) => HttpResponse::Ok().json(foods),
+        Err(e) => {
+            pretty_error!("Failed to search foods".to_string(), e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/{ingredient_id}/food)]
+pub async fn set_ingredient_food(
+    authorized: Authorized,
+    path: Path<i32>,
+    payload: web::Json<IngredientFoodPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let ingredient_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = verify_admin(&pool, uid).await {
+        return e;
+    }
+
+    if let Some(grams) = payload.grams_per_piece {
+        if !grams.is_finite() || grams <= 0.0 {
+            pretty_error!(
+                "Invalid piece weight",
+                "The weight of a piece must be more than 0 grams",
+                error
+            );
+
+            return HttpResponse::BadRequest().json(error);
+        }
+    }
+
+    match Ingredient::get_by_id(&pool, ingredient_id).await {
+        Ok(Some(..)) => {}
+        Ok(None) => {
+            pretty_error!(
+                "No ingredient found".to_string(),
+                format!("Couldn't find ingredient with the id: {}", ingredient_id),
+                error
+            );
+
+            return HttpResponse::NotFound().json(error);
+        }
+        Err(e) => {
+            pretty_error!("Failed to set food", e.to_string(), error);
+
+            return HttpResponse::InternalServerError().json(error);
+        }
+    }
+
+    if let Some(fdc_id) = payload.fdc_id {
+        match Food::exists(&pool, fdc_id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                pretty_error!(
+                    "No food found".to_string(),
+                    format!("Couldn't find food with the fdc id: {}", fdc_id),
+                    error
+                );
+
+                return HttpResponse::NotFound().json(error);
+            }
+            Err(e) => {
+                pretty_error!("Failed to set food", e.to_string(), error);
+
+                return HttpResponse::InternalServerError().json(error);
+            }
+        }
+    }
+
+    match Ingredient::set_food(
+        &pool,
+        ingredient_id,
+        payload.fdc_id,
+        payload.grams_per_piece,
+    )
+    .await
+    {
+        Ok(..) => HttpResponse::Ok().body("Succesfully set the food of the ingredient"),
+        Err(e) => {
+            pretty_error!("Failed to set food", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// Links ingredients added since the foods were imported to their food, recipes don't do this
+// when they are saved
+// #[post(/match_foods)]
+pub async fn match_ingredient_foods(
+    authorized: Authorized,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = verify_admin(&pool, uid).await {
+        return e;
+    }
+
+    match Ingredient::match_unlinked_foods(&pool).await {
+        Ok(matched) => HttpResponse::Ok().json(json!({ "matched": matched })),
+        Err(e) => {
+            pretty_error!("Failed to match foods", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/{ingredient_id}/aisle)]
+pub async fn set_ingredient_aisle(
+    authorized: Authorized,
+    path: Path<i32>,
+    payload: web::Json<IngredientAislePayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let ingredient_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = verify_admin(&pool, uid).await {
+        return e;
+    }
+
+    match Ingredient::get_by_id(&pool, ingredient_id).await {
+        Ok(Some(..)) => {}
+        Ok(None) => {
+            pretty_error!(
+                "No ingredient found".to_string(),
+                format!("Couldn't find ingredient with the id: {}", ingredient_id),
+                error
+            );
+
+            return HttpResponse::NotFound().json(error);
+        }
+        Err(e) => {
+            pretty_error!("Failed to set aisle", e.to_string(), error);
+
+            return HttpResponse::InternalServerError().json(error);
+        }
+    }
+
+    match Ingredient::set_aisle(&pool, ingredient_id, payload.aisle).await {
+        Ok(..) => HttpResponse::Ok().body("Succesfully set the aisle of the ingredient"),
+        Err(e) => {
+            pretty_error!("Failed to set aisle", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/{ingredient_id}/profile)]
+pub async fn set_ingredient_profile(
+    authorized: Authorized,
+    path: Path<i32>,
+    payload: web::Json<IngredientProfile>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let ingredient_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = verify_admin(&pool, uid).await {
+        return e;
+    }
+
+    match Ingredient::get_by_id(&pool, ingredient_id).await {
+        Ok(Some(..)) => {}
+        Ok(None) => {
+            pretty_error!(
+                "No ingredient found".to_string(),
+                format!("Couldn't find ingredient with the id: {}", ingredient_id),
+                error
+            );
+
+            return HttpResponse::NotFound().json(error);
+        }
+        Err(e) => {
+            pretty_error!("Failed to set profile", e.to_string(), error);
+
+            return HttpResponse::InternalServerError().json(error);
+        }
+    }
+
+    let mut profile = payload.into_inner();
+    profile.allergens.sort();
+    profile.allergens.dedup();
+
+    match Ingredient::update_profile(&pool, ingredient_id, &profile).await {
+        Ok(reclassified) => HttpResponse::Ok().body(format!(
+            "Succesfully set the profile of the ingredient, {} recipe(s) were updated",
+            reclassified
+        )),
+        Err(e) => {
+            pretty_error!("Failed to set profile", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/merge)]
+pub async fn merge_ingredients(
+    authorized: Authorized,
+    payload: web::Json<MergeIngredientsPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = verify_admin(&pool, uid).await {
+        return e;
+    }
+
+    if payload.source_id == payload.target_id {
+        pretty_error!(
+            "Invalid merge",
+            "An ingredient can't be merged into itself",
+            error
+        );
+
+        return HttpResponse::BadRequest().json(error);
+    }
+
+    for id in [payload.source_id, payload.target_id] {
+        match Ingredient::get_by_id(&pool, id).await {
+            Ok(Some(..)) => {}
+            Ok(None) => {
+                pretty_error!(
+                    "No ingredient found".to_string(),
+                    format!("Couldn't find ingredient with the id: {}", id),
+                    error
+                );
+
+                return HttpResponse::NotFound().json(error);
+            }
+            Err(e) => {
+                pretty_error!("Failed to merge ingredients", e.to_string(), error);
+
+                return HttpResponse::InternalServerError().json(error);
+            }
+        }
+    }
+
+    match Ingredient::merge(&pool, payload.source_id, payload.target_id).await {
+        Ok(..) => HttpResponse::Ok().body("Succesfully merged ingredients"),
+        Err(e) => {
+            pretty_error!("Failed to merge ingredients", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/{ingredient_id}/aliases)]
+pub async fn add_ingredient_alias(
+    authorized: Authorized,
+    path: Path<i32>,
+    payload: web::Json<AliasPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let ingredient_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = verify_admin(&pool, uid).await {
+        return e;
+    }
+
+    let alias = normalise_ingredient(&payload.alias);
+    if alias.is_empty() || alias.len() > 100 {
+        pretty_error!(
+            "Invalid alias",
+            "Aliases must be between 1 and 100 characters",
+            error
+        );
+
+        return HttpResponse::BadRequest().json(error);
+    }
+
+    match Ingredient::get_by_id(&pool, ingredient_id).await {
+        Ok(Some(..)) => {}
+        Ok(None) => {
+            pretty_error!(
+                "No ingredient found".to_string(),
+                format!("Couldn't find ingredient with the id: {}", ingredient_id),
+                error
+            );
+
+            return HttpResponse::NotFound().json(error);
+        }
+        Err(e) => {
+            pretty_error!("Failed to add alias", e.to_string(), error);
+
+            return HttpResponse::InternalServerError().json(error);
+        }
+    }
+
+    match Ingredient::add_alias(&pool, ingredient_id, &alias).await {
+        Ok(true) => HttpResponse::Ok().body("Succesfully added alias"),
+        Ok(false) => {
+            pretty_error!(
+                "Alias already taken".to_string(),
+                format!(
+                    "'{}' is already an ingredient or alias, merge the ingredients instead",
+                    alias
+                ),
+                error
+            );
+
+            HttpResponse::Conflict().json(error)
+        }
+        Err(e) => {
+            pretty_error!("Failed to add alias", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[delete(/aliases/{alias})]
+pub async fn remove_ingredient_alias(
+    authorized: Authorized,
+    path: Path<String>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let alias = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = verify_admin(&pool, uid).await {
+        return e;
+    }
+
+    match Ingredient::remove_alias(&pool, &alias).await {
+        Ok(true) => HttpResponse::Ok().body("Succesfully removed alias"),
+        Ok(false) => {
+            pretty_error!(
+                "No alias found".to_string(),
+                format!("Couldn't find the alias: {}", alias),
+                error
+            );
+
+            HttpResponse::NotFound().json(error)
+        }
+        Err(e) => {
+            pretty_error!("Failed to remove alias", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use actix_web::HttpResponse;
+use chrono::{Datelike, Days, NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::{
+    database::models::meal_plan::{MealPlanEntry, MealSlot},
+    pretty_error,
+    routes::error::PrettyErrorResponse,
+};
+
+const MAX_SERVINGS: i16 = 100;
+// Long enough for a month view padded out to whole weeks
+const MAX_RANGE_DAYS: u64 = 42;
+// How long each meal lasts in calendar apps
+const MEAL_MINUTES: i64 = 60;
+
+#[derive(Deserialize)]
+pub struct MealPlanEntryPayload {
+    pub recipe_id: i32,
+    pub date: NaiveDate,
+    pub slot: MealSlot,
+    pub servings: Option<i16>,
+}
+
+impl MealPlanEntryPayload {
+    #[allow(clippy::result_large_err)]
+    pub fn verify(&self) -> Result<(), HttpResponse> {
+        verify_servings(self.servings)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct EditMealPlanEntryPayload {
+    pub date: NaiveDate,
+    pub slot: MealSlot,
+    // Leave out to go back to the recipe's own servings
+    pub servings: Option<i16>,
+}
+
+impl EditMealPlanEntryPayload {
+    #[allow(clippy::result_large_err)]
+    pub fn verify(&self) -> Result<(), HttpResponse> {
+        verify_servings(self.servings)
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn verify_servings(servings: Option<i16>) -> Result<(), HttpResponse> {
+    if let Some(servings) = servings {
+        if !(1..=MAX_SERVINGS).contains(&servings) {
+            pretty_error!(
+                "These servings are invalid".to_string(),
+                format!("Please plan from 1 to {} servings", MAX_SERVINGS),
+                error
+            );
+
+            return Err(HttpResponse::BadRequest().json(error));
+        }
+    }
+
+    Ok(())
+}
+
+// Both days are included, the current week (monday to sunday) if neither is given
+#[derive(Deserialize)]
+pub struct MealPlanQueryParams {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl MealPlanQueryParams {
+    #[allow(clippy::result_large_err)]
+    pub fn range(&self) -> Result<(NaiveDate, NaiveDate), HttpResponse> {
+        let today = Utc::now().date_naive();
+
+        // Days near the ends of the calendar can't be stepped past
+        let from = match (self.from, self.to) {
+            (Some(from), _) => Some(from),
+            (None, Some(to)) => to.checked_sub_days(Days::new(6)),
+            (None, None) => {
+                today.checked_sub_days(Days::new(today.weekday().num_days_from_monday() as u64))
+            }
+        };
+        let to = self
+            .to
+            .or(from.and_then(|from| from.checked_add_days(Days::new(6))));
+
+        let (Some(from), Some(to)) = (from, to) else {
+            return Err(invalid_range());
+        };
+
+        if to < from
+            || from
+                .checked_add_days(Days::new(MAX_RANGE_DAYS - 1))
+                .is_some_and(|last| to > last)
+        {
+            return Err(invalid_range());
+        }
+
+        Ok((from, to))
+    }
+}
+
+fn invalid_range() -> HttpResponse {
+    pretty_error!(
+        "This date range is invalid".to_string(),
+        format!(
+            "The range must end after it starts and span at most {} days",
+            MAX_RANGE_DAYS
+        ),
+        error
+    );
+
+    HttpResponse::BadRequest().json(error)
+}
+
+// Builds an iCalendar file with an event for every entry. Times are floating, so meals show
+// up at the same time of day whatever timezone the calendar app is in
+pub fn to_ics(entries: &[MealPlanEntry]) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//cookbook.io//Meal Plan//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:Meal Plan".to_string(),
+    ];
+
+    for entry in entries {
+        let start = entry.planned_on.and_time(entry.slot.start_time());
+        let end = start + chrono::Duration::minutes(MEAL_MINUTES);
+        let title = entry
+            .recipe_title
+            .clone()
+            .unwrap_or("Hidden recipe".to_string());
+
+        let mut description = format!("{} for {}", capitalise(entry.slot.name()), title);
+        if let Some(servings) = entry.servings.map(|s| s as i32).or(entry.recipe_servings) {
+            description += &format!(", {} servings", servings);
+        }
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:meal-plan-{}@cookbook.io", entry.id));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")));
+        lines.push(format!("DTEND:{}", end.format("%Y%m%dT%H%M%S")));
+        lines.push(format!("SUMMARY:{}", escape_ics_text(&title)));
+        lines.push(format!("DESCRIPTION:{}", escape_ics_text(&description)));
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    lines
+        .iter()
+        .map(|line| fold_ics_line(line))
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+fn capitalise(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn escape_ics_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\r', '\n'], "\\n")
+}
+
+// Lines can be at most 75 bytes, longer ones carry on in lines starting with a space
+fn fold_ics_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded += "\r\n ";
+            length = 1;
+        }
+
+        folded.push(c);
+        length += c.len_utf8();
+    }
+
+    folded + "\r\n"
+}
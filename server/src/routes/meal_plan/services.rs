@@ -0,0 +1,285 @@
+use actix_web::{
+    get,
+    http::header,
+    web::{self, Data, Path},
+    HttpResponse, Responder,
+};
+use chrono::{Days, Utc};
+use serde_json::json;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    database::models::meal_plan::{MealPlan, NewMealPlanEntry},
+    extractors::auth::Authorized,
+    pretty_error,
+    routes::{error::PrettyErrorResponse, recipes::helpers::find_viewable_recipe},
+};
+
+use super::helpers::{to_ics, EditMealPlanEntryPayload, MealPlanEntryPayload, MealPlanQueryParams};
+
+// How far back and ahead the calendar feed goes
+const FEED_DAYS_BEHIND: u64 = 31;
+const FEED_DAYS_AHEAD: u64 = 366;
+
+// #[get(/meal_plan)]
+pub async fn get_meal_plan(
+    authorized: Authorized,
+    query: web::Query<MealPlanQueryParams>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let (from, to) = match query.range() {
+        Ok(range) => range,
+        Err(e) => return e,
+    };
+
+    match MealPlan::get_range(&pool, uid, from, to).await {
+        Ok(entries) => HttpResponse::Ok().json(json!({
+            "from": from,
+            "to": to,
+            "entries": entries,
+        })),
+        Err(e) => {
+            pretty_error!("Failed to get meal plan", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/meal_plan)]
+pub async fn plan_meal(
+    authorized: Authorized,
+    payload: web::Json<MealPlanEntryPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = payload.verify() {
+        return e;
+    }
+
+    if let Err(e) = find_viewable_recipe(&pool, payload.recipe_id, Some(uid)).await {
+        return e;
+    }
+
+    let entry = NewMealPlanEntry {
+        recipe_id: payload.recipe_id,
+        planned_on: payload.date,
+        slot: payload.slot,
+        servings: payload.servings,
+    };
+
+    match MealPlan::insert(&pool, uid, &entry).await {
+        Ok(entry_id) => HttpResponse::Ok().body(entry_id.to_string()),
+        Err(e) => {
+            pretty_error!("Failed to plan meal", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/meal_plan/{entry_id})]
+pub async fn edit_meal_plan_entry(
+    authorized: Authorized,
+    path: Path<i32>,
+    payload: web::Json<EditMealPlanEntryPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let entry_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = payload.verify() {
+        return e;
+    }
+
+    match MealPlan::update(
+        &pool,
+        uid,
+        entry_id,
+        payload.date,
+        payload.slot,
+        payload.servings,
+    )
+    .await
+    {
+        Ok(true) => HttpResponse::Ok().body("Succesfully updated meal plan entry"),
+        Ok(false) => {
+            pretty_error!(
+                "No meal plan entry found".to_string(),
+                format!("Couldn't find meal plan entry with the id: {}", entry_id),
+                error
+            );
+
+            HttpResponse::NotFound().json(error)
+        }
+        Err(e) => {
+            pretty_error!("Failed to update meal plan entry", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[delete(/meal_plan/{entry_id})]
+pub async fn delete_meal_plan_entry(
+    authorized: Authorized,
+    path: Path<i32>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let entry_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match MealPlan::delete(&pool, uid, entry_id).await {
+        Ok(true) => HttpResponse::Ok().body("Succesfully deleted meal plan entry"),
+        Ok(false) => {
+            pretty_error!(
+                "No meal plan entry found".to_string(),
+                format!("Couldn't find meal plan entry with the id: {}", entry_id),
+                error
+            );
+
+            HttpResponse::NotFound().json(error)
+        }
+        Err(e) => {
+            pretty_error!("Failed to delete meal plan entry", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/meal_plan/feed)]
+// Calendar apps can't send our auth header, so the feed is found by a secret token instead.
+// Calling this again replaces the token
+pub async fn create_meal_plan_feed(
+    authorized: Authorized,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match MealPlan::reset_feed_token(&pool, uid).await {
+        Ok(token) => HttpResponse::Ok().json(json!({
+            "token": token,
+            "path": format!("/v1/meal_plan/{}/plan.ics", token),
+        })),
+        Err(e) => {
+            pretty_error!("Failed to create calendar feed", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[delete(/meal_plan/feed)]
+pub async fn delete_meal_plan_feed(
+    authorized: Authorized,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match MealPlan::delete_feed_token(&pool, uid).await {
+        Ok(true) => HttpResponse::Ok().body("Succesfully deleted calendar feed"),
+        Ok(false) => {
+            pretty_error!(
+                "No calendar feed found".to_string(),
+                "You don't have a calendar feed",
+                error
+            );
+
+            HttpResponse::NotFound().json(error)
+        }
+        Err(e) => {
+            pretty_error!("Failed to delete calendar feed", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+#[get("/{token}/plan.ics")]
+pub async fn get_meal_plan_feed(pool: Data<Pool<Postgres>>, path: Path<String>) -> impl Responder {
+    let token = path.into_inner();
+    let uid = match MealPlan::get_feed_user(&pool, &token).await {
+        Ok(Some(uid)) => uid,
+        Ok(None) => {
+            pretty_error!(
+                "No calendar feed found".to_string(),
+                "This calendar feed doesn't exist or has been replaced",
+                error
+            );
+
+            return HttpResponse::NotFound().json(error);
+        }
+        Err(e) => {
+            pretty_error!("Failed to get calendar feed", e.to_string(), error);
+
+            return HttpResponse::InternalServerError().json(error);
+        }
+    };
+
+    let today = Utc::now().date_naive();
+    let from = today - Days::new(FEED_DAYS_BEHIND);
+    let to = today + Days::new(FEED_DAYS_AHEAD);
+
+    match MealPlan::get_range(&pool, uid, from, to).await {
+        Ok(entries) => HttpResponse::Ok()
+            .insert_header((header::CONTENT_TYPE, "text/calendar; charset=utf-8"))
+            .body(to_ics(&entries)),
+        Err(e) => {
+            pretty_error!("Failed to get calendar feed", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
@@ -2,3 +2,12 @@ pub mod users;
 pub mod error;
 pub mod recipes;
 pub mod account;
+pub mod ingredients;
+pub mod collections;
+pub mod reviews;
+pub mod comments;
+pub mod cook_log;
+pub mod notifications;
+pub mod events;
+pub mod meal_plan;
+pub mod shopping_lists;
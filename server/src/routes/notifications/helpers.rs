@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{
+    database::models::notification::NotificationKind,
+    routes::recipes::helpers::GetRecipeQueryParams,
+};
+
+#[derive(Deserialize)]
+pub struct GetNotificationsQueryParams {
+    // Only unread notifications if true
+    pub unread: Option<bool>,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+impl GetNotificationsQueryParams {
+    pub fn pagination(&self) -> GetRecipeQueryParams {
+        GetRecipeQueryParams {
+            offset: self.offset,
+            limit: self.limit,
+        }
+    }
+}
+
+// Kinds left out keep their current setting, e.g. { "favourite": false, "follow": true }
+#[derive(Deserialize)]
+pub struct NotificationPreferencesPayload(pub HashMap<NotificationKind, bool>);
+
+impl NotificationPreferencesPayload {
+    // Applies the changes to the kinds the user had already turned off
+    pub fn opt_outs(&self, current: &[NotificationKind]) -> Vec<NotificationKind> {
+        NotificationKind::ALL
+            .into_iter()
+            .filter(|kind| match self.0.get(kind) {
+                Some(enabled) => !enabled,
+                None => current.contains(kind),
+            })
+            .collect()
+    }
+}
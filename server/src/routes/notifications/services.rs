@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+
+use actix_web::{
+    web::{self, Data, Path},
+    HttpResponse, Responder,
+};
+use serde_json::json;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    database::models::notification::{Notification, NotificationKind},
+    extractors::auth::Authorized,
+    pretty_error,
+    routes::error::PrettyErrorResponse,
+};
+
+use super::helpers::{GetNotificationsQueryParams, NotificationPreferencesPayload};
+
+// #[get(/notifications)]
+pub async fn get_notifications(
+    authorized: Authorized,
+    query: web::Query<GetNotificationsQueryParams>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let pagination = query.pagination();
+    match Notification::get_for_user(
+        &pool,
+        uid,
+        query.unread.unwrap_or(false),
+        pagination.offset(),
+        pagination.limit(),
+    )
+    .await
+    {
+        Ok(notifications) => HttpResponse::Ok().json(notifications),
+        Err(e) => {
+            pretty_error!("Failed to get notifications", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[get(/notifications/unread)]
+pub async fn get_unread_count(
+    authorized: Authorized,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match Notification::count_unread(&pool, uid).await {
+        Ok(unread) => HttpResponse::Ok().json(json!({ "unread": unread })),
+        Err(e) => {
+            pretty_error!("Failed to count notifications", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/notifications/{notification_id}/read)]
+pub async fn read_notification(
+    authorized: Authorized,
+    path: Path<i32>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let notification_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match Notification::mark_read(&pool, uid, notification_id).await {
+        Ok(true) => HttpResponse::Ok().body("Succesfully marked notification as read"),
+        Ok(false) => {
+            pretty_error!(
+                "No notification found".to_string(),
+                format!(
+                    "Couldn't find notification with the id: {}",
+                    notification_id
+                ),
+                error
+            );
+
+            HttpResponse::NotFound().json(error)
+        }
+        Err(e) => {
+            pretty_error!("Failed to mark notification as read", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/notifications/read)]
+pub async fn read_all_notifications(
+    authorized: Authorized,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match Notification::mark_all_read(&pool, uid).await {
+        Ok(marked) => HttpResponse::Ok().json(json!({ "marked": marked })),
+        Err(e) => {
+            pretty_error!("Failed to mark notifications as read", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[get(/notifications/preferences)]
+pub async fn get_notification_preferences(
+    authorized: Authorized,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match Notification::get_opt_outs(&pool, uid).await {
+        Ok(opt_outs) => {
+            let preferences: BTreeMap<&str, bool> = NotificationKind::ALL
+                .into_iter()
+                .map(|kind| (kind.name(), !opt_outs.contains(&kind)))
+                .collect();
+
+            HttpResponse::Ok().json(preferences)
+        }
+        Err(e) => {
+            pretty_error!(
+                "Failed to get notification preferences",
+                e.to_string(),
+                error
+            );
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/notifications/preferences)]
+pub async fn update_notification_preferences(
+    authorized: Authorized,
+    payload: web::Json<NotificationPreferencesPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let current = match Notification::get_opt_outs(&pool, uid).await {
+        Ok(current) => current,
+        Err(e) => {
+            pretty_error!(
+                "Failed to update notification preferences",
+                e.to_string(),
+                error
+            );
+
+            return HttpResponse::InternalServerError().json(error);
+        }
+    };
+
+    match Notification::set_opt_outs(&pool, uid, &payload.opt_outs(&current)).await {
+        Ok(..) => HttpResponse::Ok().body("Succesfully updated notification preferences"),
+        Err(e) => {
+            pretty_error!(
+                "Failed to update notification preferences",
+                e.to_string(),
+                error
+            );
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
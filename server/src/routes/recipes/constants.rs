@@ -1 +0,0 @@
-pub const RECIPE_DIR: &str = "./recipes/";
@@ -1,18 +1,28 @@
-use std::{
-    fs::File,
-    io::{BufWriter, Read, Write},
-};
-
 use actix_multipart::form::{tempfile::TempFile, text::Text, MultipartForm};
+use actix_web::{
+    http::header::{self, EntityTag, IfMatch},
+    HttpMessage, HttpRequest, HttpResponse,
+};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
 
 use crate::{
-    database::models::recipe::{Poster, RecipeWithPoster},
-    recipe_io::RecipeFileJson,
+    database::models::{
+        recipe::{Poster, Recipe, RecipeParent, RecipeWithPoster, Visibility},
+        review::RatingSummary,
+    },
+    pretty_error,
+    recipe_io::{
+        conversion::UnitSystem,
+        dietary::{Allergen, Diet, Dietary, DietaryFilter},
+        nutrition::Nutrition,
+        RecipeFileJson,
+    },
+    routes::error::PrettyErrorResponse,
 };
 
-use super::constants::RECIPE_DIR;
+const MAX_PAGE_SIZE: u32 = 10;
 
 #[derive(Deserialize, Clone, Copy)]
 pub struct GetRecipeQueryParams {
@@ -20,11 +30,202 @@ pub struct GetRecipeQueryParams {
     pub limit: Option<u32>,
 }
 
+impl GetRecipeQueryParams {
+    pub fn offset(&self) -> u32 {
+        self.offset.unwrap_or(0)
+    }
+
+    // Pages are capped at MAX_PAGE_SIZE recipes
+    pub fn limit(&self) -> u32 {
+        self.limit.unwrap_or(MAX_PAGE_SIZE).min(MAX_PAGE_SIZE)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SearchRecipeQueryParams {
+    pub q: String,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+impl SearchRecipeQueryParams {
+    pub fn pagination(&self) -> GetRecipeQueryParams {
+        GetRecipeQueryParams {
+            offset: self.offset,
+            limit: self.limit,
+        }
+    }
+}
+
+// Ingredient lists are passed comma separated, e.g. ?have=chicken,rice,garlic
+#[derive(Deserialize)]
+pub struct IngredientSearchQueryParams {
+    pub have: Option<String>,
+    // Recipes must use every one of these
+    pub include: Option<String>,
+    // Recipes must not use any of these
+    pub exclude: Option<String>,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+impl IngredientSearchQueryParams {
+    // Anything that must be included is also something the user has
+    pub fn have(&self) -> Vec<String> {
+        let mut have = split_ingredient_list(&self.have);
+        have.extend(self.include());
+        have
+    }
+
+    pub fn include(&self) -> Vec<String> {
+        split_ingredient_list(&self.include)
+    }
+
+    pub fn exclude(&self) -> Vec<String> {
+        split_ingredient_list(&self.exclude)
+    }
+
+    pub fn pagination(&self) -> GetRecipeQueryParams {
+        GetRecipeQueryParams {
+            offset: self.offset,
+            limit: self.limit,
+        }
+    }
+}
+
+// Narrows any recipe listing, both lists are comma separated, e.g.
+// ?diets=vegan,gluten_free&exclude_allergens=peanut,tree_nut
+#[derive(Deserialize)]
+pub struct DietaryFilterQueryParams {
+    pub diets: Option<String>,
+    pub exclude_allergens: Option<String>,
+}
+
+impl DietaryFilterQueryParams {
+    #[allow(clippy::result_large_err)]
+    pub fn filter(&self) -> Result<DietaryFilter, HttpResponse> {
+        let mut filter = DietaryFilter::default();
+
+        for name in split_ingredient_list(&self.diets) {
+            match Diet::from_name(&name) {
+                Some(diet) => filter.diets.push(diet),
+                None => {
+                    pretty_error!(
+                        "Invalid diet".to_string(),
+                        format!("'{}' is not a diet we know of", name),
+                        error
+                    );
+
+                    return Err(HttpResponse::BadRequest().json(error));
+                }
+            }
+        }
+
+        for name in split_ingredient_list(&self.exclude_allergens) {
+            match Allergen::from_name(&name) {
+                Some(allergen) => filter.exclude_allergens.push(allergen),
+                None => {
+                    pretty_error!(
+                        "Invalid allergen".to_string(),
+                        format!("'{}' is not an allergen we know of", name),
+                        error
+                    );
+
+                    return Err(HttpResponse::BadRequest().json(error));
+                }
+            }
+        }
+
+        Ok(filter)
+    }
+}
+
+fn split_ingredient_list(list: &Option<String>) -> Vec<String> {
+    let Some(list) = list else {
+        return Vec::new();
+    };
+
+    list.split(',')
+        .map(|ingredient| ingredient.trim().to_lowercase())
+        .filter(|ingredient| !ingredient.is_empty())
+        .collect()
+}
+
+#[derive(Deserialize)]
+pub struct RevisionDiffQueryParams {
+    // The revision to compare against, the one before by default
+    pub against: Option<i32>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct RecipeViewQueryParams {
+    // Normalises every measurement to "metric" or "us" units
+    pub units: Option<UnitSystem>,
+    // Resizes the recipe, either to a number of servings or by a factor
+    pub servings: Option<u32>,
+    pub scale: Option<f64>,
+}
+
+impl RecipeViewQueryParams {
+    // The factor to scale the recipe by, none if it should be left as is
+    #[allow(clippy::result_large_err)]
+    pub fn scale_factor(&self, recipe: &RecipeFileJson) -> Result<Option<f64>, HttpResponse> {
+        match (self.servings, self.scale) {
+            (Some(_), Some(_)) => {
+                pretty_error!(
+                    "Invalid scale",
+                    "Only one of servings or scale can be passed",
+                    error
+                );
+
+                Err(HttpResponse::BadRequest().json(error))
+            }
+            (Some(0), None) => {
+                pretty_error!("Invalid scale", "Servings must be at least 1", error);
+
+                Err(HttpResponse::BadRequest().json(error))
+            }
+            (Some(servings), None) => match recipe.scale_for_servings(servings) {
+                Some(factor) => Ok(Some(factor)),
+                None => {
+                    pretty_error!(
+                        "Invalid scale",
+                        "This recipe doesn't say how many servings it makes, use scale instead",
+                        error
+                    );
+
+                    Err(HttpResponse::BadRequest().json(error))
+                }
+            },
+            (None, Some(scale)) => {
+                if !scale.is_finite() || scale <= 0.0 {
+                    pretty_error!("Invalid scale", "Scale must be above 0", error);
+
+                    return Err(HttpResponse::BadRequest().json(error));
+                }
+
+                Ok(Some(scale))
+            }
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+// Publishing is setting the visibility to public, or giving a time to publish at along with
+// the visibility the recipe has until then
+#[derive(Deserialize)]
+pub struct VisibilityPayload {
+    pub visibility: Visibility,
+    pub publish_at: Option<chrono::DateTime<Utc>>,
+}
+
 #[derive(Debug, MultipartForm)]
 pub struct CreateRecipeForm {
     #[multipart(limit = "2MB")]
     pub thumbnail: Option<TempFile>,
     pub recipe: Text<String>,
+    // Public if not given
+    pub visibility: Option<Text<String>>,
 }
 
 #[derive(Debug, MultipartForm)]
@@ -32,6 +233,8 @@ pub struct EditRecipeForm {
     pub thumbnail: Option<TempFile>,
     pub recipe: Text<String>,
     pub recipe_id: Text<i32>,
+    // The revision the edit was made against, for clients that can't send If-Match
+    pub revision: Option<Text<i32>>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -41,23 +244,98 @@ pub struct FullRecipePayload {
     pub id: i32,
     pub date_created: chrono::DateTime<Utc>,
     pub thumbnail: Option<String>,
+    pub dietary: Dietary,
+    pub revision: i32,
+    pub favourites: i64,
+    pub favourited: bool,
+    pub rating: RatingSummary,
+    pub visibility: Visibility,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publish_at: Option<chrono::DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nutrition: Option<Nutrition>,
+    // Set if the recipe was forked from another one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adapted_from: Option<RecipeParent>,
+    pub forks: i64,
 }
 
-pub fn create_recipe_file(file_path: String, recipe: &RecipeFileJson) -> anyhow::Result<()> {
-    let file = File::create(file_path.clone())?;
-    let mut writer = BufWriter::new(file);
-    serde_json::to_writer(&mut writer, recipe)?;
-    writer.flush()?;
+// Recipes are tagged with their revision number, e.g. ETag: "4"
+pub fn revision_etag(revision: i32) -> header::ETag {
+    header::ETag(EntityTag::new_strong(revision.to_string()))
+}
+
+// The revision an edit was made against, from the If-Match header or else the revision field
+// of the form. None means any revision will do, which is what If-Match: * asks for
+#[allow(clippy::result_large_err)]
+pub fn expected_revision(
+    req: &HttpRequest,
+    form_revision: Option<i32>,
+) -> Result<Option<i32>, HttpResponse> {
+    if !req.headers().contains_key(header::IF_MATCH) {
+        return match form_revision {
+            Some(revision) => Ok(Some(revision)),
+            None => {
+                pretty_error!(
+                    "Missing revision",
+                    "Edits must send the revision they were made against, either as If-Match or the revision field",
+                    error
+                );
+
+                Err(HttpResponse::PreconditionRequired().json(error))
+            }
+        };
+    }
 
-    Ok(())
+    let tags = match req.get_header::<IfMatch>() {
+        Some(IfMatch::Any) => return Ok(None),
+        Some(IfMatch::Items(tags)) => tags,
+        None => Vec::new(),
+    };
+
+    match tags.as_slice() {
+        [tag] if !tag.weak => match tag.tag().parse::<i32>() {
+            Ok(revision) => Ok(Some(revision)),
+            // Not a tag we ever handed out, so it can't be current
+            Err(_) => Ok(Some(-1)),
+        },
+        _ => {
+            pretty_error!(
+                "Invalid If-Match",
+                "If-Match must be a single strong entity tag, e.g. \"4\"",
+                error
+            );
+
+            Err(HttpResponse::BadRequest().json(error))
+        }
+    }
 }
 
-pub fn get_recipe_file(recipe: &RecipeWithPoster) -> anyhow::Result<RecipeFileJson> {
-    let file_path = RECIPE_DIR.to_string() + recipe.recipe_file_path.as_str();
-    let mut file = File::open(file_path)?;
-    let mut data = String::new();
-    file.read_to_string(&mut data).unwrap();
-    let recipe_json = serde_json::from_str::<RecipeFileJson>(&data)?;
+// Drafts and private recipes are reported as missing to anyone but their poster
+pub async fn find_viewable_recipe(
+    pool: &Pool<Postgres>,
+    recipe_id: i32,
+    viewer: Option<i32>,
+) -> Result<RecipeWithPoster, HttpResponse> {
+    match Recipe::get_by_id(pool, recipe_id, viewer).await {
+        Ok(recipe) if recipe.can_view(viewer) => Ok(recipe),
+        Ok(_) => {
+            pretty_error!(
+                format!("Failed to get recipe with id: {}", recipe_id),
+                "Couldn't find a recipe with that id".to_string(),
+                error
+            );
+
+            Err(HttpResponse::NotFound().json(error))
+        }
+        Err(e) => {
+            pretty_error!(
+                format!("Failed to get recipe with id: {}", recipe_id),
+                e.to_string(),
+                error
+            );
 
-    Ok(recipe_json)
+            Err(HttpResponse::NotFound().json(error))
+        }
+    }
 }
@@ -1,3 +1,2 @@
 pub mod services;
 pub mod helpers;
-pub mod constants;
@@ -1,54 +1,54 @@
-use std::{
-    fs::{self, File},
-    io::{BufWriter, Write},
-    path::Path,
-};
-
 use crate::{
-    database::models::{recipe::Recipe, recipe_thumbnails::RecipeThumbnail}, extractors::auth::Authorized, pretty_error, recipe_io::RecipeFileJson, routes::{
-        error::PrettyErrorResponse,
-        recipes::helpers::{get_recipe_file, FullRecipePayload},
-    }, static_files::helpers::rename_temp_file
+    database::models::{
+        favourite::Favourite,
+        food::Food,
+        notification::{Notification, NotificationKind},
+        recipe::{IngredientSearch, Recipe, Visibility},
+        recipe_revision::RecipeRevision,
+        recipe_thumbnails::RecipeThumbnail,
+    },
+    extractors::auth::{Authorized, Viewer},
+    pretty_error,
+    recipe_io::RecipeFileJson,
+    routes::{error::PrettyErrorResponse, recipes::helpers::FullRecipePayload},
+    static_files::helpers::{copy_file, save_temp_file},
+    storage::{constants::THUMBNAIL_DIR, key, Storage},
 };
 use actix_multipart::form::MultipartForm;
 use actix_web::{
     get,
+    http::header,
     web::{self, Data},
-    HttpResponse, Responder,
+    HttpRequest, HttpResponse, Responder,
 };
 use serde_json::json;
 use sqlx::{Pool, Postgres};
 use uuid::Uuid;
 
-use super::{
-    constants::RECIPE_DIR,
-    helpers::{create_recipe_file, CreateRecipeForm, EditRecipeForm, GetRecipeQueryParams},
+use super::helpers::{
+    expected_revision, find_viewable_recipe, revision_etag, CreateRecipeForm,
+    DietaryFilterQueryParams, EditRecipeForm, GetRecipeQueryParams, IngredientSearchQueryParams,
+    RecipeViewQueryParams, RevisionDiffQueryParams, SearchRecipeQueryParams, VisibilityPayload,
 };
 
 #[get("/all")]
 pub async fn get_recipes(
     pool: Data<Pool<Postgres>>,
-    mut pagination: web::Query<GetRecipeQueryParams>,
+    pagination: web::Query<GetRecipeQueryParams>,
+    dietary: web::Query<DietaryFilterQueryParams>,
+    viewer: Viewer,
 ) -> impl Responder {
-    pagination.limit = match pagination.limit {
-        Some(limit) => {
-            if limit > 10 {
-                Some(10)
-            } else {
-                Some(limit)
-            }
-        }
-        None => Some(10),
+    let filter = match dietary.filter() {
+        Ok(filter) => filter,
+        Err(e) => return e,
     };
 
-    if pagination.offset.is_none() {
-        pagination.offset = Some(0);
-    }
-
     let recipes = Recipe::get_paginated_recipes_with_poster(
         &pool,
-        pagination.offset.unwrap(),
-        pagination.limit.unwrap(),
+        &filter,
+        pagination.offset(),
+        pagination.limit(),
+        viewer.0,
     )
     .await;
 
@@ -61,22 +61,147 @@ pub async fn get_recipes(
 
     let mut json_values: Vec<serde_json::Value> = Vec::new();
     for recipe in recipes.iter() {
-        // Read recipe json
-        let recipe_json = get_recipe_file(recipe);
-        if let Err(e) = recipe_json {
-            pretty_error!("Recipe file is invalid".to_string(), e.to_string(), error);
-
-            return HttpResponse::InternalServerError().json(error);
-        }
-        let recipe_json = recipe_json.unwrap();
-
         // Push value to vec
         let value = json!({
             "poster": recipe.poster,
             "id": recipe.id,
-            "title": recipe_json.title,
-            "description": recipe_json.description,
+            "title": recipe.recipe.title,
+            "description": recipe.recipe.description,
             "thumbnail": recipe.thumbnail,
+            "dietary": recipe.dietary,
+            "favourites": recipe.favourites,
+            "favourited": recipe.favourited,
+            "rating": recipe.rating,
+            "visibility": recipe.visibility,
+        });
+
+        json_values.push(value);
+    }
+
+    HttpResponse::Ok().json(json_values)
+}
+
+#[get("/search")]
+pub async fn search_recipes(
+    pool: Data<Pool<Postgres>>,
+    params: web::Query<SearchRecipeQueryParams>,
+    dietary: web::Query<DietaryFilterQueryParams>,
+    viewer: Viewer,
+) -> impl Responder {
+    if params.q.trim().is_empty() {
+        pretty_error!(
+            "Invalid search".to_string(),
+            "Please provide something to search for",
+            error
+        );
+
+        return HttpResponse::BadRequest().json(error);
+    }
+
+    let filter = match dietary.filter() {
+        Ok(filter) => filter,
+        Err(e) => return e,
+    };
+
+    let pagination = params.pagination();
+    let results = Recipe::search(
+        &pool,
+        &params.q,
+        &filter,
+        pagination.offset(),
+        pagination.limit(),
+        viewer.0,
+    )
+    .await;
+    if let Err(e) = results {
+        pretty_error!("Failed to search recipes".to_string(), e.to_string(), error);
+
+        return HttpResponse::InternalServerError().json(error);
+    };
+
+    let results = results.unwrap();
+    let mut json_values: Vec<serde_json::Value> = Vec::new();
+    for result in results.iter() {
+        let value = json!({
+            "poster": result.recipe.poster,
+            "id": result.recipe.id,
+            "title": result.recipe.recipe.title,
+            "description": result.recipe.recipe.description,
+            "thumbnail": result.recipe.thumbnail,
+            "dietary": result.recipe.dietary,
+            "favourites": result.recipe.favourites,
+            "favourited": result.recipe.favourited,
+            "rating": result.recipe.rating,
+            "rank": result.rank,
+            "highlights": {
+                "title": result.title_highlight,
+                "snippet": result.snippet,
+            },
+        });
+
+        json_values.push(value);
+    }
+
+    HttpResponse::Ok().json(json_values)
+}
+
+#[get("/by_ingredients")]
+pub async fn search_recipes_by_ingredients(
+    pool: Data<Pool<Postgres>>,
+    params: web::Query<IngredientSearchQueryParams>,
+    dietary: web::Query<DietaryFilterQueryParams>,
+    viewer: Viewer,
+) -> impl Responder {
+    let have = params.have();
+    if have.is_empty() {
+        pretty_error!(
+            "Invalid search".to_string(),
+            "Please provide the ingredients you have",
+            error
+        );
+
+        return HttpResponse::BadRequest().json(error);
+    }
+
+    let filter = match dietary.filter() {
+        Ok(filter) => filter,
+        Err(e) => return e,
+    };
+
+    let pagination = params.pagination();
+    let search = IngredientSearch {
+        have,
+        include: params.include(),
+        exclude: params.exclude(),
+        filter,
+        offset: pagination.offset(),
+        limit: pagination.limit(),
+        viewer: viewer.0,
+    };
+    let matches = Recipe::get_by_ingredients(&pool, &search).await;
+
+    if let Err(e) = matches {
+        pretty_error!("Failed to search recipes".to_string(), e.to_string(), error);
+
+        return HttpResponse::InternalServerError().json(error);
+    };
+
+    let matches = matches.unwrap();
+    let mut json_values: Vec<serde_json::Value> = Vec::new();
+    for m in matches.iter() {
+        let value = json!({
+            "poster": m.recipe.poster,
+            "id": m.recipe.id,
+            "title": m.recipe.recipe.title,
+            "description": m.recipe.recipe.description,
+            "thumbnail": m.recipe.thumbnail,
+            "dietary": m.recipe.dietary,
+            "favourites": m.recipe.favourites,
+            "favourited": m.recipe.favourited,
+            "rating": m.recipe.rating,
+            "covered": m.covered,
+            "total": m.total,
+            "missing": m.missing,
         });
 
         json_values.push(value);
@@ -89,10 +214,16 @@ pub async fn get_recipes(
 pub async fn get_recipe_by_poster(
     pool: Data<Pool<Postgres>>,
     path: actix_web::web::Path<i32>,
+    dietary: web::Query<DietaryFilterQueryParams>,
+    viewer: Viewer,
 ) -> impl Responder {
     let uid = path.into_inner();
+    let filter = match dietary.filter() {
+        Ok(filter) => filter,
+        Err(e) => return e,
+    };
 
-    let recipes = Recipe::get_by_poster(&pool, uid).await;
+    let recipes = Recipe::get_by_poster(&pool, uid, &filter, viewer.0).await;
     if let Err(e) = recipes {
         pretty_error!(
             format!("Failed to get recipes by user with id: {}", uid),
@@ -107,20 +238,17 @@ pub async fn get_recipe_by_poster(
     let mut json_values: Vec<serde_json::Value> = Vec::new();
 
     for recipe in recipes.iter() {
-        let recipe_json = get_recipe_file(recipe);
-        if let Err(e) = recipe_json {
-            pretty_error!("Recipe file is invalid".to_string(), e.to_string(), error);
-
-            return HttpResponse::InternalServerError().json(error);
-        }
-
-        let recipe_json = recipe_json.unwrap();
         let value = json!({
             "poster": recipe.poster,
             "id": recipe.id,
-            "title": recipe_json.title,
-            "description": recipe_json.description,
+            "title": recipe.recipe.title,
+            "description": recipe.recipe.description,
             "thumbnail": recipe.thumbnail,
+            "dietary": recipe.dietary,
+            "favourites": recipe.favourites,
+            "favourited": recipe.favourited,
+            "rating": recipe.rating,
+            "visibility": recipe.visibility,
         });
 
         json_values.push(value)
@@ -143,44 +271,83 @@ pub async fn get_recipe_by_poster(
 pub async fn get_recipe(
     pool: Data<Pool<Postgres>>,
     path: actix_web::web::Path<i32>,
+    view: web::Query<RecipeViewQueryParams>,
+    viewer: Viewer,
 ) -> impl Responder {
     let id = path.into_inner();
 
-    let recipe = Recipe::get_by_id(&pool, id).await;
-    if let Err(e) = recipe {
-        pretty_error!(
-            format!("Failed to get recipe with id: {}", id),
-            e.to_string(),
-            error
-        );
+    let recipe = match find_viewable_recipe(&pool, id, viewer.0).await {
+        Ok(recipe) => recipe,
+        Err(e) => return e,
+    };
 
-        return HttpResponse::NotFound().json(error);
+    let mut recipe_json = recipe.recipe;
+    match view.scale_factor(&recipe_json) {
+        Ok(Some(factor)) => recipe_json = recipe_json.scale(factor),
+        Ok(None) => {}
+        Err(e) => return e,
+    }
+
+    // Worked out before converting units so it isn't thrown off by their rounding
+    let nutrition = match Food::get_for_recipe(&pool, id).await {
+        Ok(foods) => recipe_json.nutrition(&foods),
+        Err(e) => {
+            pretty_error!(
+                format!("Failed to get the nutrition of recipe with id: {}", id),
+                e.to_string(),
+                error
+            );
+
+            return HttpResponse::InternalServerError().json(error);
+        }
     };
-    let recipe = recipe.unwrap();
 
-    let recipe_json = get_recipe_file(&recipe);
-    if let Err(e) = recipe_json {
-        pretty_error!("Recipe file is invalid".to_string(), e.to_string(), error);
+    if let Some(units) = view.units {
+        recipe_json = recipe_json.to_system(units);
+    }
+    recipe_json.format_amounts();
+
+    let adapted_from = Recipe::get_parent(&pool, id).await;
+    let forks = Recipe::count_forks(&pool, id).await;
+    let (Ok(adapted_from), Ok(forks)) = (adapted_from, forks) else {
+        pretty_error!(
+            format!("Failed to get the forks of recipe with id: {}", id),
+            "Couldn't get where the recipe was adapted from or its forks".to_string(),
+            error
+        );
 
         return HttpResponse::InternalServerError().json(error);
-    }
+    };
 
-    let recipe_json = recipe_json.unwrap();
     let full_recipe = FullRecipePayload {
         date_created: recipe.date_created,
         id: recipe.id,
         recipe: recipe_json,
         poster: recipe.poster,
         thumbnail: recipe.thumbnail,
+        dietary: recipe.dietary,
+        revision: recipe.revision,
+        favourites: recipe.favourites,
+        favourited: recipe.favourited,
+        rating: recipe.rating,
+        visibility: recipe.visibility,
+        publish_at: recipe.publish_at,
+        nutrition: Some(nutrition),
+        adapted_from,
+        forks,
     };
 
-    HttpResponse::Ok().json(full_recipe)
+    HttpResponse::Ok()
+        .insert_header(revision_etag(full_recipe.revision))
+        .json(full_recipe)
 }
 
 pub async fn edit_recipe(
+    req: HttpRequest,
     authorized: Authorized,
     MultipartForm(form): MultipartForm<EditRecipeForm>,
-    pool: Data<Pool<Postgres>>
+    pool: Data<Pool<Postgres>>,
+    storage: Data<dyn Storage>,
 ) -> impl Responder {
     if let Authorized::Failed(reason) = authorized {
         pretty_error!("Unauthorized", reason, error);
@@ -192,7 +359,7 @@ pub async fn edit_recipe(
         panic!("Despite the if let authorized::failed, we still panicked");
     };
 
-    let recipe = Recipe::get_by_id(&pool, *form.recipe_id).await;
+    let recipe = Recipe::get_by_id(&pool, *form.recipe_id, Some(uid)).await;
     if let Err(e) = recipe {
         pretty_error!(
             format!("Failed to get recipe with id: {}", *form.recipe_id),
@@ -226,22 +393,25 @@ pub async fn edit_recipe(
     }
     let recipe_json = recipe_json.unwrap();
 
-    // Very unlikely, but better to be robust than not
-    if !Path::new(RECIPE_DIR).exists() {
-        let create_dir = fs::create_dir_all(RECIPE_DIR);
+    if let Err(e) = recipe_json.is_valid_recipe() {
+        pretty_error!("Invalid recipe format", e.to_string(), error);
 
-        if let Err(e) = create_dir {
-            pretty_error!("Failed to create dir on recipe save", e.to_string(), error);
+        return HttpResponse::BadRequest().json(error);
+    };
 
-            return HttpResponse::InternalServerError().json(error);
-        }
-    }
+    let expected = match expected_revision(&req, form.revision.as_ref().map(|r| r.0)) {
+        Ok(expected) => expected,
+        Err(e) => return e,
+    };
 
-    let file_path = RECIPE_DIR.to_owned() + &recipe.recipe_file_path;
-    if let Err(e) = create_recipe_file(file_path.clone(), &recipe_json) {
-        pretty_error!("Failed to save recipe file", e.to_string(), error);
+    match Recipe::update(&pool, recipe.id, &recipe_json, uid, expected).await {
+        Ok(Some(..)) => {}
+        Ok(None) => return stale_recipe_response(&pool, recipe.id, uid).await,
+        Err(e) => {
+            pretty_error!("Failed to save recipe", e.to_string(), error);
 
-        return HttpResponse::InternalServerError().json(error);
+            return HttpResponse::InternalServerError().json(error);
+        }
     }
 
     if let Some(temp_thumbnail_file) = form.thumbnail {
@@ -263,9 +433,19 @@ pub async fn edit_recipe(
 
         // Does not need to resolve or return
         // If it fails we just use default thumbnail
-        let file_name = uid.to_string() + "-" + &recipe.recipe_file_path;
-        if let Ok(file_name) = rename_temp_file(temp_thumbnail_file, "./thumbnails", &file_name) {
-            let _ = RecipeThumbnail::insert_or_update(&pool, form.recipe_id.0, file_name).await;
+        let file_name = uid.to_string() + "-" + &Uuid::new_v4().to_string();
+        if let Ok(file_name) =
+            save_temp_file(&**storage, temp_thumbnail_file, THUMBNAIL_DIR, &file_name).await
+        {
+            if RecipeThumbnail::insert_or_update(&pool, form.recipe_id.0, file_name)
+                .await
+                .is_ok()
+            {
+                // Thumbnails get a new name on every upload, so the old one is now an orphan
+                if let Some(old_thumbnail) = &recipe.thumbnail {
+                    let _ = storage.delete(&key(THUMBNAIL_DIR, old_thumbnail)).await;
+                }
+            }
         }
     };
 
@@ -277,6 +457,7 @@ pub async fn create_recipe(
     authorized: Authorized,
     MultipartForm(form): MultipartForm<CreateRecipeForm>,
     pool: Data<Pool<Postgres>>,
+    storage: Data<dyn Storage>,
 ) -> impl Responder {
     if let Authorized::Failed(reason) = authorized {
         pretty_error!("Unauthorized", reason, error);
@@ -288,10 +469,6 @@ pub async fn create_recipe(
         panic!("Despite the if let authorized::failed, we still panicked");
     };
 
-    let uuid = Uuid::new_v4();
-    let file_name = uid.to_string() + "-" + &uuid.to_string();
-    let file_path = RECIPE_DIR.to_owned() + &file_name;
-
     let recipe = serde_json::from_str::<RecipeFileJson>(&form.recipe.to_string());
 
     if let Err(_err) = recipe {
@@ -311,23 +488,23 @@ pub async fn create_recipe(
         return HttpResponse::BadRequest().json(error);
     };
 
-    if !Path::new(RECIPE_DIR).exists() {
-        let create_dir = fs::create_dir_all(RECIPE_DIR);
-
-        if let Err(e) = create_dir {
-            pretty_error!("Failed to create dir on recipe save", e.to_string(), error);
-
-            return HttpResponse::InternalServerError().json(error);
-        }
-    }
-
-    if let Err(e) = create_recipe_file(file_path.clone(), &recipe) {
-        pretty_error!("Failed to save recipe file", e.to_string(), error);
-
-        return HttpResponse::InternalServerError().json(error);
-    }
+    let visibility = match &form.visibility {
+        Some(visibility) => match Visibility::from_name(visibility) {
+            Some(visibility) => visibility,
+            None => {
+                pretty_error!(
+                    "Invalid visibility",
+                    "Visibility must be one of draft, private, unlisted or public",
+                    error
+                );
+
+                return HttpResponse::BadRequest().json(error);
+            }
+        },
+        None => Visibility::Public,
+    };
 
-    let insert_recipe = Recipe::insert(&pool, file_name.clone(), uid).await;
+    let insert_recipe = Recipe::insert(&pool, &recipe, uid, None, visibility).await;
     if let Err(e) = insert_recipe {
         pretty_error!(
             "Failed to insert recipe data into database",
@@ -358,7 +535,10 @@ pub async fn create_recipe(
 
         // Does not need to resolve or return
         // If it fails we just use default thumbnail
-        if let Ok(file_name) = rename_temp_file(temp_thumbnail_file, "./thumbnails", &file_name) {
+        let file_name = uid.to_string() + "-" + &Uuid::new_v4().to_string();
+        if let Ok(file_name) =
+            save_temp_file(&**storage, temp_thumbnail_file, THUMBNAIL_DIR, &file_name).await
+        {
             let _ = RecipeThumbnail::insert_or_update(&pool, recipe_id, file_name).await;
         }
     };
@@ -395,8 +575,9 @@ pub async fn can_edit(
     let poster_id = poster_id.unwrap();
 
     let authorized = poster_id == uid;
+    let mut response = HttpResponse::Ok();
     let json = if authorized {
-        let recipe = Recipe::get_by_id(&pool, recipe_id).await;
+        let recipe = Recipe::get_by_id(&pool, recipe_id, Some(uid)).await;
         if let Err(e) = recipe {
             pretty_error!(
                 format!("Failed to get recipe with id: {}", recipe_id),
@@ -406,24 +587,30 @@ pub async fn can_edit(
 
             return HttpResponse::InternalServerError().json(error);
         }
-        let recipe = recipe.unwrap();
-
-        let recipe_json = get_recipe_file(&recipe);
-        if let Err(e) = recipe_json {
-            pretty_error!("Recipe file is invalid".to_string(), e.to_string(), error);
-
-            return HttpResponse::InternalServerError().json(error);
-        }
+        let mut recipe = recipe.unwrap();
+        recipe.recipe.format_amounts();
 
-        let recipe_json = recipe_json.unwrap();
         let full_recipe = FullRecipePayload {
             date_created: recipe.date_created,
             id: recipe.id,
-            recipe: recipe_json,
+            recipe: recipe.recipe,
             poster: recipe.poster,
             thumbnail: recipe.thumbnail,
+            dietary: recipe.dietary,
+            revision: recipe.revision,
+            favourites: recipe.favourites,
+            favourited: recipe.favourited,
+            rating: recipe.rating,
+            visibility: recipe.visibility,
+            publish_at: recipe.publish_at,
+            nutrition: None,
+            adapted_from: None,
+            forks: 0,
         };
 
+        // Sent back as If-Match when the edit is saved
+        response.insert_header(revision_etag(full_recipe.revision));
+
         json!({
             "authorized": authorized,
             "recipe": full_recipe
@@ -434,5 +621,582 @@ pub async fn can_edit(
         })
     };
 
-    HttpResponse::Ok().json(json)
+    response.json(json)
+}
+
+// #[delete(/{recipe_id})]
+pub async fn delete_recipe(
+    authorized: Authorized,
+    path: actix_web::web::Path<i32>,
+    pool: Data<Pool<Postgres>>,
+    storage: Data<dyn Storage>,
+) -> impl Responder {
+    let recipe_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let recipe = Recipe::get_by_id(&pool, recipe_id, Some(uid)).await;
+    if let Err(e) = recipe {
+        pretty_error!(
+            format!("Failed to get recipe with id: {}", recipe_id),
+            e.to_string(),
+            error
+        );
+
+        return HttpResponse::NotFound().json(error);
+    };
+
+    let recipe = recipe.unwrap();
+    if recipe.poster.uid != uid {
+        pretty_error!(
+            format!("Failed to delete recipe"),
+            "Poster id and submitter id do not match",
+            error
+        );
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    // The row goes first, a leftover file is better than a row pointing at nothing
+    if let Err(e) = Recipe::delete(&pool, recipe_id).await {
+        pretty_error!("Failed to delete recipe", e.to_string(), error);
+
+        return HttpResponse::InternalServerError().json(error);
+    }
+
+    if let Some(thumbnail) = &recipe.thumbnail {
+        if let Err(e) = storage.delete(&key(THUMBNAIL_DIR, thumbnail)).await {
+            pretty_error!("Failed to delete recipe thumbnail", e.to_string(), error);
+
+            return HttpResponse::InternalServerError().json(error);
+        }
+    }
+
+    HttpResponse::Ok().body("Succesfully deleted recipe")
+}
+
+#[get("/{recipe_id}/revisions")]
+pub async fn get_recipe_revisions(
+    pool: Data<Pool<Postgres>>,
+    path: actix_web::web::Path<i32>,
+    viewer: Viewer,
+) -> impl Responder {
+    let recipe_id = path.into_inner();
+    if let Err(e) = find_viewable_recipe(&pool, recipe_id, viewer.0).await {
+        return e;
+    }
+
+    match RecipeRevision::get_all(&pool, recipe_id).await {
+        Ok(revisions) if revisions.is_empty() => {
+            pretty_error!(
+                "No revisions found".to_string(),
+                format!(
+                    "Couldn't find any revisions of recipe with id: {}",
+                    recipe_id
+                ),
+                error
+            );
+
+            HttpResponse::NotFound().json(error)
+        }
+        Ok(revisions) => HttpResponse::Ok().json(revisions),
+        Err(e) => {
+            pretty_error!("Failed to get revisions", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+#[get("/{recipe_id}/revisions/{revision}")]
+pub async fn get_recipe_revision(
+    pool: Data<Pool<Postgres>>,
+    path: actix_web::web::Path<(i32, i32)>,
+    viewer: Viewer,
+) -> impl Responder {
+    let (recipe_id, revision) = path.into_inner();
+    if let Err(e) = find_viewable_recipe(&pool, recipe_id, viewer.0).await {
+        return e;
+    }
+
+    match find_revision(&pool, recipe_id, revision).await {
+        Ok(mut revision) => {
+            revision.recipe.format_amounts();
+
+            HttpResponse::Ok().json(revision)
+        }
+        Err(e) => e,
+    }
+}
+
+#[get("/{recipe_id}/revisions/{revision}/diff")]
+pub async fn get_recipe_revision_diff(
+    pool: Data<Pool<Postgres>>,
+    path: actix_web::web::Path<(i32, i32)>,
+    params: web::Query<RevisionDiffQueryParams>,
+    viewer: Viewer,
+) -> impl Responder {
+    let (recipe_id, revision) = path.into_inner();
+    if let Err(e) = find_viewable_recipe(&pool, recipe_id, viewer.0).await {
+        return e;
+    }
+    let against = params.against.unwrap_or(revision - 1);
+    if against < 1 {
+        pretty_error!(
+            "Nothing to compare against",
+            "Revisions start at 1, so the first revision has nothing before it to compare against",
+            error
+        );
+
+        return HttpResponse::BadRequest().json(error);
+    }
+
+    let mut to = match find_revision(&pool, recipe_id, revision).await {
+        Ok(to) => to,
+        Err(e) => return e,
+    };
+
+    let mut from = match find_revision(&pool, recipe_id, against).await {
+        Ok(from) => from,
+        Err(e) => return e,
+    };
+    from.recipe.format_amounts();
+    to.recipe.format_amounts();
+
+    HttpResponse::Ok().json(json!({
+        "from": from.revision,
+        "to": to.revision,
+        "diff": from.recipe.diff(&to.recipe),
+    }))
+}
+
+// #[post(/{recipe_id}/revisions/{revision}/restore)]
+pub async fn restore_recipe_revision(
+    req: HttpRequest,
+    authorized: Authorized,
+    path: actix_web::web::Path<(i32, i32)>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let (recipe_id, revision) = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let poster_id = Recipe::get_poster(&pool, recipe_id).await;
+    if let Err(e) = poster_id {
+        pretty_error!(
+            format!("Failed to get recipe with id: {}", recipe_id),
+            e.to_string(),
+            error
+        );
+
+        return HttpResponse::NotFound().json(error);
+    }
+
+    if poster_id.unwrap() != uid {
+        pretty_error!(
+            format!("Failed to restore recipe"),
+            "Poster id and submitter id do not match",
+            error
+        );
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let old = match find_revision(&pool, recipe_id, revision).await {
+        Ok(old) => old,
+        Err(e) => return e,
+    };
+
+    // Old revisions were saved under older rules, so they are checked like any edit
+    if let Err(e) = old.recipe.is_valid_recipe() {
+        pretty_error!("Invalid recipe format", e.to_string(), error);
+
+        return HttpResponse::BadRequest().json(error);
+    };
+
+    // If-Match is optional here, restoring is a deliberate choice of version anyway
+    let expected = if req.headers().contains_key(header::IF_MATCH) {
+        match expected_revision(&req, None) {
+            Ok(expected) => expected,
+            Err(e) => return e,
+        }
+    } else {
+        None
+    };
+
+    // Restoring saves the old recipe as a new revision, so the history is never rewritten
+    match Recipe::update(&pool, recipe_id, &old.recipe, uid, expected).await {
+        Ok(Some(new_revision)) => HttpResponse::Ok()
+            .insert_header(revision_etag(new_revision))
+            .json(json!({ "revision": new_revision })),
+        Ok(None) => stale_recipe_response(&pool, recipe_id, uid).await,
+        Err(e) => {
+            pretty_error!("Failed to restore recipe", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/{recipe_id}/visibility)]
+pub async fn set_recipe_visibility(
+    authorized: Authorized,
+    path: actix_web::web::Path<i32>,
+    payload: web::Json<VisibilityPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let recipe_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let poster_id = Recipe::get_poster(&pool, recipe_id).await;
+    if let Err(e) = poster_id {
+        pretty_error!(
+            format!("Failed to get recipe with id: {}", recipe_id),
+            e.to_string(),
+            error
+        );
+
+        return HttpResponse::NotFound().json(error);
+    }
+
+    if poster_id.unwrap() != uid {
+        pretty_error!(
+            format!("Failed to change recipe visibility"),
+            "Poster id and submitter id do not match",
+            error
+        );
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    if let Some(publish_at) = payload.publish_at {
+        if payload.visibility == Visibility::Public {
+            pretty_error!(
+                "Invalid schedule",
+                "A recipe can only be scheduled to publish if it isn't public yet",
+                error
+            );
+
+            return HttpResponse::BadRequest().json(error);
+        }
+
+        if publish_at <= chrono::Utc::now() {
+            pretty_error!(
+                "Invalid schedule",
+                "A recipe can only be scheduled to publish in the future",
+                error
+            );
+
+            return HttpResponse::BadRequest().json(error);
+        }
+    }
+
+    if let Err(e) =
+        Recipe::set_visibility(&pool, recipe_id, payload.visibility, payload.publish_at).await
+    {
+        pretty_error!("Failed to change recipe visibility", e.to_string(), error);
+
+        return HttpResponse::InternalServerError().json(error);
+    }
+
+    HttpResponse::Ok().json(json!({
+        "visibility": payload.visibility,
+        "publish_at": payload.publish_at,
+    }))
+}
+
+// #[post(/{recipe_id}/favourite)]
+pub async fn favourite_recipe(
+    authorized: Authorized,
+    path: actix_web::web::Path<i32>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let recipe_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let recipe = match find_viewable_recipe(&pool, recipe_id, Some(uid)).await {
+        Ok(recipe) => recipe,
+        Err(e) => return e,
+    };
+
+    // Favouriting twice is not an error, the recipe is favourited either way
+    match Favourite::insert(&pool, uid, recipe_id).await {
+        Ok(favourited) => {
+            if favourited {
+                // The favourite is saved either way, so a missed notification doesn't fail it
+                let _ = Notification::notify(
+                    &pool,
+                    recipe.poster.uid,
+                    NotificationKind::Favourite,
+                    uid,
+                    Some(recipe_id),
+                    None,
+                )
+                .await;
+            }
+
+            HttpResponse::Ok().body("Succesfully favourited recipe")
+        }
+        Err(e) => {
+            pretty_error!("Failed to favourite recipe", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[delete(/{recipe_id}/favourite)]
+pub async fn unfavourite_recipe(
+    authorized: Authorized,
+    path: actix_web::web::Path<i32>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let recipe_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match Favourite::delete(&pool, uid, recipe_id).await {
+        Ok(..) => HttpResponse::Ok().body("Succesfully unfavourited recipe"),
+        Err(e) => {
+            pretty_error!("Failed to unfavourite recipe", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/{recipe_id}/fork)]
+pub async fn fork_recipe(
+    authorized: Authorized,
+    path: actix_web::web::Path<i32>,
+    pool: Data<Pool<Postgres>>,
+    storage: Data<dyn Storage>,
+) -> impl Responder {
+    let recipe_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let recipe = match find_viewable_recipe(&pool, recipe_id, Some(uid)).await {
+        Ok(recipe) => recipe,
+        Err(e) => return e,
+    };
+
+    if recipe.poster.uid == uid {
+        pretty_error!(
+            "Failed to fork recipe",
+            "You can't fork your own recipe",
+            error
+        );
+
+        return HttpResponse::BadRequest().json(error);
+    }
+
+    // A fork of an unlisted recipe stays unlisted, so forking can't make it public
+    let insert_recipe = Recipe::insert(
+        &pool,
+        &recipe.recipe,
+        uid,
+        Some(recipe_id),
+        recipe.visibility,
+    )
+    .await;
+    if let Err(e) = insert_recipe {
+        pretty_error!(
+            "Failed to insert recipe data into database",
+            e.to_string(),
+            error
+        );
+
+        return HttpResponse::InternalServerError().json(error);
+    }
+    let fork_id = insert_recipe.unwrap();
+
+    // The fork gets its own copy of the thumbnail so it isn't lost if the parent is deleted
+    // If it fails we just use default thumbnail
+    if let Some(thumbnail) = recipe.thumbnail {
+        let file_name = uid.to_string() + "-" + &Uuid::new_v4().to_string();
+        if let Ok(file_name) = copy_file(&**storage, THUMBNAIL_DIR, &thumbnail, &file_name).await {
+            let _ = RecipeThumbnail::insert_or_update(&pool, fork_id, file_name).await;
+        }
+    }
+
+    // The fork is saved either way, so a missed notification doesn't fail it
+    let _ = Notification::notify(
+        &pool,
+        recipe.poster.uid,
+        NotificationKind::Fork,
+        uid,
+        Some(fork_id),
+        None,
+    )
+    .await;
+
+    HttpResponse::Ok().body(fork_id.to_string())
+}
+
+#[get("/{recipe_id}/forks")]
+pub async fn get_recipe_forks(
+    pool: Data<Pool<Postgres>>,
+    path: actix_web::web::Path<i32>,
+    pagination: web::Query<GetRecipeQueryParams>,
+    viewer: Viewer,
+) -> impl Responder {
+    let recipe_id = path.into_inner();
+
+    let recipes = Recipe::get_forks(
+        &pool,
+        recipe_id,
+        pagination.offset(),
+        pagination.limit(),
+        viewer.0,
+    )
+    .await;
+    if let Err(e) = recipes {
+        pretty_error!(
+            format!("Failed to get forks of recipe with id: {}", recipe_id),
+            e.to_string(),
+            error
+        );
+
+        return HttpResponse::NotFound().json(error);
+    };
+    let recipes = recipes.unwrap();
+
+    let mut json_values: Vec<serde_json::Value> = Vec::new();
+    for recipe in recipes.iter() {
+        let value = json!({
+            "poster": recipe.poster,
+            "id": recipe.id,
+            "title": recipe.recipe.title,
+            "description": recipe.recipe.description,
+            "thumbnail": recipe.thumbnail,
+            "dietary": recipe.dietary,
+            "favourites": recipe.favourites,
+            "favourited": recipe.favourited,
+            "rating": recipe.rating,
+            "visibility": recipe.visibility,
+        });
+
+        json_values.push(value);
+    }
+
+    HttpResponse::Ok().json(json_values)
+}
+
+// The edit was made against an old revision, so the client gets the current one to merge with
+async fn stale_recipe_response(pool: &Pool<Postgres>, recipe_id: i32, uid: i32) -> HttpResponse {
+    let mut recipe = match Recipe::get_by_id(pool, recipe_id, Some(uid)).await {
+        Ok(recipe) => recipe,
+        Err(e) => {
+            pretty_error!(
+                format!("Failed to get recipe with id: {}", recipe_id),
+                e.to_string(),
+                error
+            );
+
+            return HttpResponse::InternalServerError().json(error);
+        }
+    };
+
+    recipe.recipe.format_amounts();
+
+    let current = FullRecipePayload {
+        date_created: recipe.date_created,
+        id: recipe.id,
+        recipe: recipe.recipe,
+        poster: recipe.poster,
+        thumbnail: recipe.thumbnail,
+        dietary: recipe.dietary,
+        revision: recipe.revision,
+        favourites: recipe.favourites,
+        favourited: recipe.favourited,
+        rating: recipe.rating,
+        visibility: recipe.visibility,
+        publish_at: recipe.publish_at,
+        nutrition: None,
+        adapted_from: None,
+        forks: 0,
+    };
+
+    HttpResponse::PreconditionFailed()
+        .insert_header(revision_etag(current.revision))
+        .json(json!({
+            "error": "Recipe has changed",
+            "description": format!(
+                "The recipe is now on revision {}, please apply your changes to it and save again",
+                current.revision
+            ),
+            "current": current,
+        }))
+}
+
+async fn find_revision(
+    pool: &Pool<Postgres>,
+    recipe_id: i32,
+    revision: i32,
+) -> Result<RecipeRevision, HttpResponse> {
+    match RecipeRevision::get(pool, recipe_id, revision).await {
+        Ok(Some(revision)) => Ok(revision),
+        Ok(None) => {
+            pretty_error!(
+                "No revision found".to_string(),
+                format!(
+                    "Couldn't find revision {} of recipe with id: {}",
+                    revision, recipe_id
+                ),
+                error
+            );
+
+            Err(HttpResponse::NotFound().json(error))
+        }
+        Err(e) => {
+            pretty_error!("Failed to get revision", e.to_string(), error);
+
+            Err(HttpResponse::InternalServerError().json(error))
+        }
+    }
 }
@@ -0,0 +1,49 @@
+use actix_web::HttpResponse;
+use serde::Deserialize;
+
+use crate::{pretty_error, recipe_io::RecipeStep, routes::error::PrettyErrorResponse};
+
+#[derive(Deserialize)]
+pub struct ReviewPayload {
+    // 1 to 5 stars
+    pub rating: i16,
+    pub review: Option<String>,
+}
+
+impl ReviewPayload {
+    #[allow(clippy::result_large_err)]
+    pub fn verify(&self) -> Result<(), HttpResponse> {
+        if !(1..=5).contains(&self.rating) {
+            pretty_error!(
+                "This rating is invalid".to_string(),
+                "Please rate the recipe from 1 to 5 stars",
+                error
+            );
+
+            return Err(HttpResponse::BadRequest().json(error));
+        }
+
+        // Checked the same way as the details of a recipe step
+        if let Some(review) = self.review() {
+            if !RecipeStep::is_valid_details(&review) {
+                pretty_error!(
+                    "This review is invalid".to_string(),
+                    "Please only use alphanumerical characters",
+                    error
+                );
+
+                return Err(HttpResponse::BadRequest().json(error));
+            }
+        }
+
+        Ok(())
+    }
+
+    // A blank review is the same as leaving only a rating
+    pub fn review(&self) -> Option<String> {
+        self.review
+            .as_ref()
+            .map(|review| review.trim().to_string())
+            .filter(|review| !review.is_empty())
+    }
+}
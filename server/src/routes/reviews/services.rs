@@ -0,0 +1,143 @@
+use actix_web::{
+    get,
+    web::{self, Data, Path},
+    HttpResponse, Responder,
+};
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    database::models::{
+        notification::{Notification, NotificationKind},
+        review::Review,
+    },
+    extractors::auth::{Authorized, Viewer},
+    pretty_error,
+    routes::{
+        error::PrettyErrorResponse,
+        recipes::helpers::{find_viewable_recipe, GetRecipeQueryParams},
+    },
+};
+
+use super::helpers::ReviewPayload;
+
+// #[post(/{recipe_id}/review)]
+pub async fn review_recipe(
+    authorized: Authorized,
+    path: Path<i32>,
+    payload: web::Json<ReviewPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let recipe_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let recipe = match find_viewable_recipe(&pool, recipe_id, Some(uid)).await {
+        Ok(recipe) => recipe,
+        Err(e) => return e,
+    };
+
+    if recipe.poster.uid == uid {
+        pretty_error!(
+            "Failed to review recipe",
+            "You can't review your own recipe",
+            error
+        );
+
+        return HttpResponse::BadRequest().json(error);
+    }
+
+    if let Err(e) = payload.verify() {
+        return e;
+    }
+
+    match Review::insert_or_update(&pool, uid, recipe_id, payload.rating, &payload.review()).await {
+        Ok(true) => {
+            // The review is saved either way, so a missed notification doesn't fail it
+            let _ = Notification::notify(
+                &pool,
+                recipe.poster.uid,
+                NotificationKind::Review,
+                uid,
+                Some(recipe_id),
+                None,
+            )
+            .await;
+
+            HttpResponse::Ok().body("Succesfully reviewed recipe")
+        }
+        Ok(false) => HttpResponse::Ok().body("Succesfully updated review"),
+        Err(e) => {
+            pretty_error!("Failed to review recipe", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[delete(/{recipe_id}/review)]
+pub async fn delete_review(
+    authorized: Authorized,
+    path: Path<i32>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let recipe_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match Review::delete(&pool, uid, recipe_id).await {
+        Ok(true) => HttpResponse::Ok().body("Succesfully deleted review"),
+        Ok(false) => {
+            pretty_error!(
+                "No review found".to_string(),
+                format!(
+                    "Couldn't find your review of the recipe with id: {}",
+                    recipe_id
+                ),
+                error
+            );
+
+            HttpResponse::NotFound().json(error)
+        }
+        Err(e) => {
+            pretty_error!("Failed to delete review", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+#[get("/{recipe_id}/reviews")]
+pub async fn get_recipe_reviews(
+    pool: Data<Pool<Postgres>>,
+    path: Path<i32>,
+    pagination: web::Query<GetRecipeQueryParams>,
+    viewer: Viewer,
+) -> impl Responder {
+    let recipe_id = path.into_inner();
+    if let Err(e) = find_viewable_recipe(&pool, recipe_id, viewer.0).await {
+        return e;
+    }
+
+    match Review::get_for_recipe(&pool, recipe_id, pagination.offset(), pagination.limit()).await {
+        Ok(reviews) => HttpResponse::Ok().json(reviews),
+        Err(e) => {
+            pretty_error!("Failed to get reviews", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+
+use actix_web::HttpResponse;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    database::models::{
+        ingredient::Ingredient,
+        meal_plan::MealPlan,
+        shopping_list::{NewShoppingListItem, ShoppingList, ShoppingListItem},
+    },
+    helpers::is_alnum_whitespace_and_ex_chars,
+    pretty_error,
+    recipe_io::{
+        conversion::{Dimension, UnitSystem},
+        shopping::{combine, Aisle, Quantity, ShoppingIngredient},
+        RecipeFileJson,
+    },
+    routes::{
+        error::PrettyErrorResponse, meal_plan::helpers::MealPlanQueryParams,
+        recipes::helpers::find_viewable_recipe,
+    },
+};
+
+const MAX_NAME_LENGTH: usize = 50;
+const MAX_ITEM_NAME_LENGTH: usize = 100;
+const MAX_RECIPES: usize = 50;
+const MAX_SERVINGS: u32 = 100;
+
+#[derive(Deserialize)]
+pub struct ShoppingRecipe {
+    pub recipe_id: i32,
+    // Scales the recipe to make this many servings, if it says how many it makes
+    pub servings: Option<u32>,
+}
+
+// A list is generated from recipes, the meal plan between two days, or both
+#[derive(Deserialize)]
+pub struct CreateShoppingListPayload {
+    pub name: String,
+    #[serde(default)]
+    pub recipes: Vec<ShoppingRecipe>,
+    pub meal_plan: Option<MealPlanQueryParams>,
+    // The units amounts are given in when they had to be converted, metric by default
+    pub system: Option<UnitSystem>,
+}
+
+impl CreateShoppingListPayload {
+    #[allow(clippy::result_large_err)]
+    pub fn verify(&self) -> Result<(), HttpResponse> {
+        verify_name(&self.name, MAX_NAME_LENGTH, "shopping list name")?;
+
+        if self.recipes.is_empty() && self.meal_plan.is_none() {
+            pretty_error!(
+                "Nothing to shop for".to_string(),
+                "Please give some recipes or a meal plan date range",
+                error
+            );
+
+            return Err(HttpResponse::BadRequest().json(error));
+        }
+
+        if self.recipes.len() > MAX_RECIPES {
+            pretty_error!(
+                "Too many recipes".to_string(),
+                format!(
+                    "A shopping list can be made from up to {} recipes",
+                    MAX_RECIPES
+                ),
+                error
+            );
+
+            return Err(HttpResponse::BadRequest().json(error));
+        }
+
+        for recipe in self.recipes.iter() {
+            if let Some(servings) = recipe.servings {
+                if !(1..=MAX_SERVINGS).contains(&servings) {
+                    pretty_error!(
+                        "These servings are invalid".to_string(),
+                        format!("Please shop for 1 to {} servings", MAX_SERVINGS),
+                        error
+                    );
+
+                    return Err(HttpResponse::BadRequest().json(error));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ShoppingListItemPayload {
+    pub name: String,
+    pub quantity: Option<Quantity>,
+    // Other if not given
+    pub aisle: Option<Aisle>,
+}
+
+impl ShoppingListItemPayload {
+    #[allow(clippy::result_large_err)]
+    pub fn verify(&self) -> Result<(), HttpResponse> {
+        verify_name(&self.name, MAX_ITEM_NAME_LENGTH, "item name")?;
+
+        if let Some(quantity) = &self.quantity {
+            if !quantity.amount.is_valid()
+                || quantity.measurement.dimension() == Dimension::Temperature
+            {
+                pretty_error!(
+                    "This quantity is invalid".to_string(),
+                    "Please give a positive amount of something you can buy",
+                    error
+                );
+
+                return Err(HttpResponse::BadRequest().json(error));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CheckItemPayload {
+    pub checked: bool,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Text,
+    Markdown,
+}
+
+#[derive(Deserialize)]
+pub struct ExportQueryParams {
+    // Plain text if not given
+    pub format: Option<ExportFormat>,
+}
+
+#[derive(Serialize)]
+pub struct AisleItems {
+    pub aisle: Aisle,
+    pub items: Vec<ShoppingListItem>,
+}
+
+#[derive(Serialize)]
+pub struct FullShoppingList {
+    #[serde(flatten)]
+    pub list: ShoppingList,
+    // Only aisles with something in them, in the order they are walked
+    pub aisles: Vec<AisleItems>,
+}
+
+#[allow(clippy::result_large_err)]
+fn verify_name(name: &str, max_length: usize, what: &str) -> Result<(), HttpResponse> {
+    let name = name.trim();
+    if name.is_empty()
+        || name.chars().count() > max_length
+        || !is_alnum_whitespace_and_ex_chars(name)
+    {
+        pretty_error!(
+            format!("This {} is invalid", what),
+            format!(
+                "Please only use alphanumerical characters, up to {} of them",
+                max_length
+            ),
+            error
+        );
+
+        return Err(HttpResponse::BadRequest().json(error));
+    }
+
+    Ok(())
+}
+
+// Gets a shopping list for viewing or changing it, only its owner is allowed to
+pub async fn find_own_shopping_list(
+    pool: &Pool<Postgres>,
+    list_id: i32,
+    uid: i32,
+) -> Result<ShoppingList, HttpResponse> {
+    match ShoppingList::get(pool, list_id).await {
+        Ok(Some(list)) if list.user_id == uid => Ok(list),
+        Ok(Some(..)) => {
+            pretty_error!(
+                "Failed to get shopping list",
+                "Owner id and submitter id do not match",
+                error
+            );
+
+            Err(HttpResponse::Unauthorized().json(error))
+        }
+        Ok(None) => {
+            pretty_error!(
+                "No shopping list found".to_string(),
+                format!("Couldn't find shopping list with the id: {}", list_id),
+                error
+            );
+
+            Err(HttpResponse::NotFound().json(error))
+        }
+        Err(e) => {
+            pretty_error!("Failed to get shopping list", e.to_string(), error);
+
+            Err(HttpResponse::InternalServerError().json(error))
+        }
+    }
+}
+
+// Works out what to buy for the recipes and meal plan in the payload, the same ingredient
+// across recipes becomes one item
+pub async fn generate_items(
+    pool: &Pool<Postgres>,
+    uid: i32,
+    payload: &CreateShoppingListPayload,
+) -> Result<Vec<NewShoppingListItem>, HttpResponse> {
+    // Each recipe with the servings to shop for, None for however many it makes
+    let mut wanted: Vec<(i32, Option<u32>)> = payload
+        .recipes
+        .iter()
+        .map(|recipe| (recipe.recipe_id, recipe.servings))
+        .collect();
+
+    if let Some(meal_plan) = &payload.meal_plan {
+        let (from, to) = meal_plan.range()?;
+        let entries = match MealPlan::get_range(pool, uid, from, to).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                pretty_error!("Failed to get meal plan", e.to_string(), error);
+
+                return Err(HttpResponse::InternalServerError().json(error));
+            }
+        };
+
+        // Recipes hidden since they were planned are left out
+        wanted.extend(
+            entries
+                .iter()
+                .filter(|entry| entry.recipe_title.is_some())
+                .map(|entry| {
+                    (
+                        entry.recipe_id,
+                        entry.servings.map(|servings| servings as u32),
+                    )
+                }),
+        );
+    }
+
+    let mut recipes: HashMap<i32, RecipeFileJson> = HashMap::new();
+    for (recipe_id, _) in wanted.iter() {
+        if !recipes.contains_key(recipe_id) {
+            let recipe = find_viewable_recipe(pool, *recipe_id, Some(uid)).await?;
+            recipes.insert(*recipe_id, recipe.recipe);
+        }
+    }
+
+    let recipe_ids: Vec<i32> = recipes.keys().copied().collect();
+    let links = match Ingredient::get_recipe_links(pool, &recipe_ids).await {
+        Ok(links) => links,
+        Err(e) => {
+            pretty_error!("Failed to create shopping list", e.to_string(), error);
+
+            return Err(HttpResponse::InternalServerError().json(error));
+        }
+    };
+
+    let mut ingredients = Vec::new();
+    for (recipe_id, servings) in wanted {
+        let Some(recipe) = recipes.get(&recipe_id) else {
+            continue;
+        };
+
+        let recipe = match servings.and_then(|servings| recipe.scale_for_servings(servings)) {
+            Some(factor) => recipe.scale(factor),
+            None => recipe.clone(),
+        };
+
+        for (position, measurements) in recipe.ingredients.iter().enumerate() {
+            let link = links.get(&(recipe_id, position as i32));
+            ingredients.push(ShoppingIngredient::from_measurements(
+                measurements,
+                link.map(|link| link.id),
+                link.and_then(|link| link.plural.clone()),
+                link.map(|link| link.aisle)
+                    .unwrap_or_else(|| Aisle::guess(&measurements.ingredient)),
+            ));
+        }
+    }
+
+    let system = payload.system.unwrap_or(UnitSystem::Metric);
+
+    Ok(combine(&ingredients, system)
+        .into_iter()
+        .map(|ingredient| NewShoppingListItem {
+            ingredient_id: ingredient.ingredient_id,
+            name: ingredient.list_name(),
+            quantity: Some(ingredient.quantity),
+            aisle: ingredient.aisle,
+            manual: false,
+        })
+        .collect())
+}
+
+pub fn group_by_aisle(items: Vec<ShoppingListItem>) -> Vec<AisleItems> {
+    let mut by_aisle: HashMap<Aisle, Vec<ShoppingListItem>> = HashMap::new();
+    for mut item in items {
+        item.quantity_display = item.quantity.map(|quantity| quantity.to_string());
+        by_aisle.entry(item.aisle).or_default().push(item);
+    }
+
+    Aisle::ALL
+        .into_iter()
+        .filter_map(|aisle| {
+            by_aisle
+                .remove(&aisle)
+                .map(|items| AisleItems { aisle, items })
+        })
+        .collect()
+}
+
+fn item_line(item: &ShoppingListItem, name: &str) -> String {
+    match &item.quantity {
+        Some(quantity) => format!("{} {}", quantity, name),
+        None => name.to_string(),
+    }
+}
+
+// Names are free text, so anything markdown would read as formatting is escaped
+fn escape_markdown(text: &str) -> String {
+    text.chars().fold(String::new(), |mut escaped, c| {
+        if "\\`*_[]<>#|~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        escaped
+    })
+}
+
+pub fn export(list: &FullShoppingList, format: ExportFormat) -> String {
+    let mut lines = Vec::new();
+
+    match format {
+        ExportFormat::Text => {
+            lines.push(list.list.name.clone());
+            for group in list.aisles.iter() {
+                lines.push(String::new());
+                lines.push(group.aisle.title().to_string());
+                for item in group.items.iter() {
+                    let check = if item.checked { "[x]" } else { "[ ]" };
+                    lines.push(format!("{} {}", check, item_line(item, &item.name)));
+                }
+            }
+        }
+        ExportFormat::Markdown => {
+            lines.push(format!("# {}", escape_markdown(&list.list.name)));
+            for group in list.aisles.iter() {
+                lines.push(String::new());
+                lines.push(format!("## {}", group.aisle.title()));
+                lines.push(String::new());
+                for item in group.items.iter() {
+                    let check = if item.checked { "[x]" } else { "[ ]" };
+                    lines.push(format!(
+                        "- {} {}",
+                        check,
+                        item_line(item, &escape_markdown(&item.name))
+                    ));
+                }
+            }
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
@@ -0,0 +1,314 @@
+use actix_web::{
+    http::header,
+    web::{self, Data, Path},
+    HttpResponse, Responder,
+};
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    database::models::shopping_list::{NewShoppingListItem, ShoppingList},
+    extractors::auth::Authorized,
+    pretty_error,
+    recipe_io::shopping::Aisle,
+    routes::{error::PrettyErrorResponse, recipes::helpers::GetRecipeQueryParams},
+};
+
+use super::helpers::{
+    export, find_own_shopping_list, generate_items, group_by_aisle, CheckItemPayload,
+    CreateShoppingListPayload, ExportFormat, ExportQueryParams, FullShoppingList,
+    ShoppingListItemPayload,
+};
+
+// #[get(/shopping_lists)]
+pub async fn get_shopping_lists(
+    authorized: Authorized,
+    pagination: web::Query<GetRecipeQueryParams>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match ShoppingList::get_by_user(&pool, uid, pagination.offset(), pagination.limit()).await {
+        Ok(lists) => HttpResponse::Ok().json(lists),
+        Err(e) => {
+            pretty_error!("Failed to get shopping lists", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/shopping_lists)]
+pub async fn create_shopping_list(
+    authorized: Authorized,
+    payload: web::Json<CreateShoppingListPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = payload.verify() {
+        return e;
+    }
+
+    let items = match generate_items(&pool, uid, &payload).await {
+        Ok(items) => items,
+        Err(e) => return e,
+    };
+
+    match ShoppingList::insert(&pool, uid, payload.name.trim(), &items).await {
+        Ok(list_id) => HttpResponse::Ok().body(list_id.to_string()),
+        Err(e) => {
+            pretty_error!("Failed to create shopping list", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+async fn get_full_shopping_list(
+    pool: &Pool<Postgres>,
+    list_id: i32,
+    uid: i32,
+) -> Result<FullShoppingList, HttpResponse> {
+    let list = find_own_shopping_list(pool, list_id, uid).await?;
+
+    match ShoppingList::get_items(pool, list_id).await {
+        Ok(items) => Ok(FullShoppingList {
+            list,
+            aisles: group_by_aisle(items),
+        }),
+        Err(e) => {
+            pretty_error!("Failed to get shopping list", e.to_string(), error);
+
+            Err(HttpResponse::InternalServerError().json(error))
+        }
+    }
+}
+
+// #[get(/shopping_lists/{list_id})]
+pub async fn get_shopping_list(
+    authorized: Authorized,
+    path: Path<i32>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let list_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match get_full_shopping_list(&pool, list_id, uid).await {
+        Ok(list) => HttpResponse::Ok().json(list),
+        Err(e) => e,
+    }
+}
+
+// #[get(/shopping_lists/{list_id}/export)]
+pub async fn export_shopping_list(
+    authorized: Authorized,
+    path: Path<i32>,
+    query: web::Query<ExportQueryParams>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let list_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    let list = match get_full_shopping_list(&pool, list_id, uid).await {
+        Ok(list) => list,
+        Err(e) => return e,
+    };
+
+    let format = query.format.unwrap_or(ExportFormat::Text);
+    let content_type = match format {
+        ExportFormat::Text => "text/plain; charset=utf-8",
+        ExportFormat::Markdown => "text/markdown; charset=utf-8",
+    };
+
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, content_type))
+        .body(export(&list, format))
+}
+
+// #[delete(/shopping_lists/{list_id})]
+pub async fn delete_shopping_list(
+    authorized: Authorized,
+    path: Path<i32>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let list_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = find_own_shopping_list(&pool, list_id, uid).await {
+        return e;
+    }
+
+    match ShoppingList::delete(&pool, list_id).await {
+        Ok(..) => HttpResponse::Ok().body("Succesfully deleted shopping list"),
+        Err(e) => {
+            pretty_error!("Failed to delete shopping list", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/shopping_lists/{list_id}/items)]
+pub async fn add_shopping_list_item(
+    authorized: Authorized,
+    path: Path<i32>,
+    payload: web::Json<ShoppingListItemPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let list_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = find_own_shopping_list(&pool, list_id, uid).await {
+        return e;
+    }
+
+    if let Err(e) = payload.verify() {
+        return e;
+    }
+
+    let item = NewShoppingListItem {
+        ingredient_id: None,
+        name: payload.name.trim().to_string(),
+        quantity: payload.quantity,
+        aisle: payload.aisle.unwrap_or(Aisle::Other),
+        manual: true,
+    };
+
+    match ShoppingList::add_item(&pool, list_id, &item).await {
+        Ok(item_id) => HttpResponse::Ok().body(item_id.to_string()),
+        Err(e) => {
+            pretty_error!("Failed to add item", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[post(/shopping_lists/{list_id}/items/{item_id})]
+pub async fn check_shopping_list_item(
+    authorized: Authorized,
+    path: Path<(i32, i32)>,
+    payload: web::Json<CheckItemPayload>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let (list_id, item_id) = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = find_own_shopping_list(&pool, list_id, uid).await {
+        return e;
+    }
+
+    match ShoppingList::set_checked(&pool, list_id, item_id, payload.checked).await {
+        Ok(true) => HttpResponse::Ok().body("Succesfully updated item"),
+        Ok(false) => {
+            pretty_error!(
+                "No item found".to_string(),
+                format!("Couldn't find item with the id: {}", item_id),
+                error
+            );
+
+            HttpResponse::NotFound().json(error)
+        }
+        Err(e) => {
+            pretty_error!("Failed to update item", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[delete(/shopping_lists/{list_id}/items/{item_id})]
+pub async fn delete_shopping_list_item(
+    authorized: Authorized,
+    path: Path<(i32, i32)>,
+    pool: Data<Pool<Postgres>>,
+) -> impl Responder {
+    let (list_id, item_id) = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if let Err(e) = find_own_shopping_list(&pool, list_id, uid).await {
+        return e;
+    }
+
+    match ShoppingList::delete_item(&pool, list_id, item_id).await {
+        Ok(true) => HttpResponse::Ok().body("Succesfully deleted item"),
+        Ok(false) => {
+            pretty_error!(
+                "No item found".to_string(),
+                format!("Couldn't find item with the id: {}", item_id),
+                error
+            );
+
+            HttpResponse::NotFound().json(error)
+        }
+        Err(e) => {
+            pretty_error!("Failed to delete item", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
@@ -11,7 +11,12 @@ use sqlx::{Pool, Postgres};
 
 use crate::{
     auth::helpers::get_signed_jwt_token,
-    database::models::user::User,
+    database::models::{
+        follow::Follow,
+        notification::{Notification, NotificationKind},
+        user::User,
+    },
+    extractors::auth::Authorized,
     helpers::is_alnum_whitespace_and_ex_chars,
     pretty_error,
     routes::{
@@ -244,3 +249,97 @@ pub async fn login_user(
 
     HttpResponse::Ok().json(jwt_payload)
 }
+
+// #[post(/{user_id}/follow)]
+pub async fn follow_user(
+    authorized: Authorized,
+    db: Data<Pool<Postgres>>,
+    path: Path<i32>,
+) -> impl Responder {
+    let followee_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    if followee_id == uid {
+        pretty_error!("Failed to follow user", "You can't follow yourself", error);
+
+        return HttpResponse::BadRequest().json(error);
+    }
+
+    match User::exists(&db, followee_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            pretty_error!(
+                "No user found".to_string(),
+                format!("Couldn't find user with the id: {}", followee_id),
+                error
+            );
+
+            return HttpResponse::NotFound().json(error);
+        }
+        Err(e) => {
+            pretty_error!("Failed to follow user", e.to_string(), error);
+
+            return HttpResponse::InternalServerError().json(error);
+        }
+    }
+
+    // Following twice is not an error, they are followed either way
+    match Follow::insert(&db, uid, followee_id).await {
+        Ok(followed) => {
+            if followed {
+                // The follow is saved either way, so a missed notification doesn't fail it
+                let _ = Notification::notify(
+                    &db,
+                    followee_id,
+                    NotificationKind::Follow,
+                    uid,
+                    None,
+                    None,
+                )
+                .await;
+            }
+
+            HttpResponse::Ok().body("Succesfully followed user")
+        }
+        Err(e) => {
+            pretty_error!("Failed to follow user", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
+
+// #[delete(/{user_id}/follow)]
+pub async fn unfollow_user(
+    authorized: Authorized,
+    db: Data<Pool<Postgres>>,
+    path: Path<i32>,
+) -> impl Responder {
+    let followee_id = path.into_inner();
+    if let Authorized::Failed(reason) = authorized {
+        pretty_error!("Unauthorized", reason, error);
+
+        return HttpResponse::Unauthorized().json(error);
+    }
+
+    let Authorized::Passed(uid, _username) = authorized else {
+        panic!("Despite the if let authorized::failed, we still panicked");
+    };
+
+    match Follow::delete(&db, uid, followee_id).await {
+        Ok(..) => HttpResponse::Ok().body("Succesfully unfollowed user"),
+        Err(e) => {
+            pretty_error!("Failed to unfollow user", e.to_string(), error);
+
+            HttpResponse::InternalServerError().json(error)
+        }
+    }
+}
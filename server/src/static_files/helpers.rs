@@ -1,8 +1,4 @@
-use std::{
-    io::Write,
-    path::{Path, PathBuf},
-    str::FromStr,
-};
+use std::{io::Write, path::Path};
 
 use actix_multipart::form::tempfile::TempFile;
 use actix_multipart::Multipart;
@@ -10,6 +6,8 @@ use actix_web::web;
 use anyhow::anyhow;
 use futures::{StreamExt, TryStreamExt};
 
+use crate::storage::{key, Storage};
+
 pub async fn save_file(
     mut payload: Multipart,
     file_path: &'static str,
@@ -29,12 +27,14 @@ pub async fn save_file(
     Ok(())
 }
 
-pub fn rename_temp_file(
+// Moves an uploaded temp file into storage under the dir, keeping the uploaded extension.
+// Returns the stored file name
+pub async fn save_temp_file(
+    storage: &dyn Storage,
     temp_file: TempFile,
-    new_path: &str,
+    dir: &str,
     new_file_name: &str,
 ) -> Result<String, anyhow::Error> {
-    let temp_file_path = temp_file.file.path();
     let file_name = temp_file
         .file_name
         .ok_or(anyhow!("No file name in content-disposition header"))?;
@@ -44,10 +44,33 @@ pub fn rename_temp_file(
         .ok_or(anyhow!("File has no extension"))?
         .to_string_lossy();
 
-    let file_name: String = new_file_name.to_owned() + "." + &file_ext;
-    let mut file_path = PathBuf::from_str(new_path)?;
-    file_path.push(sanitize_filename::sanitize(&file_name));
-    std::fs::rename(temp_file_path, file_path)?;
+    let file_name = sanitize_filename::sanitize(new_file_name.to_owned() + "." + &file_ext);
+    let temp_file = temp_file.file;
+    let data = web::block(move || std::fs::read(temp_file.path())).await??;
+    storage.put(&key(dir, &file_name), data).await?;
 
     Ok(file_name)
 }
+
+// Copies a stored file to a new name in the same dir, keeping its extension. Returns the new
+// file name
+pub async fn copy_file(
+    storage: &dyn Storage,
+    dir: &str,
+    file_name: &str,
+    new_file_name: &str,
+) -> Result<String, anyhow::Error> {
+    let data = storage
+        .get(&key(dir, file_name))
+        .await?
+        .ok_or(anyhow!("File does not exist"))?;
+
+    let new_file_name = match Path::new(file_name).extension() {
+        Some(file_ext) => new_file_name.to_owned() + "." + &file_ext.to_string_lossy(),
+        None => new_file_name.to_owned(),
+    };
+    let new_file_name = sanitize_filename::sanitize(new_file_name);
+    storage.put(&key(dir, &new_file_name), data).await?;
+
+    Ok(new_file_name)
+}
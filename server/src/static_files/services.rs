@@ -0,0 +1,64 @@
+use actix_web::{
+    get,
+    web::{Data, Path},
+    HttpResponse, Responder,
+};
+
+use crate::{
+    pretty_error,
+    routes::error::PrettyErrorResponse,
+    storage::{
+        constants::{COOK_PHOTO_DIR, PROFILE_PICTURE_DIR, THUMBNAIL_DIR},
+        key, Storage,
+    },
+};
+
+#[get("/thumbnails/{file_name}")]
+pub async fn get_thumbnail(storage: Data<dyn Storage>, path: Path<String>) -> impl Responder {
+    serve_file(&storage, THUMBNAIL_DIR, &path.into_inner()).await
+}
+
+#[get("/pfp/{file_name}")]
+pub async fn get_profile_picture_file(
+    storage: Data<dyn Storage>,
+    path: Path<String>,
+) -> impl Responder {
+    serve_file(&storage, PROFILE_PICTURE_DIR, &path.into_inner()).await
+}
+
+#[get("/cook_photos/{file_name}")]
+pub async fn get_cook_photo(storage: Data<dyn Storage>, path: Path<String>) -> impl Responder {
+    serve_file(&storage, COOK_PHOTO_DIR, &path.into_inner()).await
+}
+
+async fn serve_file(storage: &Data<dyn Storage>, dir: &str, file_name: &str) -> HttpResponse {
+    let data = match storage.get(&key(dir, file_name)).await {
+        Ok(Some(data)) => data,
+        Ok(None) => {
+            pretty_error!(
+                "File not found".to_string(),
+                format!("Couldn't find the file: {}", file_name),
+                error
+            );
+
+            return HttpResponse::NotFound().json(error);
+        }
+        Err(e) => {
+            pretty_error!("Failed to get file".to_string(), e.to_string(), error);
+
+            return HttpResponse::InternalServerError().json(error);
+        }
+    };
+
+    // We only ever accept jpeg and png uploads
+    let content_type = match std::path::Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+    {
+        Some("png") => mime::IMAGE_PNG,
+        Some("jpg") | Some("jpeg") => mime::IMAGE_JPEG,
+        _ => mime::APPLICATION_OCTET_STREAM,
+    };
+
+    HttpResponse::Ok().content_type(content_type).body(data)
+}
@@ -0,0 +1,4 @@
+pub const THUMBNAIL_DIR: &str = "thumbnails";
+pub const PROFILE_PICTURE_DIR: &str = "profile_pictures";
+pub const RECIPE_DIR: &str = "recipes";
+pub const COOK_PHOTO_DIR: &str = "cook_photos";
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+use actix_web::web;
+use async_trait::async_trait;
+
+use super::Storage;
+
+// Stores everything on the local disk under STORAGE_ROOT, which defaults to the working
+// directory so existing ./thumbnails and ./profile_pictures folders keep working
+pub struct FilesystemStorage {
+    root: PathBuf,
+}
+
+impl FilesystemStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        FilesystemStorage { root: root.into() }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("STORAGE_ROOT").unwrap_or(".".to_string()))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+#[async_trait]
+impl Storage for FilesystemStorage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        let path = self.path(key);
+
+        web::block(move || {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+
+            std::fs::write(path, data)
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let path = self.path(key);
+
+        match web::block(move || std::fs::read(path)).await? {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        let path = self.path(key);
+
+        match web::block(move || std::fs::remove_file(path)).await? {
+            Ok(..) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list(&self, dir: &str) -> anyhow::Result<Vec<String>> {
+        let path = self.path(dir);
+
+        let file_names = web::block(move || -> std::io::Result<Vec<String>> {
+            let mut file_names = Vec::new();
+            for entry in std::fs::read_dir(path)? {
+                let entry = entry?;
+                if entry.file_type()?.is_file() {
+                    file_names.push(entry.file_name().to_string_lossy().to_string());
+                }
+            }
+
+            Ok(file_names)
+        })
+        .await??;
+
+        Ok(file_names)
+    }
+}
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use self::{filesystem::FilesystemStorage, s3::S3Storage};
+
+pub mod constants;
+pub mod filesystem;
+pub mod s3;
+
+// Everything we store outside of postgres (thumbnails, profile pictures and the old recipe
+// files) goes through this, so every server instance can share the same backend.
+// Keys are always "<dir>/<file name>", use `key` to build them
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, key: &str, data: Vec<u8>) -> anyhow::Result<()>;
+
+    // Returns none if there is nothing stored under the key
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>>;
+
+    // Deleting a key that does not exist is not an error
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+
+    // Returns the file names stored under the dir, without the dir prefix
+    async fn list(&self, dir: &str) -> anyhow::Result<Vec<String>>;
+}
+
+pub fn key(dir: &str, file_name: &str) -> String {
+    format!("{}/{}", dir, sanitize_filename::sanitize(file_name))
+}
+
+// Picks the backend from STORAGE_BACKEND, either "filesystem" (the default) or "s3"
+pub fn from_env() -> anyhow::Result<Arc<dyn Storage>> {
+    let backend = std::env::var("STORAGE_BACKEND").unwrap_or("filesystem".to_string());
+
+    match backend.as_str() {
+        "filesystem" => Ok(Arc::new(FilesystemStorage::from_env())),
+        "s3" => Ok(Arc::new(S3Storage::from_env()?)),
+        _ => Err(anyhow::anyhow!("Unknown STORAGE_BACKEND: {}", backend)),
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use object_store::{aws::AmazonS3Builder, path::Path, ObjectStore};
+
+use super::Storage;
+
+// Stores everything in an S3 compatible bucket. For a local MinIO something like
+// S3_ENDPOINT=http://127.0.0.1:9000, S3_BUCKET=cookbook, S3_ACCESS_KEY_ID=minioadmin and
+// S3_SECRET_ACCESS_KEY=minioadmin is enough, http endpoints are allowed
+pub struct S3Storage {
+    store: Box<dyn ObjectStore>,
+}
+
+impl S3Storage {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let bucket = std::env::var("S3_BUCKET").context("S3_BUCKET must be set")?;
+        let access_key_id =
+            std::env::var("S3_ACCESS_KEY_ID").context("S3_ACCESS_KEY_ID must be set")?;
+        let secret_access_key =
+            std::env::var("S3_SECRET_ACCESS_KEY").context("S3_SECRET_ACCESS_KEY must be set")?;
+
+        let mut builder = AmazonS3Builder::new()
+            .with_bucket_name(bucket)
+            .with_region(std::env::var("S3_REGION").unwrap_or("us-east-1".to_string()))
+            .with_access_key_id(access_key_id)
+            .with_secret_access_key(secret_access_key);
+
+        // MinIO and most self hosted stores want path style requests
+        if let Ok(endpoint) = std::env::var("S3_ENDPOINT") {
+            builder = builder
+                .with_allow_http(endpoint.starts_with("http://"))
+                .with_endpoint(endpoint)
+                .with_virtual_hosted_style_request(false);
+        }
+
+        let store = builder.build().context("Failed to configure s3 storage")?;
+
+        Ok(S3Storage {
+            store: Box::new(store),
+        })
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        self.store
+            .put(&Path::from(key), data.into())
+            .await
+            .context(format!("Failed to upload {}", key))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let result = match self.store.get(&Path::from(key)).await {
+            Ok(result) => result,
+            Err(object_store::Error::NotFound { .. }) => return Ok(None),
+            Err(e) => return Err(e).context(format!("Failed to download {}", key)),
+        };
+
+        let data = result.bytes().await?;
+        Ok(Some(data.to_vec()))
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match self.store.delete(&Path::from(key)).await {
+            Ok(..) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e).context(format!("Failed to delete {}", key)),
+        }
+    }
+
+    async fn list(&self, dir: &str) -> anyhow::Result<Vec<String>> {
+        let objects: Vec<_> = self
+            .store
+            .list(Some(&Path::from(dir)))
+            .try_collect()
+            .await
+            .context(format!("Failed to list {}", dir))?;
+
+        Ok(objects
+            .into_iter()
+            .filter_map(|object| object.location.filename().map(|name| name.to_string()))
+            .collect())
+    }
+}